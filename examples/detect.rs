@@ -0,0 +1,61 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            detect.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Detects the timezone through the providers in a configuration
+//                  file, the way the daemon would, and prints it.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+
+//! cargo run --example detect [-- /path/to/config.toml]
+//!
+//! Without a path, the providers in the default configuration are used.
+
+use iwd_auto_timezone::{Config, Detector};
+use std::path::Path;
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let config = match std::env::args().nth(1) {
+        Some(path) => Config::load_valid(Path::new(&path), &[])?,
+        None => Config::default(),
+    };
+    let mut detector = Detector::from_config(&config)?;
+    let detection = detector.detect().await?;
+    print!("{}, from {}", detection.timezone, detection.provider);
+    if let Some(coordinates) = detection.coordinates {
+        print!(" at {}, {}", coordinates.latitude, coordinates.longitude);
+    }
+    println!(" in {}ms", detection.latency.as_millis());
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,136 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            boot.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Time measured from boot, which the wall clock being set can't skew.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BOOT_ID: &str = "/proc/sys/kernel/random/boot_id";
+const UPTIME: &str = "/proc/uptime";
+
+fn boot_id() -> Option<String> {
+    Some(fs::read_to_string(BOOT_ID).ok()?.trim().to_string())
+}
+
+/// Time since boot, including time spent suspended.
+fn uptime() -> Option<Duration> {
+    let contents = fs::read_to_string(UPTIME).ok()?;
+    let seconds: f64 = contents.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(seconds))
+}
+
+/// A moment, as an offset from the start of a particular boot. Unlike a wall
+/// clock timestamp, it's unaffected by the clock being stepped, so the time
+/// elapsed since can be trusted, as long as it's from the current boot.
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct BootTime {
+    pub boot_id: String,
+
+    /// Seconds since that boot.
+    pub uptime: u64,
+}
+
+impl BootTime {
+    pub fn now() -> Option<Self> {
+        Some(Self {
+            boot_id: boot_id()?,
+            uptime: uptime()?.as_secs(),
+        })
+    }
+
+    /// The time since this moment, or None if it was during another boot.
+    pub fn elapsed(&self) -> Option<Duration> {
+        if Some(&self.boot_id) != boot_id().as_ref() {
+            return None;
+        }
+        let since = uptime()?.saturating_sub(Duration::from_secs(self.uptime));
+        Some(since)
+    }
+}
+
+/// The time since a moment recorded as `wall`, in seconds since the Unix
+/// epoch, and as `boot`, if that could be read. The wall clock is only gone
+/// by when the moment was during another boot.
+pub fn since(wall: u64, boot: Option<&BootTime>) -> Duration {
+    match boot.and_then(BootTime::elapsed) {
+        Some(elapsed) => elapsed,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH + Duration::from_secs(wall))
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(time: SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[test]
+    fn the_boot_clock_wins_over_a_wall_clock_that_jumped() {
+        let Some(boot) = BootTime::now() else {
+            return;
+        };
+        // Recorded with the clock at the epoch, and again a day ahead.
+        let day = Duration::from_secs(24 * 60 * 60);
+        for recorded in [0, wall(SystemTime::now() + day)] {
+            assert!(since(recorded, Some(&boot)) < Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn the_wall_clock_is_used_across_boots() {
+        let hour = Duration::from_secs(60 * 60);
+        let recorded = wall(SystemTime::now() - hour);
+        let other = BootTime {
+            boot_id: "another boot".into(),
+            uptime: 0,
+        };
+        for boot in [None, Some(&other)] {
+            let elapsed = since(recorded, boot);
+            assert!(
+                elapsed >= hour && elapsed < hour + Duration::from_secs(5)
+            );
+        }
+    }
+
+    #[test]
+    fn a_wall_clock_in_the_future_counts_as_just_now() {
+        let recorded = wall(SystemTime::now() + Duration::from_secs(60 * 60));
+        assert_eq!(Duration::ZERO, since(recorded, None));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
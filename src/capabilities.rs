@@ -0,0 +1,199 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            capabilities.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     What this build and configuration of the daemon supports.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::monitor::{self, Backend};
+use crate::provider;
+use dbus::arg::{PropMap, RefArg, Variant};
+
+/// The cargo features this binary was built with.
+pub const FEATURES: &[&str] = &[
+    #[cfg(feature = "backend-iwd")]
+    "backend-iwd",
+    #[cfg(feature = "backend-wpa-supplicant")]
+    "backend-wpa-supplicant",
+    #[cfg(feature = "backend-modem-manager")]
+    "backend-modem-manager",
+    #[cfg(feature = "backend-netlink")]
+    "backend-netlink",
+    #[cfg(feature = "provider-http")]
+    "provider-http",
+    #[cfg(feature = "provider-exec")]
+    "provider-exec",
+    #[cfg(feature = "provider-file")]
+    "provider-file",
+    #[cfg(feature = "provider-gpsd")]
+    "provider-gpsd",
+    #[cfg(feature = "tzf")]
+    "tzf",
+    #[cfg(feature = "faults")]
+    "faults",
+];
+
+fn insert<T: RefArg + 'static>(map: &mut PropMap, key: &str, value: T) {
+    map.insert(key.to_string(), Variant(Box::new(value)));
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+/// What GetCapabilities reports. Keys are only ever added to it, never
+/// removed or changed in meaning, so that whatever reads it keeps working.
+pub struct Capabilities {
+    backend: Backend,
+
+    /// What sets the timezone: timedated, or /etc/localtime directly.
+    setter: &'static str,
+
+    /// The names of the configured providers, never their URLs or keys.
+    providers: Vec<String>,
+    toggles: Vec<(&'static str, bool)>,
+}
+
+impl Capabilities {
+    pub fn new(
+        config: &Config,
+        backend: Backend,
+        setter: &'static str,
+        providers: Vec<String>,
+        confirm: bool,
+    ) -> Self {
+        let toggles = vec![
+            ("PublicIpCheck", config.public_ip_url.is_some()),
+            ("ExportLocation", config.export_location),
+            ("InteractiveAuth", config.interactive_auth),
+            ("RequireActiveSession", config.require_active_session),
+            ("DnsTrigger", config.dns_trigger),
+            ("DeferWhileScanning", config.defer_while_scanning),
+            ("RejectCountryMismatch", config.reject_country_mismatch),
+            ("ApplyReconfirm", config.apply_reconfirm),
+            ("Confirm", confirm),
+        ];
+        Self {
+            backend,
+            setter,
+            providers,
+            toggles,
+        }
+    }
+
+    pub fn to_dict(&self) -> PropMap {
+        let mut map = PropMap::new();
+        insert(&mut map, "Version", env!("CARGO_PKG_VERSION").to_string());
+        insert(&mut map, "Features", strings(FEATURES));
+        insert(&mut map, "Backends", strings(monitor::COMPILED));
+        insert(&mut map, "ProviderTypes", strings(provider::COMPILED));
+        insert(&mut map, "Backend", self.backend.to_string());
+        insert(&mut map, "Setter", self.setter.to_string());
+        insert(&mut map, "Providers", self.providers.clone());
+        insert(&mut map, "CountryCheck", self.backend.reads_country());
+        for (name, enabled) in &self.toggles {
+            insert(&mut map, name, *enabled);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "backend-netlink")]
+    use dbus::arg;
+
+    #[cfg(feature = "backend-netlink")]
+    fn strings_at(map: &PropMap, key: &str) -> Vec<String> {
+        arg::cast::<Vec<String>>(&map[key].0).unwrap().clone()
+    }
+
+    #[test]
+    fn features_match_the_build() {
+        let built = [
+            ("backend-iwd", cfg!(feature = "backend-iwd")),
+            (
+                "backend-wpa-supplicant",
+                cfg!(feature = "backend-wpa-supplicant"),
+            ),
+            (
+                "backend-modem-manager",
+                cfg!(feature = "backend-modem-manager"),
+            ),
+            ("backend-netlink", cfg!(feature = "backend-netlink")),
+            ("provider-http", cfg!(feature = "provider-http")),
+            ("provider-exec", cfg!(feature = "provider-exec")),
+            ("provider-file", cfg!(feature = "provider-file")),
+            ("provider-gpsd", cfg!(feature = "provider-gpsd")),
+            ("tzf", cfg!(feature = "tzf")),
+            ("faults", cfg!(feature = "faults")),
+        ];
+        let expected: Vec<&str> = built
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(expected, FEATURES);
+    }
+
+    #[cfg(feature = "backend-netlink")]
+    #[test]
+    fn map_describes_the_build_and_configuration() {
+        let config = Config {
+            dns_trigger: true,
+            ..Config::default()
+        };
+        let capabilities = Capabilities::new(
+            &config,
+            Backend::Netlink,
+            "timedated",
+            vec!["ipapi".to_string()],
+            false,
+        );
+        let map = capabilities.to_dict();
+
+        assert_eq!(Some(env!("CARGO_PKG_VERSION")), map["Version"].0.as_str());
+        assert_eq!(strings(FEATURES), strings_at(&map, "Features"));
+        assert_eq!(strings(monitor::COMPILED), strings_at(&map, "Backends"));
+        assert_eq!(
+            strings(provider::COMPILED),
+            strings_at(&map, "ProviderTypes")
+        );
+        assert_eq!(Some("netlink"), map["Backend"].0.as_str());
+        assert_eq!(Some("timedated"), map["Setter"].0.as_str());
+        assert_eq!(vec!["ipapi"], strings_at(&map, "Providers"));
+        assert_eq!(Some(&false), arg::cast::<bool>(&map["CountryCheck"].0));
+        assert_eq!(Some(&true), arg::cast::<bool>(&map["DnsTrigger"].0));
+        assert_eq!(Some(&false), arg::cast::<bool>(&map["Confirm"].0));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
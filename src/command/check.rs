@@ -0,0 +1,72 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            check.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Validate the configuration without starting the daemon.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::provider::{Context, ProviderChain};
+use std::path::Path;
+use std::process::ExitCode;
+
+pub async fn run(
+    path: &Path,
+    overrides: &[String],
+    probe: bool,
+) -> Result<ExitCode, anyhow::Error> {
+    let config = match Config::load(path, overrides) {
+        Ok(config) => config,
+        Err(error) => {
+            println!("{:#}", error);
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let mut problems = config.validate();
+    if probe && problems.is_empty() {
+        let providers = ProviderChain::new(&config)?;
+        for (name, result) in providers.probe(&Context::default()).await {
+            match result {
+                Ok(timezone) => println!("{}: {}", name, timezone),
+                Err(error) => problems.push(format!("{}: {:#}", name, error)),
+            }
+        }
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    match problems.is_empty() {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::FAILURE),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
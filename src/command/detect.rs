@@ -0,0 +1,76 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            detect.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     The detect subcommand.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::detector::Detector;
+use serde::Serialize;
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(Serialize)]
+struct Report {
+    timezone: String,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    provider: String,
+    latency_ms: u64,
+}
+
+/// Detect the timezone and print it, without setting it.
+pub async fn run(
+    path: &Path,
+    overrides: &[String],
+    json: bool,
+) -> Result<ExitCode, anyhow::Error> {
+    let config = Config::load_valid(path, overrides)?;
+    let detection = Detector::from_config(&config)?.detect().await?;
+    let report = Report {
+        timezone: detection.timezone,
+        latitude: detection.coordinates.map(|point| point.latitude),
+        longitude: detection.coordinates.map(|point| point.longitude),
+        provider: detection.provider,
+        latency_ms: detection.latency.as_millis() as u64,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "{} (from {} in {}ms)",
+            report.timezone, report.provider, report.latency_ms
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+///////////////////////////////////////////////////////////////////////////////
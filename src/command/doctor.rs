@@ -0,0 +1,373 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            doctor.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Implementation of the doctor subcommand, which checks for common
+//                  setup problems.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::daemon;
+use crate::environment::{self, Environment, WithoutTimedated};
+use crate::jitter::Jitter;
+use crate::localtime::LocaltimeSetter;
+use crate::monitor::Backend;
+use crate::provider::{Context, ProviderChain};
+use crate::setter::{Authorization, TimezoneSetter};
+use crate::zone;
+use dbus::nonblock::SyncConnection;
+use serde::Serialize;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Verdict {
+    Pass,
+
+    /// Something is off, but the daemon can still work.
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: &'static str,
+    verdict: Verdict,
+    detail: String,
+
+    /// What to do about a warning or failure.
+    hint: Option<&'static str>,
+}
+
+#[derive(Default)]
+struct Report(Vec<Check>);
+
+impl Report {
+    fn pass(&mut self, name: &'static str, detail: String) {
+        self.push(name, Verdict::Pass, detail, None);
+    }
+
+    fn warn(
+        &mut self,
+        name: &'static str,
+        detail: String,
+        hint: &'static str,
+    ) {
+        self.push(name, Verdict::Warn, detail, Some(hint));
+    }
+
+    fn fail(
+        &mut self,
+        name: &'static str,
+        detail: String,
+        hint: &'static str,
+    ) {
+        self.push(name, Verdict::Fail, detail, Some(hint));
+    }
+
+    fn push(
+        &mut self,
+        name: &'static str,
+        verdict: Verdict,
+        detail: String,
+        hint: Option<&'static str>,
+    ) {
+        self.0.push(Check {
+            name,
+            verdict,
+            detail,
+            hint,
+        });
+    }
+
+    fn print(&self, json: bool) -> Result<(), anyhow::Error> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&self.0)?);
+            return Ok(());
+        }
+        for check in &self.0 {
+            let label = match check.verdict {
+                Verdict::Pass => "PASS",
+                Verdict::Warn => "WARN",
+                Verdict::Fail => "FAIL",
+            };
+            println!("{} {}: {}", label, check.name, check.detail);
+            if let Some(hint) = check.hint {
+                println!("     {}", hint);
+            }
+        }
+        Ok(())
+    }
+
+    fn exit_code(&self) -> ExitCode {
+        match self.0.iter().any(|check| Verdict::Fail == check.verdict) {
+            true => ExitCode::FAILURE,
+            false => ExitCode::SUCCESS,
+        }
+    }
+}
+
+/// Check everything the daemon depends on, printing what passed and what
+/// didn't. Exits 1 if anything critical failed.
+pub async fn run(
+    path: &Path,
+    overrides: &[String],
+    json: bool,
+) -> Result<ExitCode, anyhow::Error> {
+    let mut report = Report::default();
+    diagnose(&mut report, path, overrides).await;
+    report.print(json)?;
+    Ok(report.exit_code())
+}
+
+async fn diagnose(report: &mut Report, path: &Path, overrides: &[String]) {
+    let config = match Config::load_valid(path, overrides) {
+        Ok(config) => {
+            report.pass("config", path.display().to_string());
+            Some(config)
+        }
+        Err(error) => {
+            report.fail(
+                "config",
+                format!("{:#}", error),
+                "Run the check subcommand for details",
+            );
+            None
+        }
+    };
+
+    let problems = zone::sanity_check();
+    match zone::database() {
+        Ok(_) if !problems.is_empty() => report.warn(
+            "zoneinfo",
+            problems.join("; "),
+            "Install the complete tz database, or set zoneinfo_directory",
+        ),
+        Ok(0) => report.warn(
+            "zoneinfo",
+            "tzdata.zi not found".into(),
+            "Aliases like Asia/Calcutta won't be recognized",
+        ),
+        Ok(links) => report.pass("zoneinfo", format!("{} aliases", links)),
+        Err(error) => report.fail(
+            "zoneinfo",
+            format!("{:#}", error),
+            "Install the tz database (usually the tzdata package)",
+        ),
+    }
+
+    let (system_bus, missing) =
+        match environment::probe(daemon::connect()).await {
+            Environment::Timedated(bus) => (Some(bus), None),
+            Environment::Missing { bus, reason } => (bus, Some(reason)),
+        };
+    let localtime = config.as_ref().is_some_and(|config| {
+        WithoutTimedated::Localtime == config.without_timedated
+    });
+    match (&system_bus, &missing) {
+        (Some(_), _) => {
+            report.pass("bus", "Connected to the system bus".into())
+        }
+        (None, Some(reason)) if localtime => report.warn(
+            "bus",
+            reason.clone(),
+            "Watching the routing table for connections instead",
+        ),
+        (None, reason) => {
+            report.fail(
+                "bus",
+                reason.clone().unwrap_or_default(),
+                "Check that dbus-daemon (or dbus-broker) is running, or set \
+                 without_timedated = \"localtime\"",
+            );
+            return;
+        }
+    }
+    let Some(config) = config else {
+        return;
+    };
+
+    let Some(system_bus) = system_bus else {
+        match Backend::without_bus(config.backend) {
+            Ok(backend) => report.pass("backend", backend.to_string()),
+            Err(error) => report.fail(
+                "backend",
+                format!("{:#}", error),
+                "Set backend = \"netlink\"",
+            ),
+        }
+        check_localtime(report, missing.unwrap_or_default());
+        return check_providers(report, &config).await;
+    };
+
+    match Backend::probe(config.backend, system_bus.clone()).await {
+        Ok(backend) => {
+            report.pass("backend", backend.to_string());
+            match backend.stations(system_bus.clone()).await {
+                Ok(Some(stations)) if stations.is_empty() => report.warn(
+                    "stations",
+                    "No stations found".into(),
+                    "Check that the wireless adapter is present and powered",
+                ),
+                Ok(Some(stations)) => {
+                    let stations: Vec<String> = stations
+                        .into_iter()
+                        .map(|(path, state)| format!("{} ({})", path, state))
+                        .collect();
+                    report.pass("stations", stations.join(", "));
+                }
+                Ok(None) => {}
+                Err(error) => report.warn(
+                    "stations",
+                    format!("{:#}", error),
+                    "Check that the connection manager is working",
+                ),
+            }
+        }
+        Err(error) => report.fail(
+            "backend",
+            format!("{:#}", error),
+            "Start iwd or wpa_supplicant, or set backend = \"netlink\"",
+        ),
+    }
+
+    if let Some(reason) = missing {
+        match WithoutTimedated::Localtime == config.without_timedated {
+            true => check_localtime(report, reason),
+            false => report.fail(
+                "timedated",
+                reason,
+                "Install systemd-timedated, or set without_timedated = \
+                 \"localtime\"",
+            ),
+        }
+    } else {
+        check_timedated(report, system_bus, config.interactive_auth).await;
+    }
+    check_providers(report, &config).await;
+}
+
+async fn check_timedated(
+    report: &mut Report,
+    system_bus: Arc<SyncConnection>,
+    interactive_auth: bool,
+) {
+    let setter =
+        TimezoneSetter::new(system_bus, interactive_auth, Jitter::new(0.0));
+    match setter.current_timezone().await {
+        Ok(current) => {
+            report.pass("timedated", format!("Timezone is {}", current))
+        }
+        Err(error) => report.fail(
+            "timedated",
+            format!("{:#}", error),
+            "Check that systemd-timedated is installed",
+        ),
+    }
+    match setter.authorization().await {
+        Ok(Authorization::Authorized) => {
+            report.pass("polkit", "Allowed to set the timezone".into())
+        }
+        Ok(Authorization::Challenge) if interactive_auth => {
+            report.pass("polkit", "Allowed, after a prompt".into())
+        }
+        Ok(Authorization::Challenge) => report.fail(
+            "polkit",
+            "Setting the timezone would need a prompt".into(),
+            "Add a polkit rule for org.freedesktop.timedate1.set-timezone, \
+             or set interactive_auth = true",
+        ),
+        Ok(Authorization::Denied) => report.fail(
+            "polkit",
+            "Not allowed to set the timezone".into(),
+            "Add a polkit rule for org.freedesktop.timedate1.set-timezone",
+        ),
+        Err(error) => report.warn(
+            "polkit",
+            format!("{:#}", error),
+            "Couldn't check ahead of time; setting the timezone may fail",
+        ),
+    }
+}
+
+/// Where there's no timedated, check that /etc/localtime can be read.
+fn check_localtime(report: &mut Report, reason: String) {
+    report.warn(
+        "timedated",
+        reason,
+        "Writing /etc/localtime directly instead (without_timedated = \
+         \"localtime\")",
+    );
+    match LocaltimeSetter.current_timezone() {
+        Ok(current) => {
+            report.pass("localtime", format!("Timezone is {}", current))
+        }
+        Err(error) => report.fail(
+            "localtime",
+            format!("{:#}", error),
+            "/etc/localtime should be a link into the tz database",
+        ),
+    }
+}
+
+async fn check_providers(report: &mut Report, config: &Config) {
+    let providers = match ProviderChain::new(config) {
+        Ok(providers) => providers,
+        Err(error) => {
+            return report.fail(
+                "providers",
+                format!("{:#}", error),
+                "Check the providers in the configuration",
+            )
+        }
+    };
+    let results = providers.probe(&Context::default()).await;
+    let working = results.iter().filter(|(_, result)| result.is_ok()).count();
+    for (name, result) in &results {
+        match result {
+            Ok(timezone) => {
+                report.pass("provider", format!("{}: {}", name, timezone))
+            }
+            Err(error) if 0 == working => report.fail(
+                "provider",
+                format!("{}: {:#}", name, error),
+                "Check network access, and the provider's URL or command",
+            ),
+            Err(error) => report.warn(
+                "provider",
+                format!("{}: {:#}", name, error),
+                "A later provider answered, but this one costs a timeout",
+            ),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
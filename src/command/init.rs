@@ -0,0 +1,328 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            init.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     The init subcommand: probe the system and write a starting configuration.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::daemon;
+use crate::jitter::Jitter;
+use crate::monitor::{Backend, BackendConfig};
+#[cfg(feature = "provider-http")]
+use crate::provider::{Context, ProviderChain};
+use crate::setter::TimezoneSetter;
+use crate::state::write_atomically;
+use anyhow::{anyhow, Context as _};
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+/// Providers that work without any further setup, by name, with the table
+/// that configures each.
+const PROVIDERS: &[(&str, &str)] = &[
+    #[cfg(feature = "provider-http")]
+    ("ipapi", "type = \"http\"\npreset = \"ipapi\""),
+    #[cfg(feature = "provider-http")]
+    ("ip-api", "type = \"http\"\npreset = \"ip-api\""),
+    #[cfg(feature = "provider-http")]
+    ("ipinfo", "type = \"http\"\npreset = \"ipinfo\""),
+    #[cfg(feature = "provider-gpsd")]
+    ("gpsd", "type = \"gpsd\""),
+];
+
+/// The answers the configuration is written from.
+struct Answers {
+    provider: &'static str,
+    backend: Option<Backend>,
+    interactive_auth: bool,
+}
+
+/// Ask `question` on the terminal, and return the answer.
+fn ask(question: &str) -> Result<String, anyhow::Error> {
+    print!("{} ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if 0 == io::stdin().lock().read_line(&mut answer)? {
+        return Err(anyhow!("No answer"));
+    }
+    Ok(answer.trim().to_string())
+}
+
+/// Ask a question that defaults to no.
+fn ask_yes_no(question: &str) -> Result<bool, anyhow::Error> {
+    loop {
+        match ask(&format!("{} [y/N]", question))?.to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "" | "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n."),
+        }
+    }
+}
+
+fn ask_provider(default: &'static str) -> Result<&'static str, anyhow::Error> {
+    let names: Vec<&'static str> =
+        PROVIDERS.iter().map(|(name, _)| *name).collect();
+    let question = format!("Provider ({})? [{}]", names.join(", "), default);
+    loop {
+        let answer = ask(&question)?;
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match names.iter().find(|name| **name == answer) {
+            Some(name) => return Ok(name),
+            None => println!("Please choose one of {}.", names.join(", ")),
+        }
+    }
+}
+
+/// Report what's present on the system, returning the backend that would be
+/// used, if any.
+async fn probe() -> Option<Backend> {
+    #[cfg(feature = "provider-http")]
+    if let Ok(providers) = ProviderChain::new(&Config::default()) {
+        for (name, result) in providers.probe(&Context::default()).await {
+            match result {
+                Ok(timezone) => {
+                    println!("{} is reachable: {}", name, timezone)
+                }
+                Err(error) => {
+                    println!("{} isn't reachable: {:#}", name, error)
+                }
+            }
+        }
+    }
+
+    let system_bus = match daemon::connect() {
+        Ok(system_bus) => system_bus,
+        Err(error) => {
+            println!("Couldn't connect to the system bus: {:#}", error);
+            return None;
+        }
+    };
+    let setter =
+        TimezoneSetter::new(system_bus.clone(), false, Jitter::new(0.0));
+    match setter.current_timezone().await {
+        Ok(current) => println!("timedated responds, timezone is {}", current),
+        Err(error) => println!("timedated doesn't respond: {:#}", error),
+    }
+
+    match Backend::probe(BackendConfig::Auto, system_bus).await {
+        Ok(backend) => {
+            println!("Found backend: {}", backend);
+            Some(backend)
+        }
+        Err(error) => {
+            println!("No backend found: {:#}", error);
+            None
+        }
+    }
+}
+
+/// The configuration file for `answers`, with comments.
+fn render(answers: &Answers) -> String {
+    let backend = match answers.backend {
+        Some(backend) => format!("backend = \"{}\"", backend),
+        None => "# backend = \"auto\"".to_string(),
+    };
+    let provider = PROVIDERS
+        .iter()
+        .find(|(name, _)| *name == answers.provider)
+        .map(|(_, table)| *table)
+        .unwrap_or_default();
+    format!(
+        "\
+# Written by iwd-auto-timezone init. Every key is optional; the README
+# describes the rest.
+
+# What reports connections: iwd, wpa_supplicant or netlink. By default, the
+# first of them that's running is used.
+{}
+
+# Allow polkit to prompt for authorization to set the timezone, e.g. when
+# running in a user session.
+interactive_auth = {}
+
+# After switching timezones, don't switch to a different one for this long.
+min_dwell = \"10m\"
+
+# Sources of timezone information, tried in order until one succeeds.
+[[providers]]
+{}
+",
+        backend, answers.interactive_auth, provider
+    )
+}
+
+/// Write a configuration file to `path`, from what's found on the system and,
+/// on a terminal without `defaults`, the answers to a few questions. An
+/// existing file is only replaced with `force`.
+pub async fn run(
+    path: &Path,
+    force: bool,
+    defaults: bool,
+) -> Result<ExitCode, anyhow::Error> {
+    if path.exists() && !force {
+        return Err(anyhow!(
+            "{} already exists, pass --force to replace it",
+            path.display()
+        ));
+    }
+    let Some((provider, _)) = PROVIDERS.first() else {
+        return Err(anyhow!(
+            "No provider that works without further setup is compiled in"
+        ));
+    };
+
+    let backend = probe().await;
+    let answers = if defaults || !io::stdin().is_terminal() {
+        Answers {
+            provider,
+            backend,
+            interactive_auth: false,
+        }
+    } else {
+        let provider = ask_provider(provider)?;
+        let backend = match backend {
+            Some(backend) => ask_yes_no(&format!(
+                "Always use {}, instead of looking at startup?",
+                backend
+            ))?
+            .then_some(backend),
+            None => None,
+        };
+        let interactive_auth = ask_yes_no(
+            "Allow polkit to prompt for authorization (for user sessions)?",
+        )?;
+        Answers {
+            provider,
+            backend,
+            interactive_auth,
+        }
+    };
+
+    // Whatever is written has to be something the daemon will accept.
+    let contents = render(&answers);
+    let config: Config = toml::from_str(&contents)
+        .context("The generated configuration doesn't parse")?;
+    let problems = config.validate();
+    if !problems.is_empty() {
+        return Err(anyhow!(
+            "The generated configuration is invalid:\n{}",
+            problems.join("\n")
+        ));
+    }
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).with_context(|| {
+            format!("Couldn't create {}", directory.display())
+        })?;
+    }
+    write_atomically(path, &contents)?;
+    println!("Wrote {}", path.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::BackendConfig;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    const BACKENDS: &[Option<Backend>] = &[
+        None,
+        #[cfg(feature = "backend-iwd")]
+        Some(Backend::Iwd),
+        #[cfg(feature = "backend-wpa-supplicant")]
+        Some(Backend::WpaSupplicant),
+        #[cfg(feature = "backend-modem-manager")]
+        Some(Backend::ModemManager),
+        #[cfg(feature = "backend-netlink")]
+        Some(Backend::Netlink),
+    ];
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "init-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn every_generated_config_round_trips() {
+        let directory = directory("round-trip");
+        let path = directory.join("config.toml");
+        for (provider, _) in PROVIDERS {
+            for backend in BACKENDS {
+                for interactive_auth in [false, true] {
+                    let answers = Answers {
+                        provider,
+                        backend: *backend,
+                        interactive_auth,
+                    };
+                    fs::write(&path, render(&answers)).unwrap();
+                    let config = Config::load(&path, &[]).unwrap();
+                    assert!(config.validate().is_empty());
+                    assert_eq!(interactive_auth, config.interactive_auth);
+                    assert_eq!(1, config.providers.len());
+                    assert_eq!(Duration::from_secs(600), config.min_dwell);
+                    match backend {
+                        Some(backend) => assert_eq!(
+                            serde_json::json!(backend.to_string()),
+                            serde_json::to_value(config.backend).unwrap()
+                        ),
+                        None => {
+                            assert_eq!(BackendConfig::Auto, config.backend)
+                        }
+                    }
+                }
+            }
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn an_existing_file_is_kept_without_force() {
+        let directory = directory("existing");
+        let path = directory.join("config.toml");
+        fs::write(&path, "# Mine\n").unwrap();
+        let error = run(&path, false, true).await.unwrap_err();
+        assert!(error.to_string().contains("--force"), "{}", error);
+        assert_eq!("# Mine\n", fs::read_to_string(&path).unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
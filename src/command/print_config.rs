@@ -0,0 +1,84 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            print_config.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Print the effective configuration, annotated with the
+//                  source of each setting.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use serde_json::json;
+use std::path::Path;
+use std::process::ExitCode;
+use toml::{Table, Value};
+
+pub fn run(
+    path: &Path,
+    overrides: &[String],
+    json: bool,
+) -> Result<ExitCode, anyhow::Error> {
+    let resolved = Config::resolve(path, overrides)?;
+    let table = resolved.redacted();
+
+    if json {
+        let settings: serde_json::Map<_, _> = table
+            .iter()
+            .map(|(key, value)| {
+                let source = resolved.sources.get(key);
+                (key.clone(), json!({ "value": value, "source": source }))
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&settings)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Tables have to come after all the plain values, or the values would be
+    // read back as belonging to the table.
+    let (tables, values): (Vec<_>, Vec<_>) =
+        table.iter().partition(|(_, value)| is_table(value));
+    for (key, value) in values.into_iter().chain(tables) {
+        let mut setting = Table::new();
+        setting.insert(key.clone(), value.clone());
+        if let Some(source) = resolved.sources.get(key) {
+            println!("# source: {}", source);
+        }
+        println!("{}", toml::to_string(&setting)?);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn is_table(value: &Value) -> bool {
+    match value {
+        Value::Table(_) => true,
+        Value::Array(array) => array.iter().any(|item| item.is_table()),
+        _ => false,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
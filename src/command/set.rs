@@ -0,0 +1,74 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            set.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Set the timezone by hand, the same way the daemon does.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::daemon;
+use crate::exit;
+use crate::jitter::Jitter;
+use crate::setter::{Denied, Outcome, TimezoneSetter};
+use crate::statistics::Statistics;
+use crate::zone;
+use std::path::Path;
+use std::process::ExitCode;
+
+pub async fn run(
+    path: &Path,
+    overrides: &[String],
+    timezone: &str,
+) -> Result<ExitCode, anyhow::Error> {
+    let config = Config::load_valid(path, overrides)?;
+    let setter = TimezoneSetter::new(
+        daemon::connect()?,
+        config.interactive_auth,
+        Jitter::new(config.jitter),
+    );
+    let result = setter.apply(timezone, &mut Statistics::default()).await;
+    let timezone = zone::canonicalize(timezone);
+    match result {
+        Ok(Outcome::Set) => {
+            println!("set {}", timezone);
+            Ok(ExitCode::SUCCESS)
+        }
+        Ok(Outcome::Unchanged) => {
+            println!("already current {}", timezone);
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(error) if error.downcast_ref::<Denied>().is_some() => {
+            println!("denied {}: {:#}", timezone, error);
+            Ok(ExitCode::from(exit::DENIED))
+        }
+        Err(error) => Err(error),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,160 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            verify.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Check that the current timezone matches what the providers
+//                  detect, for monitoring.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::daemon;
+use crate::detector::Detector;
+use crate::environment;
+use crate::exit;
+use crate::jitter::Jitter;
+use crate::zone;
+use serde::Serialize;
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Verdict {
+    Match,
+    Mismatch,
+    Unknown,
+}
+
+#[derive(Serialize)]
+struct Report {
+    verdict: Verdict,
+    current: Option<String>,
+    detected: Option<String>,
+    current_offset: Option<i32>,
+    detected_offset: Option<i32>,
+    error: Option<String>,
+}
+
+impl Report {
+    fn exit_code(&self) -> ExitCode {
+        match self.verdict {
+            Verdict::Match => ExitCode::SUCCESS,
+            Verdict::Mismatch => ExitCode::from(exit::FAILURE),
+            Verdict::Unknown => ExitCode::from(exit::UNKNOWN),
+        }
+    }
+
+    fn print(&self, json: bool) -> Result<(), anyhow::Error> {
+        if json {
+            println!("{}", serde_json::to_string_pretty(self)?);
+            return Ok(());
+        }
+
+        let unknown = "-".to_string();
+        let label = match self.verdict {
+            Verdict::Match => "OK",
+            Verdict::Mismatch => "MISMATCH",
+            Verdict::Unknown => {
+                println!(
+                    "UNKNOWN {}",
+                    self.error.as_ref().unwrap_or(&unknown)
+                );
+                return Ok(());
+            }
+        };
+        println!(
+            "{} current={} detected={}",
+            label,
+            self.current.as_ref().unwrap_or(&unknown),
+            self.detected.as_ref().unwrap_or(&unknown)
+        );
+        Ok(())
+    }
+}
+
+/// Detect the timezone and compare it to the one the system is set to. Exits
+/// 0 if they match, 1 if they don't and 2 if either couldn't be determined.
+pub async fn run(
+    path: &Path,
+    overrides: &[String],
+    allow_offset_equal: bool,
+    json: bool,
+) -> Result<ExitCode, anyhow::Error> {
+    let mut report = Report {
+        verdict: Verdict::Unknown,
+        current: None,
+        detected: None,
+        current_offset: None,
+        detected_offset: None,
+        error: None,
+    };
+    if let Err(error) = compare(&mut report, path, overrides).await {
+        report.error = Some(format!("{:#}", error));
+    } else if let (Some(current), Some(detected)) =
+        (&report.current, &report.detected)
+    {
+        report.current_offset = zone::utc_offset(current);
+        report.detected_offset = zone::utc_offset(detected);
+        let same_offset = report.current_offset.is_some()
+            && report.current_offset == report.detected_offset;
+        let same = zone::is_same(current, detected);
+        report.verdict = match same || (allow_offset_equal && same_offset) {
+            true => Verdict::Match,
+            false => Verdict::Mismatch,
+        };
+    }
+
+    report.print(json)?;
+    Ok(report.exit_code())
+}
+
+async fn compare(
+    report: &mut Report,
+    path: &Path,
+    overrides: &[String],
+) -> Result<(), anyhow::Error> {
+    let config = Config::load_valid(path, overrides)?;
+    let detection = Detector::from_config(&config)?.detect().await?;
+    report.detected = Some(detection.timezone);
+
+    // Read the zone the way the daemon would set it, so that this works
+    // without timedated too.
+    let (_, setter) = environment::prepare(
+        daemon::connect(),
+        config.without_timedated,
+        false,
+        &Jitter::new(0.0),
+    )
+    .await?;
+    let current = setter.current_timezone().await?;
+    report.current = Some(zone::canonicalize(&current));
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////
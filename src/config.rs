@@ -0,0 +1,525 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            config.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Daemon configuration, read from a TOML file.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::environment::WithoutTimedated;
+use crate::hook::HookConfig;
+use crate::monitor::BackendConfig;
+use crate::provider::ProviderConfig;
+use crate::quiet::QuietHours;
+use crate::status;
+use crate::zone;
+use anyhow::{anyhow, Context};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml::{Table, Value};
+
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/iwd-auto-timezone/config.toml";
+
+/// Environment variables beginning with this prefix override the setting of
+/// the same name, e.g. IWD_AUTO_TIMEZONE_INTERACTIVE_AUTH=true.
+const ENV_PREFIX: &str = "IWD_AUTO_TIMEZONE_";
+
+/// Settings whose values must never be displayed.
+const SECRETS: &[&str] = &["api_key", "token", "password"];
+
+/// Where the effective value of a setting came from, in increasing order of
+/// precedence.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Default,
+    File,
+    Env,
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File => write!(f, "file"),
+            Self::Env => write!(f, "env"),
+            Self::Flag => write!(f, "flag"),
+        }
+    }
+}
+
+/// The effective configuration, along with where each top-level setting
+/// came from.
+pub struct Resolved {
+    pub config: Config,
+    pub table: Table,
+    pub sources: BTreeMap<String, Source>,
+}
+
+impl Resolved {
+    /// The effective configuration with secrets replaced by a placeholder,
+    /// suitable for display.
+    pub fn redacted(&self) -> Table {
+        let mut table = self.table.clone();
+        redact_table(&mut table);
+        table
+    }
+}
+
+fn redact_table(table: &mut Table) {
+    for (key, value) in table.iter_mut() {
+        if SECRETS.contains(&key.as_str()) {
+            *value = Value::String("<redacted>".to_string());
+        } else {
+            redact(value);
+        }
+    }
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Table(table) => redact_table(table),
+        Value::Array(array) => array.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Replaces a detected timezone with another.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rewrite {
+    /// A timezone, or a prefix of one followed by *.
+    pub from: String,
+    pub to: String,
+}
+
+/// Interpret the value of an override as TOML, so that e.g. "true" is a
+/// boolean. Anything that doesn't parse is taken to be a string.
+fn parse_override(value: &str) -> Value {
+    format!("value = {}", value)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+/// The daemon's configuration. Settings are added in minor versions, so
+/// other crates start from Config::default() or a file rather than listing
+/// every field.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct Config {
+    /// Endpoint returning the public IP address of this host as plain text.
+    /// When set, the geo-IP lookup is skipped if the address has not changed
+    /// since the last lookup.
+    pub public_ip_url: Option<String>,
+
+    /// Directory where state is persisted across restarts.
+    pub state_directory: PathBuf,
+
+    /// File kept up to date with what the daemon is doing, for health checks.
+    pub status_file: PathBuf,
+
+    /// A socket accepting "update", "update --force" and "status", one per
+    /// line, for scripts that don't speak D-Bus. Not opened by default.
+    pub control_socket: Option<PathBuf>,
+
+    /// The group, by name or number, allowed to use control_socket besides
+    /// the daemon's own.
+    pub control_socket_group: Option<String>,
+
+    /// Sources of timezone information, consulted in order.
+    pub providers: Vec<ProviderConfig>,
+
+    /// The connection manager to monitor.
+    pub backend: BackendConfig,
+
+    /// The tz database, for systems that keep it somewhere unusual.
+    pub zoneinfo_directory: PathBuf,
+
+    /// Rules replacing detected timezones before they're checked against the
+    /// lists below, the first match winning.
+    pub rewrites: Vec<Rewrite>,
+
+    /// If not empty, the only timezones the daemon will switch to.
+    pub allowed_timezones: Vec<String>,
+
+    /// Skip a provider for demote_cooldown after it has failed this many
+    /// times in a row. Zero means never.
+    pub demote_after_failures: u32,
+
+    #[serde(with = "humantime_serde")]
+    pub demote_cooldown: Duration,
+
+    /// Timezones (or prefixes of them, like Etc/*) that are never accepted
+    /// from a provider. The next provider is tried instead.
+    pub denied_timezones: Vec<String>,
+
+    /// The longest a detection may take, from the public IP lookup through
+    /// the last provider, before it's abandoned.
+    #[serde(with = "humantime_serde")]
+    pub cycle_timeout: Duration,
+
+    /// Don't start a detection while the station that connected is scanning,
+    /// as traffic during a scan is slow on some chipsets. Only iwd reports
+    /// scanning.
+    pub defer_while_scanning: bool,
+
+    /// With defer_while_scanning, the longest to wait for a scan to finish.
+    #[serde(with = "humantime_serde")]
+    pub max_scan_wait: Duration,
+
+    /// Refuse a timezone that isn't used in the country the access point
+    /// advertises, rather than only warning about it. Only wpa_supplicant
+    /// reads the country, so with other backends this does nothing.
+    pub reject_country_mismatch: bool,
+
+    /// After switching timezones, how long to wait before switching to
+    /// another one. Switching back to the previous timezone is always allowed.
+    #[serde(with = "humantime_serde")]
+    pub min_dwell: Duration,
+
+    /// How long to wait after detecting a timezone before setting it. The
+    /// change is dropped if the connection goes away in the meantime.
+    #[serde(with = "humantime_serde")]
+    pub apply_delay: Duration,
+
+    /// How far apply_delay and retry delays may be randomly stretched or
+    /// shrunk, as a fraction. Zero makes them exact.
+    pub jitter: f64,
+
+    /// Detect the timezone again once apply_delay has passed, and only set
+    /// it if the answer hasn't changed.
+    pub apply_reconfirm: bool,
+
+    /// A window of the day, e.g. "01:00-05:00" in the timezone the system is
+    /// set to, during which changes wait for the window to end, unless
+    /// forced.
+    pub quiet_hours: Option<QuietHours>,
+
+    /// Commands run, in order, each time the timezone is set to something
+    /// new.
+    pub hooks: Vec<HookConfig>,
+
+    /// Whether a critical hook failing rolls the change back.
+    pub transactional_hooks: bool,
+
+    /// What to do where there's no system bus or timedated, e.g. in a
+    /// container: fail, or write /etc/localtime directly.
+    pub without_timedated: WithoutTimedated,
+
+    /// The most times the timezone will be set in any 24 hours, unless
+    /// forced. Zero means no limit.
+    pub max_changes_per_day: usize,
+
+    /// After this many detections in a row on a network have failed
+    /// completely, stop trying on that network for negative_cache_for. Zero
+    /// means never give up.
+    pub negative_cache_after: u32,
+    #[serde(with = "humantime_serde")]
+    pub negative_cache_for: Duration,
+
+    /// Also update when systemd-resolved learns DNS servers for a link that
+    /// had none, which on some networks is long after association.
+    pub dns_trigger: bool,
+
+    /// With dns_trigger, the name of the only link to watch, e.g. wlan0.
+    /// All links are watched if it's not set.
+    pub dns_link: Option<String>,
+
+    /// How many times the backend's signal stream may be re-subscribed in a
+    /// minute before the daemon gives up and exits.
+    pub max_resubscribes: usize,
+
+    /// Publish the location reported by providers on the control object, for
+    /// other programs to use.
+    pub export_location: bool,
+
+    /// How many detection attempts GetRecentDetections remembers. They're
+    /// only ever kept in memory.
+    pub recent_detections: usize,
+
+    /// Allow polkit to prompt for authorization to set the timezone, e.g.
+    /// when running in a user session.
+    pub interactive_auth: bool,
+
+    /// Only set the timezone while the logind session the daemon runs in is
+    /// active, so a session switched away from doesn't change it for
+    /// whoever is using the seat.
+    pub require_active_session: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            public_ip_url: None,
+            state_directory: PathBuf::from("/var/lib/iwd-auto-timezone"),
+            status_file: PathBuf::from(status::DEFAULT_STATUS_FILE),
+            control_socket: None,
+            control_socket_group: None,
+            providers: ProviderConfig::defaults(),
+            backend: BackendConfig::default(),
+            demote_after_failures: 3,
+            demote_cooldown: Duration::from_secs(300),
+            zoneinfo_directory: PathBuf::from(zone::DEFAULT_ZONEINFO),
+            rewrites: Vec::new(),
+            allowed_timezones: Vec::new(),
+            denied_timezones: Vec::new(),
+            cycle_timeout: Duration::from_secs(180),
+            defer_while_scanning: false,
+            max_scan_wait: Duration::from_secs(10),
+            reject_country_mismatch: false,
+            min_dwell: Duration::from_secs(600),
+            apply_delay: Duration::ZERO,
+            jitter: 0.1,
+            apply_reconfirm: false,
+            quiet_hours: None,
+            hooks: Vec::new(),
+            transactional_hooks: false,
+            without_timedated: WithoutTimedated::default(),
+            max_changes_per_day: 8,
+            negative_cache_after: 3,
+            negative_cache_for: Duration::from_secs(24 * 60 * 60),
+            dns_trigger: false,
+            dns_link: None,
+            max_resubscribes: 5,
+            export_location: false,
+            recent_detections: 50,
+            interactive_auth: false,
+            require_active_session: false,
+        }
+    }
+}
+
+impl Config {
+    /// Read the file at `path`. A missing file is not an error--it just
+    /// doesn't contribute any settings.
+    fn read_file(path: &Path) -> Result<Table, anyhow::Error> {
+        match fs::read_to_string(path) {
+            Ok(contents) => contents
+                .parse()
+                .with_context(|| format!("Couldn't parse {}", path.display())),
+            Err(error) if ErrorKind::NotFound == error.kind() => {
+                Ok(Table::new())
+            }
+            Err(error) => Err(error)
+                .with_context(|| format!("Couldn't read {}", path.display())),
+        }
+    }
+
+    /// Work out the effective configuration from the defaults, the file at
+    /// `path`, the environment and `flags` (of the form KEY=VALUE), each
+    /// taking precedence over the last.
+    pub fn resolve(
+        path: &Path,
+        flags: &[String],
+    ) -> Result<Resolved, anyhow::Error> {
+        let mut table = Table::try_from(Self::default())?;
+        let mut sources: BTreeMap<String, Source> = table
+            .keys()
+            .map(|key| (key.clone(), Source::Default))
+            .collect();
+        let mut apply = |key: String, value: Value, source: Source| {
+            sources.insert(key.clone(), source);
+            table.insert(key, value);
+        };
+
+        for (key, value) in Self::read_file(path)? {
+            apply(key, value, Source::File);
+        }
+        for (name, value) in env::vars() {
+            if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+                apply(key.to_lowercase(), parse_override(&value), Source::Env);
+            }
+        }
+        for flag in flags {
+            let (key, value) = flag.split_once('=').ok_or_else(|| {
+                anyhow!("Expected KEY=VALUE, not {:?}", flag)
+            })?;
+            apply(key.to_string(), parse_override(value), Source::Flag);
+        }
+
+        let config = table.clone().try_into().with_context(|| {
+            format!("Invalid configuration (from {})", path.display())
+        })?;
+        Ok(Resolved {
+            config,
+            table,
+            sources,
+        })
+    }
+
+    /// Read the configuration. The tz database is looked for in
+    /// zoneinfo_directory from then on.
+    pub fn load(path: &Path, flags: &[String]) -> Result<Self, anyhow::Error> {
+        let mut config = Self::resolve(path, flags)?.config;
+        zone::set_directory(&config.zoneinfo_directory);
+        config.drop_bad_denied_timezones();
+        Ok(config)
+    }
+
+    /// Drop the entries of denied_timezones that aren't zones or zone
+    /// patterns, warning about each. They couldn't deny anything, so they're
+    /// no reason not to start.
+    fn drop_bad_denied_timezones(&mut self) {
+        let mut index = 0;
+        self.denied_timezones.retain(|pattern| {
+            let result = zone::Pattern::new(pattern).validate();
+            if let Err(error) = &result {
+                warn!("Ignoring denied_timezones[{}]: {}", index, error);
+            }
+            index += 1;
+            result.is_ok()
+        });
+    }
+
+    /// Check the constraints that a successful parse doesn't guarantee.
+    /// Returns a description of each problem found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.providers.is_empty() {
+            problems.push("No providers are configured".to_string());
+        }
+        for (index, provider) in self.providers.iter().enumerate() {
+            for problem in provider.validate() {
+                problems.push(format!("providers[{}]: {}", index, problem));
+            }
+        }
+
+        for (index, rule) in self.rewrites.iter().enumerate() {
+            if let Err(error) = zone::Pattern::new(&rule.from).validate() {
+                problems.push(format!("rewrites[{}].from: {}", index, error));
+            }
+            if let Err(error) = zone::validate(&zone::canonicalize(&rule.to)) {
+                problems.push(format!("rewrites[{}].to: {}", index, error));
+            }
+        }
+
+        for (index, name) in self.allowed_timezones.iter().enumerate() {
+            if let Err(error) = zone::validate(&zone::canonicalize(name)) {
+                problems
+                    .push(format!("allowed_timezones[{}]: {}", index, error));
+            }
+        }
+
+        for (index, hook) in self.hooks.iter().enumerate() {
+            for problem in hook.validate() {
+                problems.push(format!("hooks[{}]: {}", index, problem));
+            }
+        }
+
+        if self.cycle_timeout.is_zero() {
+            problems.push("cycle_timeout must not be zero".into());
+        }
+        if !(0.0..1.0).contains(&self.jitter) {
+            problems.push("jitter must be at least 0 and less than 1".into());
+        }
+
+        #[cfg(feature = "provider-http")]
+        if let Some(url) = &self.public_ip_url {
+            if let Err(error) = reqwest::Url::parse(url) {
+                problems.push(format!("public_ip_url: {}", error));
+            }
+        }
+
+        if self.state_directory.exists() && !self.state_directory.is_dir() {
+            problems.push(format!(
+                "state_directory: {} is not a directory",
+                self.state_directory.display()
+            ));
+        }
+        problems
+    }
+
+    /// Read and validate the configuration, as the daemon does at startup.
+    pub fn load_valid(
+        path: &Path,
+        flags: &[String],
+    ) -> Result<Self, anyhow::Error> {
+        let config = Self::load(path, flags)?;
+        let problems = config.validate();
+        if !problems.is_empty() {
+            return Err(anyhow!(
+                "Invalid configuration in {}:\n{}",
+                path.display(),
+                problems.join("\n")
+            ));
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Load `contents` as if from the configuration file `name`.
+    fn load(name: &str, contents: &str) -> Result<Config, anyhow::Error> {
+        let path = env::temp_dir().join(format!(
+            "config-{}-{}.toml",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, contents).unwrap();
+        let config = Config::load(&path, &[]);
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn bad_denied_timezones_are_dropped() {
+        let config = load(
+            "dropped",
+            "denied_timezones = \
+             [\"UTC\", \"Etc/*\", \"not a zone\", \"Europe/Nowhere\"]\n",
+        )
+        .unwrap();
+        assert_eq!(vec!["UTC", "Etc/*"], config.denied_timezones);
+        assert!(!config
+            .validate()
+            .iter()
+            .any(|problem| problem.starts_with("denied_timezones")));
+    }
+
+    #[test]
+    fn denied_timezones_of_the_wrong_type_are_errors() {
+        assert!(load("string", "denied_timezones = \"UTC\"\n").is_err());
+        assert!(load("mixed", "denied_timezones = [\"UTC\", 7]\n").is_err());
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,101 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            confirm.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Asks before each change, when running in a terminal.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use anyhow::anyhow;
+use log::info;
+use std::io::{self, IsTerminal, Write};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines, Stdin};
+
+pub struct Confirmer {
+    timeout: Duration,
+
+    /// The user answered "always", so there's no need to ask again.
+    always: bool,
+    lines: Lines<BufReader<Stdin>>,
+}
+
+impl Confirmer {
+    /// Fails unless stdin is a terminal, since there'd be no one to answer.
+    pub fn new(timeout: Duration) -> Result<Self, anyhow::Error> {
+        if !io::stdin().is_terminal() {
+            return Err(anyhow!("--confirm requires stdin to be a terminal"));
+        }
+        Ok(Self {
+            timeout,
+            always: false,
+            lines: BufReader::new(tokio::io::stdin()).lines(),
+        })
+    }
+
+    /// Ask whether to switch to `timezone`. No answer within the timeout is
+    /// taken to mean no.
+    pub async fn confirm(
+        &mut self,
+        timezone: &str,
+        current: Option<&str>,
+    ) -> bool {
+        if self.always {
+            return true;
+        }
+        print!(
+            "Detected {} (currently {}). Apply? [y/N/always] ",
+            timezone,
+            current.unwrap_or("unknown")
+        );
+        let _ = io::stdout().flush();
+
+        let answer =
+            match tokio::time::timeout(self.timeout, self.lines.next_line())
+                .await
+            {
+                Ok(Ok(Some(answer))) => answer,
+                Ok(_) => String::new(),
+                Err(_) => {
+                    println!();
+                    info!("No answer after {:?}", self.timeout);
+                    String::new()
+                }
+            };
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => true,
+            "always" => {
+                self.always = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,146 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            connectivity.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Groups the triggers that happen while connected.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+
+/// A stretch of connectivity, from a connection while there wasn't one to
+/// the next disconnection. Roaming between access points doesn't end it.
+/// Everything recorded within it carries its ID, so that the changes made on
+/// one leg of a trip can be told from noise within it.
+#[derive(Clone, Debug)]
+pub struct Session {
+    /// Short and random, only unique enough to tell sessions apart in logs.
+    pub id: String,
+
+    /// When it started, for display.
+    pub started: SystemTime,
+
+    /// The same, on the monotonic clock, so that how long it lasted isn't
+    /// thrown off by the clock being set meanwhile.
+    began: Instant,
+}
+
+impl Session {
+    pub fn start() -> Self {
+        Self {
+            id: format!("{:08x}", fastrand::u32(..)),
+            started: SystemTime::now(),
+            began: Instant::now(),
+        }
+    }
+
+    /// How long the session has lasted so far.
+    pub fn lasted(&self) -> Duration {
+        self.began.elapsed()
+    }
+}
+
+/// The connectivity session in progress, if connected.
+#[derive(Default)]
+pub struct Sessions(Option<Session>);
+
+impl Sessions {
+    /// The session a connection belongs to, and whether it started with it.
+    pub fn connected(&mut self) -> (Session, bool) {
+        match &self.0 {
+            Some(session) => (session.clone(), false),
+            None => {
+                let session = Session::start();
+                self.0 = Some(session.clone());
+                (session, true)
+            }
+        }
+    }
+
+    /// End the session in progress, returning it, if there was one.
+    pub fn disconnected(&mut self) -> Option<Session> {
+        self.0.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECADE: Duration = Duration::from_secs(10 * 365 * 24 * 60 * 60);
+
+    #[test]
+    fn how_long_a_session_lasted_ignores_the_wall_clock() {
+        // As if the clock was stepped by a decade either way since.
+        for started in [SystemTime::now() - DECADE, SystemTime::now() + DECADE]
+        {
+            let session = Session {
+                started,
+                ..Session::start()
+            };
+            assert!(session.lasted() < Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn connections_within_a_session_share_its_id() {
+        let mut sessions = Sessions::default();
+        let (first, started) = sessions.connected();
+        assert!(started);
+        // Roaming, and reconnecting to the same network, reconnect without
+        // a disconnection in between.
+        for _ in 0..3 {
+            let (session, started) = sessions.connected();
+            assert!(!started);
+            assert_eq!(first.id, session.id);
+            assert_eq!(first.started, session.started);
+        }
+        assert_eq!(Some(first.id), sessions.disconnected().map(|s| s.id));
+    }
+
+    #[test]
+    fn a_disconnection_ends_the_session() {
+        let mut sessions = Sessions::default();
+        assert!(sessions.disconnected().is_none());
+        let ids: Vec<String> = (0..10)
+            .map(|_| {
+                let (session, started) = sessions.connected();
+                assert!(started);
+                assert!(sessions.disconnected().is_some());
+                assert!(sessions.disconnected().is_none());
+                session.id
+            })
+            .collect();
+        // IDs are random, but ten in a row sharing one would be remarkable.
+        assert!(ids.iter().any(|id| *id != ids[0]));
+        assert!(ids.iter().all(|id| 8 == id.len()));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
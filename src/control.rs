@@ -0,0 +1,368 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            control.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     D-Bus interface for controlling the daemon, and for other
+//                  programs to learn what it knows.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::capabilities::Capabilities;
+use crate::history::{Attempt, CountryCheck, History, Outcome, Ruling};
+use crate::location::Location;
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::channel::{MatchingReceiver, Sender};
+use dbus::message::MatchRule;
+use dbus::nonblock::SyncConnection;
+use dbus::Message;
+use dbus_crossroads::{Crossroads, IfaceBuilder, MethodErr};
+use log::warn;
+use serde_json::{json, Map, Value};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const NAME: &str = "io.github.AmateurECE.IwdAutoTimezone1";
+pub const PATH: &str = "/io/github/AmateurECE/IwdAutoTimezone1";
+pub const INTERFACE: &str = NAME;
+
+/// The data behind the control object.
+struct Exported {
+    /// The last location a provider reported, and when, in seconds since the
+    /// Unix epoch. Only kept with export_location.
+    location: Option<(Location, u64)>,
+
+    /// The last recent_detections detection attempts.
+    history: History,
+
+    /// Set once the backend has been chosen.
+    capabilities: Option<Capabilities>,
+}
+
+fn seconds_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn insert<T: RefArg + 'static>(map: &mut PropMap, key: &str, value: T) {
+    map.insert(key.to_string(), Variant(Box::new(value)));
+}
+
+/// An attempt as GetRecentDetections returns it. Keys without a value, e.g.
+/// provider when none answered, are left out.
+fn to_dict(attempt: &Attempt) -> PropMap {
+    let mut map = PropMap::new();
+    insert(&mut map, "Timestamp", seconds_since_epoch(attempt.time));
+    insert(&mut map, "Sequence", attempt.sequence);
+    if let Some(source) = attempt.source {
+        insert(&mut map, "Source", source.to_string());
+    }
+    if let Some(session) = &attempt.session {
+        insert(&mut map, "Session", session.clone());
+    }
+    if let Some(ssid) = &attempt.ssid {
+        insert(&mut map, "SSID", ssid.clone());
+    }
+    if let Some(provider) = &attempt.provider {
+        insert(&mut map, "Provider", provider.clone());
+    }
+    insert(&mut map, "LatencyMs", attempt.latency.as_millis() as u64);
+    match attempt.outcome {
+        Outcome::Timezone(ref timezone) => {
+            insert(&mut map, "Timezone", timezone.clone())
+        }
+        Outcome::Error(class) => insert(&mut map, "Error", class.to_string()),
+    }
+    match &attempt.country {
+        Some(CountryCheck::Unavailable) => {
+            insert(&mut map, "CountryCheck", "unavailable".to_string())
+        }
+        Some(CountryCheck::Advertised { country, matches }) => {
+            insert(&mut map, "Country", country.clone());
+            insert(&mut map, "CountryMatches", *matches);
+        }
+        None => {}
+    }
+    if let Some(coordinates) = attempt.coordinates {
+        insert(&mut map, "Latitude", coordinates.latitude);
+        insert(&mut map, "Longitude", coordinates.longitude);
+    }
+    if !attempt.guards.is_empty() {
+        let guards: Vec<(String, String)> = attempt
+            .guards
+            .iter()
+            .map(|(guard, ruling)| (guard.to_string(), ruling.to_string()))
+            .collect();
+        insert(&mut map, "Guards", guards);
+    }
+    insert(&mut map, "Applied", attempt.applied);
+    insert(&mut map, "RolledBack", attempt.rolled_back);
+    map
+}
+
+/// An attempt as the control socket's status command returns it: the same
+/// as GetRecentDetections, with the keys in snake_case.
+fn to_json(attempt: &Attempt) -> Value {
+    let mut map = Map::new();
+    map.insert("timestamp".into(), json!(seconds_since_epoch(attempt.time)));
+    map.insert("sequence".into(), json!(attempt.sequence));
+    if let Some(source) = attempt.source {
+        map.insert("source".into(), json!(source.to_string()));
+    }
+    if let Some(session) = &attempt.session {
+        map.insert("session".into(), json!(session));
+    }
+    if let Some(ssid) = &attempt.ssid {
+        map.insert("ssid".into(), json!(ssid));
+    }
+    if let Some(provider) = &attempt.provider {
+        map.insert("provider".into(), json!(provider));
+    }
+    map.insert(
+        "latency_ms".into(),
+        json!(attempt.latency.as_millis() as u64),
+    );
+    match attempt.outcome {
+        Outcome::Timezone(ref timezone) => {
+            map.insert("timezone".into(), json!(timezone))
+        }
+        Outcome::Error(class) => map.insert("error".into(), json!(class)),
+    };
+    match &attempt.country {
+        Some(CountryCheck::Unavailable) => {
+            map.insert("country_check".into(), json!("unavailable"));
+        }
+        Some(CountryCheck::Advertised { country, matches }) => {
+            map.insert("country".into(), json!(country));
+            map.insert("country_matches".into(), json!(matches));
+        }
+        None => {}
+    }
+    if let Some(coordinates) = attempt.coordinates {
+        map.insert("latitude".into(), json!(coordinates.latitude));
+        map.insert("longitude".into(), json!(coordinates.longitude));
+    }
+    if !attempt.guards.is_empty() {
+        let guards: Vec<(&str, String)> = attempt
+            .guards
+            .iter()
+            .map(|(guard, ruling)| (*guard, ruling.to_string()))
+            .collect();
+        map.insert("guards".into(), json!(guards));
+    }
+    map.insert("applied".into(), json!(attempt.applied));
+    map.insert("rolled_back".into(), json!(attempt.rolled_back));
+    Value::Object(map)
+}
+
+fn location(exported: &Exported) -> Result<&(Location, u64), MethodErr> {
+    exported
+        .location
+        .as_ref()
+        .ok_or_else(|| MethodErr::failed("No location is known"))
+}
+
+pub struct Control {
+    /// None where there's no system bus, in which case nothing is exported.
+    connection: Option<Arc<SyncConnection>>,
+    crossroads: Arc<Mutex<Crossroads>>,
+    export_location: bool,
+}
+
+impl Control {
+    /// Export the control object, and claim the well-known name for it. Not
+    /// being allowed the name (e.g. because the bus policy isn't installed)
+    /// is not fatal.
+    pub async fn new(
+        connection: Option<Arc<SyncConnection>>,
+        export_location: bool,
+        recent_detections: usize,
+    ) -> Self {
+        let mut crossroads = Crossroads::new();
+        let interface = crossroads.register(
+            INTERFACE,
+            |b: &mut IfaceBuilder<Exported>| {
+                b.method(
+                    "GetRecentDetections",
+                    (),
+                    ("detections",),
+                    |_, exported, _: ()| {
+                        let detections: Vec<PropMap> =
+                            exported.history.iter().map(to_dict).collect();
+                        Ok((detections,))
+                    },
+                );
+                b.method(
+                    "GetCapabilities",
+                    (),
+                    ("capabilities",),
+                    |_, exported, _: ()| {
+                        let capabilities =
+                            exported.capabilities.as_ref().ok_or_else(
+                                || MethodErr::failed("Still starting up"),
+                            )?;
+                        Ok((capabilities.to_dict(),))
+                    },
+                );
+                if !export_location {
+                    return;
+                }
+                b.property("Latitude").emits_changed_false().get(
+                    |_, exported| {
+                        Ok(location(exported)?.0.coordinates.latitude)
+                    },
+                );
+                b.property("Longitude").emits_changed_false().get(
+                    |_, exported| {
+                        Ok(location(exported)?.0.coordinates.longitude)
+                    },
+                );
+                b.property("Accuracy").emits_changed_false().get(
+                    |_, exported| {
+                        location(exported)?.0.accuracy.ok_or_else(|| {
+                            MethodErr::failed("The accuracy is not known")
+                        })
+                    },
+                );
+                b.property("LocationTimestamp")
+                    .emits_changed_false()
+                    .get(|_, exported| Ok(location(exported)?.1));
+                b.signal::<(f64, f64), _>(
+                    "LocationUpdated",
+                    ("latitude", "longitude"),
+                );
+            },
+        );
+        let exported = Exported {
+            location: None,
+            history: History::new(recent_detections),
+            capabilities: None,
+        };
+        crossroads.insert(PATH, &[interface], exported);
+
+        let crossroads = Arc::new(Mutex::new(crossroads));
+        if let Some(connection) = &connection {
+            let handler = crossroads.clone();
+            connection.start_receive(
+                MatchRule::new_method_call(),
+                Box::new(move |message, connection| {
+                    let mut crossroads = handler.lock().unwrap();
+                    let _ = crossroads.handle_message(message, connection);
+                    true
+                }),
+            );
+            if let Err(error) =
+                connection.request_name(NAME, false, true, false).await
+            {
+                warn!("Couldn't claim {} on the system bus: {}", NAME, error);
+            }
+        }
+
+        Self {
+            connection,
+            crossroads,
+            export_location,
+        }
+    }
+
+    /// Publish the location reported with the latest detection. The location
+    /// is only ever kept in memory.
+    pub fn set_location(&self, location: Location) {
+        if !self.export_location {
+            return;
+        }
+        let timestamp = seconds_since_epoch(SystemTime::now());
+        let mut crossroads = self.crossroads.lock().unwrap();
+        if let Some(exported) = crossroads.data_mut::<Exported>(&PATH.into()) {
+            exported.location = Some((location, timestamp));
+        }
+
+        let coordinates = location.coordinates;
+        let signal = Message::signal(
+            &PATH.into(),
+            &INTERFACE.into(),
+            &"LocationUpdated".into(),
+        )
+        .append2(coordinates.latitude, coordinates.longitude);
+        if let Some(connection) = &self.connection {
+            let _ = connection.send(signal);
+        }
+    }
+
+    pub fn set_capabilities(&self, capabilities: Capabilities) {
+        let mut crossroads = self.crossroads.lock().unwrap();
+        if let Some(exported) = crossroads.data_mut::<Exported>(&PATH.into()) {
+            exported.capabilities = Some(capabilities);
+        }
+    }
+
+    /// The detection attempts GetRecentDetections would return, for the
+    /// control socket.
+    pub fn recent_detections(&self) -> Vec<Value> {
+        let mut crossroads = self.crossroads.lock().unwrap();
+        crossroads
+            .data_mut::<Exported>(&PATH.into())
+            .map(|exported| exported.history.iter().map(to_json).collect())
+            .unwrap_or_default()
+    }
+
+    fn history(&self, update: impl FnOnce(&mut History)) {
+        let mut crossroads = self.crossroads.lock().unwrap();
+        if let Some(exported) = crossroads.data_mut::<Exported>(&PATH.into()) {
+            update(&mut exported.history);
+        }
+    }
+
+    /// Add a detection attempt to those returned by GetRecentDetections. Its
+    /// location is dropped unless export_location is set.
+    pub fn record(&self, mut attempt: Attempt) {
+        if !self.export_location {
+            attempt.coordinates = None;
+        }
+        self.history(|history| history.push(attempt));
+    }
+
+    /// Record that the timezone detected for the trigger `sequence` was set.
+    pub fn applied(&self, sequence: u64) {
+        self.history(|history| history.applied(sequence));
+    }
+
+    /// Record that the timezone detected for the trigger `sequence` was set
+    /// back.
+    pub fn rolled_back(&self, sequence: u64) {
+        self.history(|history| history.rolled_back(sequence));
+    }
+
+    /// Record what `guard` made of the timezone detected for the trigger
+    /// `sequence`.
+    pub fn ruled(&self, sequence: u64, guard: &'static str, ruling: Ruling) {
+        self.history(|history| history.ruled(sequence, guard, ruling));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,2023 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            daemon.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     The daemon, which updates the system timezone whenever a
+//                  network connection comes up.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::boot::{self, BootTime};
+use crate::capabilities::Capabilities;
+use crate::config::Config;
+use crate::confirm::Confirmer;
+use crate::connectivity::Sessions;
+use crate::control::Control;
+use crate::dns;
+use crate::egress::{self, Egress, Salt};
+use crate::environment;
+use crate::exit;
+use crate::fault::{self, Fault};
+use crate::guard::{self, Guard};
+use crate::history::{self, Attempt, CountryCheck};
+use crate::hook::Hooks;
+use crate::jitter::Jitter;
+use crate::monitor::{self, Backend, Event};
+use crate::ntp;
+use crate::provider::{
+    self, Context, Detection, Failure, Handle, Report, Worker,
+};
+use crate::queue::{Applied, Source, Trigger, UpdateQueue};
+use crate::quiet::QuietHours;
+use crate::session;
+use crate::setter::{Denied, Outcome, Setter};
+use crate::signals;
+use crate::skip::SkipReason;
+use crate::socket::{self, Command, Request};
+use crate::state::{self, Shutdown, State, Unreachable};
+use crate::statistics::Statistics;
+use crate::status::{self, Activity, Status, StatusFile};
+use crate::throttle::{Throttle, Verdict};
+use crate::zone::{self, Pattern};
+use anyhow::anyhow;
+use chrono::Utc;
+use dbus::nonblock::SyncConnection;
+use dbus_tokio::connection;
+use log::{debug, info, trace, warn};
+use serde_json::json;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::error::Elapsed;
+use tokio::time::Instant;
+
+/// How far back max_changes_per_day looks.
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long to wait before trying again to set a timezone that timedated
+/// failed to set, before jitter.
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What failed updates are tracked as, for the throttle.
+const UPDATE: &str = "update";
+
+/// A timezone that's been decided on, but not yet applied.
+#[derive(Clone)]
+struct Change {
+    timezone: String,
+
+    /// The salted hash of the public IP address it was decided at.
+    public_ip_hash: Option<String>,
+    force: bool,
+
+    /// The sequence number of the trigger it was decided on.
+    sequence: u64,
+}
+
+/// A change that timedated failed to make, waiting to be tried again.
+struct Unapplied {
+    change: Change,
+    attempts: u32,
+    deadline: Instant,
+
+    /// The system's timezone when the change failed. If it's something else
+    /// by the next attempt, someone has set it by hand, and the change is
+    /// dropped.
+    timezone_then: Option<String>,
+}
+
+/// A change waiting out apply_delay.
+struct Pending {
+    change: Change,
+    context: Context,
+    source: Option<Source>,
+    deadline: Instant,
+
+    /// Whether it's waiting for quiet_hours to end, in which case it's kept
+    /// across a restart.
+    quiet: bool,
+}
+
+/// The providers' answer, before the daemon has taken it in.
+struct Consulted {
+    time: SystemTime,
+    latency: Duration,
+    report: Result<Report, anyhow::Error>,
+    country: Option<CountryCheck>,
+}
+
+/// Where an update's timezone came from.
+enum Answer {
+    /// The public IP address hadn't changed, so the current one still holds.
+    Reused(String),
+    Consulted(Box<Consulted>),
+}
+
+/// What an update's lookups found.
+struct Findings {
+    public_ip_hash: Option<String>,
+    egress: Egress,
+    answer: Answer,
+}
+
+/// A detection that has finished, with what it was for.
+enum Landed {
+    Update {
+        trigger: Trigger,
+
+        /// How long the station was waited on to finish scanning.
+        scanned: Option<Duration>,
+        findings: Result<Findings, Elapsed>,
+    },
+    Reconfirm {
+        pending: Pending,
+        consulted: Result<Box<Consulted>, Elapsed>,
+    },
+}
+
+/// A detection in progress. It owns everything it needs, so the loop keeps
+/// handling events while it runs, and dropping it abandons it.
+type Flight = Pin<Box<dyn Future<Output = Landed> + Send>>;
+
+/// The I/O behind a detection, which runs off the daemon's state: waiting
+/// for scans, looking up the public IP address, and consulting the
+/// providers.
+struct Lookups {
+    backend: Option<(Backend, Arc<SyncConnection>)>,
+    #[cfg(feature = "provider-http")]
+    http: reqwest::Client,
+    #[cfg(feature = "provider-http")]
+    public_ip_url: Option<String>,
+    providers: Handle,
+}
+
+impl Lookups {
+    /// Wait up to `limit` for the station in `context` to finish scanning,
+    /// returning how long that took, if it did.
+    async fn wait_for_scan(
+        &self,
+        context: &Context,
+        limit: Duration,
+    ) -> Option<Duration> {
+        let (Some((backend, connection)), Some(station)) =
+            (&self.backend, &context.station)
+        else {
+            return None;
+        };
+        let result = backend
+            .wait_for_scan(connection.clone(), station, limit)
+            .await;
+        match result {
+            Ok(waited) => waited,
+            Err(error) => {
+                debug!(
+                    "Couldn't tell if {} is scanning: {:#}",
+                    station, error
+                );
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "provider-http"))]
+    async fn public_ip(&self) -> Option<String> {
+        None
+    }
+
+    #[cfg(feature = "provider-http")]
+    async fn public_ip(&self) -> Option<String> {
+        let url = self.public_ip_url.as_ref()?;
+        let response = match self.http.get(url).send().await {
+            Ok(response) => response.text().await,
+            Err(error) => Err(error),
+        };
+        match response {
+            Ok(address) => Some(address.trim().to_string()),
+            Err(error) => {
+                warn!("Couldn't determine public IP address: {}", error);
+                None
+            }
+        }
+    }
+
+    /// Compare `timezone` with the country advertised by the access point
+    /// the station in `context` is connected to. None if the access point
+    /// doesn't say, or the timezone isn't any country's.
+    async fn country(
+        &self,
+        context: &Context,
+        timezone: &str,
+    ) -> Option<CountryCheck> {
+        let (Some((backend, connection)), Some(station)) =
+            (&self.backend, &context.station)
+        else {
+            return None;
+        };
+        let countries = zone::countries(timezone);
+        if countries.is_empty() {
+            return None;
+        }
+        if !backend.reads_country() {
+            return Some(CountryCheck::Unavailable);
+        }
+        let country = match backend.country(connection.clone(), station).await
+        {
+            Ok(country) => country?,
+            Err(error) => {
+                debug!(
+                    "Couldn't read the country of {}: {:#}",
+                    station, error
+                );
+                return None;
+            }
+        };
+        Some(CountryCheck::Advertised {
+            matches: countries.contains(&country),
+            country,
+        })
+    }
+
+    /// Consult the providers, and compare the timezone with the access
+    /// point's country.
+    async fn consult(&self, context: &Context) -> Box<Consulted> {
+        let time = SystemTime::now();
+        let started = Instant::now();
+        let report = self.providers.detect(context).await;
+        let latency = started.elapsed();
+        let country = match &report {
+            Ok(Report {
+                result: Ok(detection),
+                ..
+            }) => self.country(context, &detection.timezone).await,
+            _ => None,
+        };
+        Box::new(Consulted {
+            time,
+            latency,
+            report,
+            country,
+        })
+    }
+
+    /// Find the timezone, consulting the providers unless the public IP
+    /// address is the same as it was when `reusable` was detected.
+    async fn find(
+        &self,
+        context: &Context,
+        salt: &Salt,
+        public_ip_hash: Option<String>,
+        reusable: Option<String>,
+    ) -> Findings {
+        fault::delay(Fault::ConnectivityDelay).await;
+        // Only the hash is kept, so the address itself is gone by the end of
+        // the comparison.
+        let current = self.public_ip().await.map(|address| {
+            trace!("Public IP address: {}", address);
+            salt.hash(&address)
+        });
+        let egress =
+            egress::compare(current.as_deref(), public_ip_hash.as_deref());
+        let answer = match (egress, reusable) {
+            (Egress::Unchanged, Some(timezone)) => Answer::Reused(timezone),
+            _ => Answer::Consulted(self.consult(context).await),
+        };
+        Findings {
+            public_ip_hash: current,
+            egress,
+            answer,
+        }
+    }
+}
+
+struct ZoneClient {
+    setter: Setter,
+
+    /// With require_active_session, the bus to ask logind on.
+    session: Option<Arc<SyncConnection>>,
+
+    /// The backend reporting connections, and the bus to ask it about a
+    /// station on. Only the daemon has one.
+    backend: Option<(Backend, Arc<SyncConnection>)>,
+
+    /// With defer_while_scanning, the longest to wait for a scan.
+    max_scan_wait: Option<Duration>,
+    reject_country_mismatch: bool,
+    control: Control,
+    confirmer: Option<Confirmer>,
+    #[cfg(feature = "provider-http")]
+    http: reqwest::Client,
+    providers: Worker,
+    #[cfg(feature = "provider-http")]
+    public_ip_url: Option<String>,
+    salt: Salt,
+    allowed_timezones: Vec<String>,
+    denied_timezones: Vec<Pattern>,
+    min_dwell: Duration,
+    cycle_timeout: Duration,
+    apply_delay: Duration,
+    jitter: Jitter,
+    apply_reconfirm: bool,
+    quiet_hours: Option<QuietHours>,
+    hooks: Hooks,
+    pending: Option<Pending>,
+    unapplied: Option<Unapplied>,
+    max_changes_per_day: usize,
+    negative_cache_after: u32,
+    negative_cache_for: Duration,
+    cap_warned: bool,
+    state_directory: PathBuf,
+    state: State,
+    statistics: Statistics,
+    status: Status,
+    status_file: StatusFile,
+
+    /// Why the last update failed, if it did.
+    last_failure: Option<Failure>,
+
+    /// The trigger whose timezone was last applied.
+    applied: Applied,
+
+    /// When the client was created, for the shutdown summary.
+    started: Instant,
+
+    /// Keeps updates that fail for long from filling the log.
+    throttle: Throttle,
+
+    /// Which connectivity session each connection belongs to.
+    connectivity: Sessions,
+}
+
+impl ZoneClient {
+    pub fn new(
+        connection: Option<Arc<SyncConnection>>,
+        setter: Setter,
+        control: Control,
+        confirmer: Option<Confirmer>,
+        config: &Config,
+    ) -> Result<Self, anyhow::Error> {
+        for problem in zone::sanity_check() {
+            warn!("{}", problem);
+        }
+        let providers = Worker::spawn(config)?;
+        #[cfg(not(feature = "provider-http"))]
+        if config.public_ip_url.is_some() {
+            warn!("Ignoring public_ip_url: built without HTTP support");
+        }
+        let salt = Salt::load(&config.state_directory).or_else(|error| {
+            warn!(
+                "{:#}; public IP addresses won't be compared across restarts",
+                error
+            );
+            Salt::generate()
+        })?;
+        let state =
+            State::load(&config.state_directory).unwrap_or_else(|error| {
+                warn!("Discarding saved state: {:#}", error);
+                State::default()
+            });
+        // A change that failed before a restart is tried again.
+        let unapplied =
+            state.unapplied_timezone.clone().map(|timezone| Unapplied {
+                change: Change {
+                    timezone,
+                    public_ip_hash: None,
+                    force: false,
+                    sequence: 0,
+                },
+                attempts: 0,
+                deadline: Instant::now() + RETRY_INTERVAL,
+                timezone_then: None,
+            });
+        let mut status = Status::new(state.timezone.clone());
+        status.given_up_networks = given_up(&state);
+        if let Some(unapplied) = &unapplied {
+            status.unapplied(Some(&unapplied.change.timezone), 0);
+        }
+        let mut status_file = StatusFile::new(config.status_file.clone());
+        status_file.write(&status);
+
+        if config.require_active_session && connection.is_none() {
+            warn!("Ignoring require_active_session: there's no system bus");
+        }
+        let jitter = Jitter::new(config.jitter);
+        Ok(Self {
+            session: connection.filter(|_| config.require_active_session),
+            backend: None,
+            max_scan_wait: config
+                .defer_while_scanning
+                .then_some(config.max_scan_wait),
+            reject_country_mismatch: config.reject_country_mismatch,
+            setter,
+            control,
+            confirmer,
+            #[cfg(feature = "provider-http")]
+            http: reqwest::Client::new(),
+            providers,
+            #[cfg(feature = "provider-http")]
+            public_ip_url: config.public_ip_url.clone(),
+            salt,
+            allowed_timezones: config
+                .allowed_timezones
+                .iter()
+                .map(|name| zone::canonicalize(name))
+                .collect(),
+            denied_timezones: config
+                .denied_timezones
+                .iter()
+                .map(|pattern| Pattern::new(pattern))
+                .collect(),
+            min_dwell: config.min_dwell,
+            cycle_timeout: config.cycle_timeout,
+            apply_delay: config.apply_delay,
+            jitter,
+            apply_reconfirm: config.apply_reconfirm,
+            quiet_hours: config.quiet_hours,
+            hooks: Hooks::new(
+                config.hooks.clone(),
+                config.transactional_hooks,
+            ),
+            pending: None,
+            unapplied,
+            max_changes_per_day: config.max_changes_per_day,
+            negative_cache_after: config.negative_cache_after,
+            negative_cache_for: config.negative_cache_for,
+            cap_warned: false,
+            state_directory: config.state_directory.clone(),
+            state,
+            statistics: Statistics::default(),
+            status,
+            status_file,
+            last_failure: None,
+            applied: Applied::default(),
+            started: Instant::now(),
+            throttle: Throttle::default(),
+            connectivity: Sessions::default(),
+        })
+    }
+
+    /// Tag a connection with its connectivity session, starting one if
+    /// there was no connection before it.
+    fn connected(&mut self, context: &mut Context) {
+        let (session, started) = self.connectivity.connected();
+        if started {
+            info!(
+                "Connectivity session {} started on {}",
+                session.id,
+                context.ssid.as_deref().unwrap_or("an unknown network")
+            );
+            self.statistics.sessions += 1;
+            self.status.session = Some(session.id.clone());
+            self.status.session_started =
+                Some(status::timestamp(session.started));
+            self.status_file.write(&self.status);
+        }
+        context.session = Some(session.id);
+    }
+
+    /// End the connectivity session, if one was in progress.
+    fn disconnected(&mut self) {
+        let Some(session) = self.connectivity.disconnected() else {
+            return;
+        };
+        let lasted = session.lasted();
+        info!(
+            "Connectivity session {} ended after {}s",
+            session.id,
+            lasted.as_secs()
+        );
+        self.status.session = None;
+        self.status.session_started = None;
+        self.status_file.write(&self.status);
+    }
+
+    /// Ask `backend` about the stations that connect, e.g. whether they're
+    /// scanning.
+    pub fn set_backend(
+        &mut self,
+        backend: Backend,
+        connection: Arc<SyncConnection>,
+    ) {
+        self.backend = Some((backend, connection));
+    }
+
+    /// Have timedated's properties re-read as soon as it reports a change,
+    /// rather than only once they're a few seconds old.
+    pub async fn watch_timedated(&mut self) {
+        if let Err(error) = self.setter.watch().await {
+            warn!("Not watching timedated's properties: {:#}", error);
+        }
+    }
+
+    fn set_activity(&mut self, activity: Activity) {
+        self.status.state = activity;
+        self.status_file.write(&self.status);
+    }
+
+    /// What a detection needs, to run without borrowing the client.
+    fn lookups(&mut self) -> Result<Lookups, anyhow::Error> {
+        Ok(Lookups {
+            backend: self.backend.clone(),
+            #[cfg(feature = "provider-http")]
+            http: self.http.clone(),
+            #[cfg(feature = "provider-http")]
+            public_ip_url: self.public_ip_url.clone(),
+            providers: self.providers.handle()?,
+        })
+    }
+
+    /// Start looking up the timezone for an update, giving up after
+    /// cycle_timeout. First, the station is given up to `scan` to finish
+    /// scanning, which doesn't count towards it. None if there's no point
+    /// trying on this network. A forced update always consults the
+    /// providers.
+    #[allow(clippy::type_complexity)]
+    fn find(
+        &mut self,
+        context: &Context,
+        force: bool,
+        scan: Option<Duration>,
+    ) -> Result<
+        Option<
+            impl Future<Output = (Option<Duration>, Result<Findings, Elapsed>)>
+                + Send,
+        >,
+        anyhow::Error,
+    > {
+        if let Some(ssid) = &context.ssid {
+            if force {
+                self.forget_network(ssid);
+            } else if self.given_up_on(ssid) {
+                info!("Not detecting on {}: it hasn't worked lately", ssid);
+                self.skip(SkipReason::GivenUp { ssid: ssid.clone() });
+                return Ok(None);
+            }
+        }
+        let lookups = self.lookups()?;
+        let context = context.clone();
+        let salt = self.salt.clone();
+        let public_ip_hash = self.state.public_ip_hash.clone();
+        // One since added to denied_timezones is detected afresh.
+        let reusable = self.state.timezone.clone().filter(|timezone| {
+            !force && !zone::is_denied(&self.denied_timezones, timezone)
+        });
+        let cycle_timeout = self.cycle_timeout;
+        Ok(Some(async move {
+            let scanned = match scan {
+                Some(limit) => lookups.wait_for_scan(&context, limit).await,
+                None => None,
+            };
+            let finding =
+                lookups.find(&context, &salt, public_ip_hash, reusable);
+            (scanned, tokio::time::timeout(cycle_timeout, finding).await)
+        }))
+    }
+
+    /// Start an update, handing back the detection for the loop to wait on.
+    /// When it lands, it's carried on with in land(). None if the update is
+    /// over already.
+    pub fn update(&mut self, trigger: Trigger) -> Option<Flight> {
+        match &trigger.context.session {
+            Some(session) => debug!(
+                "Update {} triggered by {} in session {}",
+                trigger.sequence, trigger.source, session
+            ),
+            None => debug!(
+                "Update {} triggered by {}",
+                trigger.sequence, trigger.source
+            ),
+        }
+        self.set_activity(Activity::Detecting);
+        // With defer_while_scanning, a new connection's station may still be
+        // scanning.
+        let scan = self
+            .max_scan_wait
+            .filter(|_| Source::Connection == trigger.source);
+        match self.find(&trigger.context, trigger.force, scan) {
+            Ok(Some(finding)) => Some(Box::pin(async move {
+                let (scanned, findings) = finding.await;
+                Landed::Update {
+                    trigger,
+                    scanned,
+                    findings,
+                }
+            })),
+            Ok(None) => {
+                self.finish(Ok(()));
+                None
+            }
+            Err(error) => {
+                self.finish(Err(error));
+                None
+            }
+        }
+    }
+
+    /// Carry on with the update or confirmation that `landed` was for.
+    pub async fn land(&mut self, landed: Landed) {
+        match landed {
+            Landed::Update {
+                trigger,
+                scanned,
+                findings,
+            } => self.conclude(trigger, scanned, findings).await,
+            Landed::Reconfirm { pending, consulted } => {
+                self.reconfirmed(pending, consulted).await
+            }
+        }
+    }
+
+    /// Finish an update, recording the outcome in the status file. A
+    /// failure only affects this update. Unless forced, the change is
+    /// deferred by apply_delay.
+    async fn conclude(
+        &mut self,
+        trigger: Trigger,
+        scanned: Option<Duration>,
+        findings: Result<Findings, Elapsed>,
+    ) {
+        let Trigger {
+            sequence,
+            source,
+            context,
+            force,
+        } = trigger;
+        if let Some(waited) = scanned {
+            debug!("Deferred detection {}ms for a scan", waited.as_millis());
+            self.statistics.scan_deferrals += 1;
+        }
+        let result = match self.decide(
+            &context,
+            Some(source),
+            force,
+            sequence,
+            findings,
+        ) {
+            Ok(Some(change)) if !force => {
+                let quiet = self.quiet_remaining().await;
+                let checks = [(Guard::QuietHours, quiet.is_some())];
+                self.guard(sequence, &change.timezone, &checks, force);
+                if quiet.is_some() || !self.apply_delay.is_zero() {
+                    self.defer(change, context, source, quiet);
+                    return;
+                }
+                self.apply(change).await
+            }
+            Ok(Some(change)) => self.apply(change).await,
+            Ok(None) => Ok(()),
+            Err(error) => Err(error),
+        };
+        self.finish(result);
+    }
+
+    /// Update the timezone straight away, regardless of apply_delay.
+    pub async fn update_now(
+        &mut self,
+        context: &Context,
+    ) -> Result<(), anyhow::Error> {
+        self.set_activity(Activity::Detecting);
+        let result = match self.find(context, false, None) {
+            Ok(Some(finding)) => {
+                let (_, findings) = finding.await;
+                match self.decide(context, None, false, 0, findings) {
+                    Ok(Some(change)) => self.apply(change).await,
+                    Ok(None) => Ok(()),
+                    Err(error) => Err(error),
+                }
+            }
+            Ok(None) => Ok(()),
+            Err(error) => Err(error),
+        };
+        self.set_activity(Activity::Waiting);
+        result
+    }
+
+    fn finish(&mut self, result: Result<(), anyhow::Error>) {
+        self.last_failure = result.as_ref().err().map(Failure::of);
+        match result {
+            Ok(()) => {
+                if let Some(streak) = self.throttle.succeeded(UPDATE) {
+                    info!("Updating works again after {}", streak);
+                }
+            }
+            Err(error) => {
+                let class = Failure::of(&error).name();
+                match self.throttle.failed(UPDATE, class) {
+                    Verdict::Log => {
+                        warn!("Couldn't update timezone: {:#}", error)
+                    }
+                    Verdict::Summarize(streak) => warn!(
+                        "Still couldn't update timezone: {}, last error: {:#}",
+                        streak, error
+                    ),
+                    Verdict::Suppress => {
+                        debug!("Couldn't update timezone: {:#}", error)
+                    }
+                }
+                self.status.last_error = Some(format!("{:#}", error));
+            }
+        }
+        self.set_activity(Activity::Waiting);
+    }
+
+    /// Record that an update ended without setting the timezone.
+    fn skip(&mut self, reason: SkipReason) {
+        info!("Skipping update: {}", reason);
+        *self.statistics.skipped.entry(reason.name()).or_default() += 1;
+        self.status.last_skip = Some(reason.to_string());
+    }
+
+    /// Whether the last update failed in a way that a correct clock might
+    /// fix.
+    pub fn failed_on_tls(&self) -> bool {
+        Some(Failure::Tls) == self.last_failure
+    }
+
+    /// How long until quiet_hours end, if they're in effect. They're read in
+    /// the timezone the system is set to now, so they move with it.
+    async fn quiet_remaining(&self) -> Option<Duration> {
+        let quiet_hours = self.quiet_hours.as_ref()?;
+        let timezone = match self.setter.current_timezone().await {
+            Ok(timezone) => Some(timezone),
+            Err(_) => self.state.timezone.clone(),
+        };
+        quiet_hours.remaining(timezone.as_deref(), Utc::now())
+    }
+
+    /// Hold on to a change until apply_delay has passed, or the quiet hours
+    /// ending in `quiet` have, whichever is later.
+    fn defer(
+        &mut self,
+        change: Change,
+        context: Context,
+        source: Source,
+        quiet: Option<Duration>,
+    ) {
+        let mut delay = self.jitter.apply(self.apply_delay);
+        let quiet = match quiet {
+            Some(remaining) if remaining >= delay => {
+                delay = remaining;
+                true
+            }
+            _ => false,
+        };
+        info!(
+            "Setting timezone to {} in {}s{}",
+            change.timezone,
+            delay.as_secs(),
+            if quiet { ", once quiet_hours end" } else { "" }
+        );
+        self.status
+            .pending(Some(&change.timezone), SystemTime::now() + delay);
+        self.pending = Some(Pending {
+            change,
+            context,
+            source: Some(source),
+            deadline: Instant::now() + delay,
+            quiet,
+        });
+        self.set_activity(Activity::Pending);
+    }
+
+    /// When the pending change is due, if there is one.
+    pub fn pending_deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|pending| pending.deadline)
+    }
+
+    /// When the unapplied change is due to be tried again, if there is one.
+    pub fn retry_deadline(&self) -> Option<Instant> {
+        self.unapplied.as_ref().map(|unapplied| unapplied.deadline)
+    }
+
+    /// Forget the unapplied change, if there is one.
+    fn drop_unapplied(&mut self) {
+        if self.unapplied.take().is_some() {
+            self.status.unapplied(None, 0);
+            self.state.unapplied_timezone = None;
+            if let Err(error) = self.state.save(&self.state_directory) {
+                warn!("Couldn't save state: {:#}", error);
+            }
+        }
+    }
+
+    /// Try again to set the timezone that timedated failed to set, unless
+    /// someone has set the timezone since.
+    pub async fn retry_unapplied(&mut self) {
+        let Some(mut unapplied) = self.unapplied.take() else {
+            return;
+        };
+        if let Some(remaining) = self.quiet_remaining().await {
+            info!(
+                "Retrying {} in {}s, once quiet_hours end",
+                unapplied.change.timezone,
+                remaining.as_secs()
+            );
+            unapplied.deadline = Instant::now() + remaining;
+            self.unapplied = Some(unapplied);
+            return;
+        }
+        let current = self.setter.current_timezone().await.ok();
+        if let (Some(current), Some(then)) =
+            (&current, &unapplied.timezone_then)
+        {
+            if current != then {
+                info!(
+                    "Timezone was set to {} meanwhile, not retrying {}",
+                    current, unapplied.change.timezone
+                );
+                self.unapplied = Some(unapplied);
+                self.drop_unapplied();
+                return self.finish(Ok(()));
+            }
+        }
+
+        info!(
+            "Retrying {} (attempt {})",
+            unapplied.change.timezone,
+            unapplied.attempts + 1
+        );
+        let result = self.apply(unapplied.change.clone()).await;
+        if let Some(failed) = &mut self.unapplied {
+            failed.attempts += unapplied.attempts;
+            failed.timezone_then = unapplied.timezone_then;
+            self.status
+                .unapplied(Some(&failed.change.timezone), failed.attempts);
+        }
+        self.finish(result);
+    }
+
+    /// Drop the pending change, because the connection it was detected on
+    /// has gone away.
+    pub fn cancel_pending(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            info!("Disconnected, not setting {}", pending.change.timezone);
+            self.skip(SkipReason::Disconnected);
+            self.status.pending(None, SystemTime::now());
+            self.set_activity(Activity::Waiting);
+        }
+    }
+
+    /// Apply the pending change, now that apply_delay has passed. With
+    /// apply_reconfirm, the detection to confirm it with is handed back, and
+    /// the change is applied when it lands.
+    pub async fn apply_pending(&mut self) -> Option<Flight> {
+        let mut pending = self.pending.take()?;
+        // Quiet hours may have begun during apply_delay, or moved with a
+        // change made some other way.
+        let quiet = self.quiet_remaining().await;
+        let checks = [(Guard::QuietHours, quiet.is_some())];
+        let change = &pending.change;
+        self.guard(change.sequence, &change.timezone, &checks, false);
+        if let Some(remaining) = quiet {
+            info!(
+                "Setting timezone to {} in {}s, once quiet_hours end",
+                pending.change.timezone,
+                remaining.as_secs()
+            );
+            self.status.pending(
+                Some(&pending.change.timezone),
+                SystemTime::now() + remaining,
+            );
+            pending.deadline = Instant::now() + remaining;
+            pending.quiet = true;
+            self.pending = Some(pending);
+            return None;
+        }
+        self.status.pending(None, SystemTime::now());
+        if self.apply_reconfirm {
+            self.set_activity(Activity::Detecting);
+            let lookups = match self.lookups() {
+                Ok(lookups) => lookups,
+                Err(error) => {
+                    self.finish(Err(error.context(format!(
+                        "Couldn't confirm {}",
+                        pending.change.timezone
+                    ))));
+                    return None;
+                }
+            };
+            let cycle_timeout = self.cycle_timeout;
+            return Some(Box::pin(async move {
+                let consulting = lookups.consult(&pending.context);
+                let consulted =
+                    tokio::time::timeout(cycle_timeout, consulting).await;
+                Landed::Reconfirm { pending, consulted }
+            }));
+        }
+        let result = self.apply(pending.change).await;
+        self.finish(result);
+        None
+    }
+
+    /// Apply the pending change if the detection after apply_delay agrees
+    /// with it.
+    async fn reconfirmed(
+        &mut self,
+        pending: Pending,
+        consulted: Result<Box<Consulted>, Elapsed>,
+    ) {
+        let sequence = pending.change.sequence;
+        let result = match consulted {
+            Ok(consulted) => self.detected(
+                &pending.context,
+                pending.source,
+                sequence,
+                *consulted,
+            ),
+            Err(_) => {
+                Err(self.timed_out(&pending.context, pending.source, sequence))
+            }
+        };
+        match result {
+            Ok((detection, _))
+                if detection.timezone == pending.change.timezone => {}
+            Ok((detection, _)) => {
+                info!(
+                    "Detected {} after apply_delay, not setting {}",
+                    detection.timezone, pending.change.timezone
+                );
+                self.skip(SkipReason::Unconfirmed {
+                    detected: detection.timezone,
+                });
+                return self.finish(Ok(()));
+            }
+            Err(error) => {
+                return self.finish(Err(error.context(format!(
+                    "Couldn't confirm {}",
+                    pending.change.timezone
+                ))))
+            }
+        }
+        let result = self.apply(pending.change).await;
+        self.finish(result);
+    }
+
+    /// Log a summary of the run and save the state, then remove the status
+    /// file. Every exit that the daemon chooses goes through here.
+    pub fn shutdown(&mut self, reason: &str) {
+        let summary = Shutdown {
+            reason: reason.to_string(),
+            at: status::timestamp(SystemTime::now()),
+            uptime_seconds: self.started.elapsed().as_secs(),
+            updates: self.statistics.updates,
+            failures: self.statistics.failures,
+            timezone: self.status.timezone.clone(),
+        };
+        info!("Shutting down: {}", summary);
+        debug!("{:?}", self.statistics);
+        self.state.last_shutdown = Some(summary);
+        // A change waiting out quiet_hours is picked up again after a
+        // restart, as one that failed would be, and waits for them again.
+        if let Some(pending) =
+            self.pending.as_ref().filter(|pending| pending.quiet)
+        {
+            self.state.unapplied_timezone =
+                Some(pending.change.timezone.clone());
+        }
+        if let Err(error) = self.state.save(&self.state_directory) {
+            warn!("Couldn't save state: {:#}", error);
+        }
+        self.status_file.remove();
+    }
+
+    /// Whether detection on `ssid` has been given up on. Once
+    /// negative_cache_for has passed, it's tried again.
+    fn given_up_on(&mut self, ssid: &str) -> bool {
+        let Some(entry) = self.state.unreachable_networks.get(ssid) else {
+            return false;
+        };
+        let Some(at) = entry.given_up_at else {
+            return false;
+        };
+        let elapsed = boot::since(at, entry.given_up_at_boot.as_ref());
+        if elapsed < self.negative_cache_for {
+            return true;
+        }
+        info!("Trying detection on {} again", ssid);
+        self.forget_network(ssid);
+        false
+    }
+
+    /// Record that detection on `ssid` failed completely, giving up on it
+    /// once that has happened negative_cache_after times in a row.
+    fn network_failed(&mut self, ssid: &str) {
+        if 0 == self.negative_cache_after {
+            return;
+        }
+        let entry = self
+            .state
+            .unreachable_networks
+            .entry(ssid.to_string())
+            .or_default();
+        entry.failures += 1;
+        if entry.failures >= self.negative_cache_after
+            && entry.given_up_at.is_none()
+        {
+            warn!(
+                "Detection failed on {} {} times in a row, not trying there \
+                 for {}",
+                ssid,
+                entry.failures,
+                humantime::format_duration(self.negative_cache_for)
+            );
+            *entry = Unreachable {
+                failures: entry.failures,
+                given_up_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .ok()
+                    .map(|elapsed| elapsed.as_secs()),
+                given_up_at_boot: BootTime::now(),
+            };
+        }
+        self.networks_changed();
+    }
+
+    /// Clear any record of detection failing on `ssid`.
+    fn forget_network(&mut self, ssid: &str) {
+        if self.state.unreachable_networks.remove(ssid).is_some() {
+            self.networks_changed();
+        }
+    }
+
+    fn networks_changed(&mut self) {
+        self.status.given_up_networks = given_up(&self.state);
+        if let Err(error) = self.state.save(&self.state_directory) {
+            warn!("Couldn't save state: {:#}", error);
+        }
+    }
+
+    /// How much longer the current timezone has to stay in effect before
+    /// switching to `timezone` is allowed, if at all.
+    fn dwell_remaining(&self, timezone: &str) -> Option<Duration> {
+        let current = self.state.timezone.as_deref()?;
+        if current == timezone
+            || Some(timezone) == self.state.previous_timezone.as_deref()
+        {
+            return None;
+        }
+        let elapsed = boot::since(
+            self.state.changed_at?,
+            self.state.changed_at_boot.as_ref(),
+        );
+        self.min_dwell
+            .checked_sub(elapsed)
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Record what the guards checked at one point made of the change to
+    /// `timezone` for the trigger `sequence`, for the debug log and
+    /// GetRecentDetections, and return the first that blocked it, if any
+    /// did.
+    fn guard(
+        &self,
+        sequence: u64,
+        timezone: &str,
+        checks: &[(Guard, bool)],
+        force: bool,
+    ) -> Option<Guard> {
+        let (rulings, blocked) = guard::rule(checks, force);
+        for (guard, ruling) in rulings {
+            debug!("Guard {} on {}: {}", guard.name(), timezone, ruling);
+            self.control.ruled(sequence, guard.name(), ruling);
+        }
+        blocked
+    }
+
+    /// Whether allowed_timezones lets the timezone be changed to `timezone`.
+    fn is_allowed(&self, timezone: &str) -> bool {
+        self.allowed_timezones.is_empty()
+            || self.allowed_timezones.iter().any(|name| name == timezone)
+    }
+
+    /// With require_active_session, whether the daemon's session isn't the
+    /// one in the foreground. Not being able to tell counts as not.
+    async fn session_inactive(&self) -> bool {
+        let Some(connection) = &self.session else {
+            return false;
+        };
+        let active = session::is_active(connection.clone())
+            .await
+            .unwrap_or_else(|error| {
+                warn!("{:#}", error);
+                false
+            });
+        !active
+    }
+
+    /// Take in what the providers said, and the access point's country. The
+    /// attempt is recorded for GetRecentDetections.
+    fn detected(
+        &mut self,
+        context: &Context,
+        source: Option<Source>,
+        sequence: u64,
+        consulted: Consulted,
+    ) -> Result<(Detection, Option<CountryCheck>), anyhow::Error> {
+        let Consulted {
+            time,
+            latency,
+            report,
+            country,
+        } = consulted;
+        let report = report?;
+        self.status.providers = report.health;
+        self.statistics.demotions += report.statistics.demotions;
+        self.statistics.rewrites += report.statistics.rewrites;
+        let result = report.result;
+        let (outcome, coordinates) = match result {
+            Ok(ref detection) => (
+                history::Outcome::Timezone(detection.timezone.clone()),
+                detection.location.map(|location| location.coordinates),
+            ),
+            Err(ref error) => {
+                (history::Outcome::Error(Failure::of(error).name()), None)
+            }
+        };
+        self.control.record(Attempt {
+            time,
+            sequence,
+            source,
+            session: context.session.clone(),
+            ssid: context.ssid.clone(),
+            provider: report.answered,
+            latency,
+            outcome,
+            country: country.clone(),
+            coordinates,
+            guards: Vec::new(),
+            applied: false,
+            rolled_back: false,
+        });
+        if let Some(ssid) = &context.ssid {
+            match result {
+                Ok(_) => self.forget_network(ssid),
+                Err(_) => self.network_failed(ssid),
+            }
+        }
+        Ok((result?, country))
+    }
+
+    fn timed_out(
+        &mut self,
+        context: &Context,
+        source: Option<Source>,
+        sequence: u64,
+    ) -> anyhow::Error {
+        self.statistics.cycle_timeouts += 1;
+        if let Some(ssid) = &context.ssid {
+            self.network_failed(ssid);
+        }
+        self.control.record(Attempt {
+            time: SystemTime::now() - self.cycle_timeout,
+            sequence,
+            source,
+            session: context.session.clone(),
+            ssid: context.ssid.clone(),
+            provider: None,
+            latency: self.cycle_timeout,
+            outcome: history::Outcome::Error("timeout"),
+            country: None,
+            coordinates: None,
+            guards: Vec::new(),
+            applied: false,
+            rolled_back: false,
+        });
+        anyhow!(
+            "Detection timed out after {}s (cycle_timeout)",
+            self.cycle_timeout.as_secs()
+        )
+    }
+
+    /// Decide which timezone to switch to, if any, from what the update
+    /// found. A forced update isn't subject to min_dwell.
+    fn decide(
+        &mut self,
+        context: &Context,
+        source: Option<Source>,
+        force: bool,
+        sequence: u64,
+        findings: Result<Findings, Elapsed>,
+    ) -> Result<Option<Change>, anyhow::Error> {
+        let Ok(findings) = findings else {
+            return Err(self.timed_out(context, source, sequence));
+        };
+        let Findings {
+            public_ip_hash,
+            egress,
+            answer,
+        } = findings;
+        debug!("Public IP address {}", egress);
+        self.status.egress = egress;
+
+        let (timezone, detection, mismatch) = match answer {
+            Answer::Reused(timezone) => {
+                info!("Public IP address unchanged, reusing {}", timezone);
+                (timezone, None, None)
+            }
+            Answer::Consulted(consulted) => {
+                let (detection, country) =
+                    self.detected(context, source, sequence, *consulted)?;
+                let mismatch = match country {
+                    Some(CountryCheck::Advertised {
+                        country,
+                        matches: false,
+                    }) => Some(country),
+                    _ => None,
+                };
+                if let Some(country) = &mismatch {
+                    warn!(
+                        "Detected {}, which isn't used in {}, where the \
+                         access point says it is (a VPN, or stale geo-IP \
+                         data?)",
+                        detection.timezone, country
+                    );
+                }
+                (detection.timezone.clone(), Some(detection), mismatch)
+            }
+        };
+        let dwell = self.dwell_remaining(&timezone);
+        // Providers never answer with a timezone in denied_timezones, nor
+        // is one reused, so there's no need to check for them here.
+        let checks = [
+            (
+                Guard::Country,
+                mismatch.is_some() && self.reject_country_mismatch,
+            ),
+            (Guard::Allowed, !self.is_allowed(&timezone)),
+            (Guard::Dwell, dwell.is_some()),
+        ];
+        let blocked = self.guard(sequence, &timezone, &checks, force);
+        if let Some(country) =
+            mismatch.filter(|_| Some(Guard::Country) == blocked)
+        {
+            self.skip(SkipReason::CountryMismatch { timezone, country });
+            return Ok(None);
+        }
+        if let Some(detection) = detection {
+            self.status.rewritten_from = detection.rewritten_from;
+            if let Some(location) = detection.location {
+                self.control.set_location(location);
+            }
+        }
+        let superseded = self
+            .unapplied
+            .as_ref()
+            .is_some_and(|unapplied| unapplied.change.timezone != timezone);
+        if superseded {
+            info!("Detected {}, no longer retrying", timezone);
+            self.drop_unapplied();
+        }
+
+        if Some(Guard::Allowed) == blocked {
+            warn!("Ignoring {}: not in allowed_timezones", timezone);
+            self.statistics.rejections += 1;
+            self.skip(SkipReason::NotAllowed { timezone });
+            return Ok(None);
+        }
+
+        if let Some(remaining) = dwell {
+            if Some(Guard::Dwell) == blocked {
+                info!("Not switching to {} yet (min_dwell)", timezone);
+                self.skip(SkipReason::Dwell { remaining });
+                return Ok(None);
+            }
+            info!("Forced update, ignoring min_dwell");
+        }
+        Ok(Some(Change {
+            timezone,
+            public_ip_hash,
+            force,
+            sequence,
+        }))
+    }
+
+    /// Hold on to a change that timedated failed to make, to try it again
+    /// shortly.
+    fn keep_unapplied(
+        &mut self,
+        change: Change,
+        timezone_then: Option<String>,
+    ) {
+        let delay = self.jitter.apply(RETRY_INTERVAL);
+        info!("Will try {} again in {}s", change.timezone, delay.as_secs());
+        self.status.unapplied(Some(&change.timezone), 1);
+        self.state.unapplied_timezone = Some(change.timezone.clone());
+        self.unapplied = Some(Unapplied {
+            change,
+            attempts: 1,
+            deadline: Instant::now() + delay,
+            timezone_then,
+        });
+        if let Err(error) = self.state.save(&self.state_directory) {
+            warn!("Couldn't save state: {:#}", error);
+        }
+    }
+
+    /// Whether max_changes_per_day has been reached.
+    fn capped(&mut self) -> bool {
+        self.state
+            .recent_changes
+            .retain(|change| change.elapsed() < DAY);
+        let capped = 0 != self.max_changes_per_day
+            && self.state.recent_changes.len() >= self.max_changes_per_day;
+        if !capped {
+            self.cap_warned = false;
+        }
+        capped
+    }
+
+    async fn apply(&mut self, change: Change) -> Result<(), anyhow::Error> {
+        let Change {
+            timezone,
+            public_ip_hash,
+            force,
+            sequence,
+        } = change;
+        let capped = self.capped();
+        let checks = [
+            (Guard::Stale, self.applied.is_stale(sequence)),
+            (Guard::Session, self.session_inactive().await),
+            (Guard::Cap, capped),
+        ];
+        let blocked = self.guard(sequence, &timezone, &checks, force);
+        if Some(Guard::Stale) == blocked {
+            info!("Not setting {}: a later update has finished", timezone);
+            self.skip(SkipReason::Stale);
+            return Ok(());
+        }
+
+        if Some(Guard::Session) == blocked {
+            info!("Not setting {}: session is not active", timezone);
+            self.skip(SkipReason::InactiveSession);
+            return Ok(());
+        }
+
+        if capped {
+            if Some(Guard::Cap) == blocked {
+                if !self.cap_warned {
+                    warn!(
+                        "Set the timezone {} times in the last 24 hours, \
+                         suppressing further changes",
+                        self.max_changes_per_day
+                    );
+                    self.cap_warned = true;
+                }
+                info!("Suppressed change to {}", timezone);
+                self.statistics.suppressed += 1;
+                self.skip(SkipReason::Capped);
+                return Ok(());
+            }
+            info!("Forced update, ignoring max_changes_per_day");
+        }
+
+        if let Some(confirmer) = &mut self.confirmer {
+            let current = self.setter.current_timezone().await.ok();
+            let declined = Some(&timezone) != current.as_ref()
+                && !confirmer.confirm(&timezone, current.as_deref()).await;
+            let checks = [(Guard::Confirm, declined)];
+            self.guard(sequence, &timezone, &checks, force);
+            if declined {
+                info!("Not setting timezone to {}: declined", timezone);
+                self.skip(SkipReason::Declined);
+                return Ok(());
+            }
+        }
+
+        // What to roll back to, should a critical hook fail.
+        let before = match self.hooks.is_empty() {
+            true => None,
+            false => self.setter.current_timezone().await.ok(),
+        };
+        info!("Setting timezone to {}", timezone);
+        let result = self.setter.apply(&timezone, &mut self.statistics).await;
+        match result {
+            Ok(Outcome::Set) => {
+                self.statistics.updates += 1;
+                // Saved now, since a critical hook failing would skip
+                // the save below.
+                self.state.recent_changes.push(state::Change::now());
+                if let Err(error) = self.state.save(&self.state_directory) {
+                    warn!("Couldn't save state: {:#}", error);
+                }
+            }
+            Ok(Outcome::Unchanged) => {
+                info!("Timezone is already {}", timezone)
+            }
+            Err(ref error) => {
+                self.statistics.failures += 1;
+                if error.downcast_ref::<Denied>().is_none() {
+                    let timezone_then =
+                        self.setter.current_timezone().await.ok();
+                    self.keep_unapplied(
+                        Change {
+                            timezone: timezone.clone(),
+                            public_ip_hash: public_ip_hash.clone(),
+                            force,
+                            sequence,
+                        },
+                        timezone_then,
+                    );
+                }
+            }
+        }
+        debug!("{:?}", self.statistics);
+        let outcome = result?;
+        let checks = [(Guard::Unchanged, Outcome::Unchanged == outcome)];
+        self.guard(sequence, &timezone, &checks, force);
+        if Outcome::Set == outcome {
+            let hooks = self.hooks.run(&timezone, before.as_deref(), false);
+            if let Err(error) = hooks.await {
+                return self
+                    .roll_back(&timezone, before, sequence, error)
+                    .await;
+            }
+        }
+
+        self.applied.record(sequence);
+        self.control.applied(sequence);
+        self.unapplied = None;
+        self.status.unapplied(None, 0);
+        self.state.unapplied_timezone = None;
+        self.status.applied(&timezone);
+        if Outcome::Unchanged == outcome {
+            self.skip(SkipReason::Unchanged);
+        }
+        self.state.public_ip_hash = public_ip_hash;
+        if Some(&timezone) != self.state.timezone.as_ref() {
+            self.state.previous_timezone = self.state.timezone.take();
+            self.state.changed_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs());
+            self.state.changed_at_boot = BootTime::now();
+        }
+        self.state.timezone = Some(timezone);
+        if let Err(error) = self.state.save(&self.state_directory) {
+            warn!("Couldn't save state: {:#}", error);
+        }
+        Ok(())
+    }
+
+    /// Set the timezone back to `previous` after a critical hook failed on
+    /// the change to `timezone`, and run the hooks again for that. A failure
+    /// of the rollback isn't rolled back in turn. Returns what happened, as
+    /// the error ending the update.
+    async fn roll_back(
+        &mut self,
+        timezone: &str,
+        previous: Option<String>,
+        sequence: u64,
+        error: anyhow::Error,
+    ) -> Result<(), anyhow::Error> {
+        self.statistics.rollbacks += 1;
+        self.control.rolled_back(sequence);
+        let Some(previous) = previous else {
+            return Err(error.context(format!(
+                "Couldn't roll back from {}: the timezone before is unknown",
+                timezone
+            )));
+        };
+        warn!("{:#}, rolling back to {}", error, previous);
+        let result = self.setter.apply(&previous, &mut self.statistics).await;
+        if let Err(rollback) = result {
+            return Err(error.context(format!(
+                "Couldn't roll back to {}: {:#}",
+                previous, rollback
+            )));
+        }
+        let _ = self.hooks.run(&previous, Some(timezone), true).await;
+        Err(error
+            .context(format!("Rolled back from {} to {}", timezone, previous)))
+    }
+}
+
+/// The networks in `state` that detection has been given up on.
+fn given_up(state: &State) -> Vec<String> {
+    state
+        .unreachable_networks
+        .iter()
+        .filter(|(_, entry)| entry.given_up_at.is_some())
+        .map(|(ssid, _)| ssid.clone())
+        .collect()
+}
+
+/// Sleep until `deadline`, or forever if there isn't one.
+async fn until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Update the timezone once, and exit. With `wait_timeout`, wait that long
+/// for a connection to come up first, instead of assuming there is one.
+pub async fn once(
+    config: Config,
+    confirmer: Option<Confirmer>,
+    wait_timeout: Option<Duration>,
+) -> Result<ExitCode, anyhow::Error> {
+    let (system_bus, setter) = environment::prepare(
+        connect(),
+        config.without_timedated,
+        config.interactive_auth,
+        &Jitter::new(config.jitter),
+    )
+    .await?;
+    let control = Control::new(
+        system_bus.clone(),
+        config.export_location,
+        config.recent_detections,
+    )
+    .await;
+    let mut client = ZoneClient::new(
+        system_bus.clone(),
+        setter,
+        control,
+        confirmer,
+        &config,
+    )?;
+
+    let context = match wait_timeout {
+        Some(timeout) => {
+            match wait_for_connection(&config, system_bus, timeout).await? {
+                Some(context) => context,
+                None => {
+                    warn!("No connection came up within {:?}", timeout);
+                    client.shutdown("no connectivity");
+                    return Ok(ExitCode::from(exit::NO_CONNECTIVITY));
+                }
+            }
+        }
+        None => Context::default(),
+    };
+
+    let result = client.update_now(&context).await;
+    match &result {
+        Ok(()) => client.shutdown("finished"),
+        Err(error) => client.shutdown(&format!("{:#}", error)),
+    }
+    result?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Wait for a connection to come up, or to be up already. Returns None if
+/// none does within `timeout`.
+async fn wait_for_connection(
+    config: &Config,
+    system_bus: Option<Arc<SyncConnection>>,
+    timeout: Duration,
+) -> Result<Option<Context>, anyhow::Error> {
+    let backend = choose_backend(config, system_bus.as_ref()).await?;
+    info!("Waiting for a connection managed by {}", backend);
+    let (sender, mut events) = monitor::channel(monitor::CAPACITY);
+    let max_resubscribes = config.max_resubscribes;
+    let monitor = tokio::spawn(async move {
+        backend
+            .monitor(system_bus, sender, max_resubscribes, true)
+            .await
+    });
+
+    // Whatever happens, the monitor is stopped before anything else is done,
+    // so a connection arriving late can't start a second update.
+    let connected = tokio::time::timeout(timeout, async {
+        while let Some(event) = events.recv().await {
+            if let Event::Connected(context) = event {
+                return Some(context);
+            }
+        }
+        None
+    })
+    .await;
+    monitor.abort();
+    match connected {
+        Ok(Some(context)) => Ok(Some(context)),
+        Ok(None) => match monitor.await {
+            Ok(result) => result.map(|_| None),
+            Err(error) => Err(error.into()),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+/// Choose the backend, on the system bus if there is one.
+async fn choose_backend(
+    config: &Config,
+    system_bus: Option<&Arc<SyncConnection>>,
+) -> Result<Backend, anyhow::Error> {
+    match system_bus {
+        Some(system_bus) => {
+            Backend::probe(config.backend, system_bus.clone()).await
+        }
+        None => Backend::without_bus(config.backend),
+    }
+}
+
+/// Resolves, with what went wrong, when the connection to the system bus is
+/// lost.
+pub struct Lost(oneshot::Receiver<String>);
+
+/// Wait for the detection in flight to land, or forever if there isn't one.
+async fn land(flight: &mut Option<Flight>) -> Landed {
+    let Some(lookup) = flight else {
+        return std::future::pending().await;
+    };
+    let landed = lookup.as_mut().await;
+    *flight = None;
+    landed
+}
+
+/// Wait for the connection to be lost, or forever if there isn't one.
+async fn until_lost(lost: &mut Option<Lost>) -> String {
+    match lost {
+        Some(Lost(receiver)) => receiver
+            .await
+            .unwrap_or_else(|_| "Lost connection to D-Bus".to_string()),
+        None => std::future::pending().await,
+    }
+}
+
+/// Connect to the system bus. Losing the connection is only logged; calls
+/// on it fail from then on.
+pub fn connect() -> Result<Arc<SyncConnection>, anyhow::Error> {
+    connect_watched().map(|(system_bus, _)| system_bus)
+}
+
+/// Connect to the system bus, and report when the connection is lost.
+pub fn connect_watched() -> Result<(Arc<SyncConnection>, Lost), anyhow::Error>
+{
+    let (resource, system_bus) = connection::new_system_sync()?;
+
+    // The resource is a task that should be spawned onto a tokio compatible
+    // reactor ASAP. If the resource ever finishes, you lost connection to
+    // D-Bus.
+    let (sender, lost) = oneshot::channel();
+    tokio::spawn(async move {
+        let error = resource.await;
+        let message = format!("Lost connection to D-Bus: {}", error);
+        if let Err(message) = sender.send(message) {
+            warn!("{}", message);
+        }
+    });
+
+    // Several watchers match the same signals: timedated's properties are
+    // both cached by the setter and watched for NTPSynchronized, and a
+    // station's are watched by the monitor and while waiting for a scan. By
+    // default only the first match would see each signal.
+    system_bus.set_signal_match_mode(true);
+    Ok((system_bus, Lost(lost)))
+}
+
+/// Carry out a command from the control socket. Updates are queued like any
+/// other, and answered before they run.
+fn command(
+    request: Request,
+    queue: &mut UpdateQueue,
+    client: &ZoneClient,
+    connection: &Option<Context>,
+) {
+    let response = match request.command {
+        Command::Update { force } => {
+            info!("Update requested on the control socket");
+            let context = connection.clone().unwrap_or_default();
+            let coalesced = queue.push(Source::Socket, context, force);
+            json!({
+                "ok": true,
+                "queued": true,
+                "force": force,
+                "coalesced": coalesced,
+            })
+        }
+        // The recent detections may hold SSIDs and locations, so they're
+        // only ever in the reply, never in the status file.
+        Command::Status => json!({
+            "ok": true,
+            "status": client.status,
+            "recent_detections": client.control.recent_detections(),
+        }),
+    };
+    // The client may have gone already.
+    let _ = request.reply.send(response);
+}
+
+/// Queue an update for a connection, or drop what's waiting for a
+/// disconnection. `connection` is kept up to date with the latest one.
+fn receive(
+    event: Event,
+    queue: &mut UpdateQueue,
+    client: &mut ZoneClient,
+    connection: &mut Option<Context>,
+) {
+    match event {
+        Event::Connected(mut context) => {
+            client.connected(&mut context);
+            *connection = Some(context.clone());
+            if queue.push(Source::Connection, context, false) {
+                client.statistics.coalesced += 1;
+            }
+        }
+        Event::Disconnected(context) => {
+            debug!("Disconnected: {:?}", context);
+            queue.cancel(&context.station);
+            client.cancel_pending();
+            client.disconnected();
+            *connection = None;
+        }
+    }
+}
+
+pub async fn run(
+    config: Config,
+    confirmer: Option<Confirmer>,
+) -> Result<(), anyhow::Error> {
+    info!(
+        "Built with backends: {}; providers: {}",
+        monitor::COMPILED.join(", "),
+        provider::COMPILED.join(", ")
+    );
+    let (connection, mut lost) = match connect_watched() {
+        Ok((connection, lost)) => (Ok(connection), Some(lost)),
+        Err(error) => (Err(error), None),
+    };
+    let (system_bus, setter) = environment::prepare(
+        connection,
+        config.without_timedated,
+        config.interactive_auth,
+        &Jitter::new(config.jitter),
+    )
+    .await?;
+    let timedated = matches!(setter, Setter::Timedated(_));
+    let control = Control::new(
+        system_bus.clone(),
+        config.export_location,
+        config.recent_detections,
+    )
+    .await;
+    let backend = choose_backend(&config, system_bus.as_ref()).await?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut user1 = signal(SignalKind::user_defined1())?;
+    let confirm = confirmer.is_some();
+    let setter_name = setter.name();
+    let mut client = ZoneClient::new(
+        system_bus.clone(),
+        setter,
+        control,
+        confirmer,
+        &config,
+    )?;
+    info!("Monitoring connections managed by {}", backend);
+    if config.reject_country_mismatch && !backend.reads_country() {
+        warn!(
+            "reject_country_mismatch does nothing with {}, which can't read \
+             the country access points advertise",
+            backend
+        );
+    }
+    if let Some(system_bus) = &system_bus {
+        client.set_backend(backend, system_bus.clone());
+    }
+    client.control.set_capabilities(Capabilities::new(
+        &config,
+        backend,
+        setter_name,
+        client.providers.names().to_vec(),
+        confirm,
+    ));
+    client.watch_timedated().await;
+
+    // On first boot the clock may be far enough off that TLS fails, and
+    // nothing else will prompt another attempt once NTP has fixed it.
+    let (sender, mut synchronized) = mpsc::unbounded_channel();
+    if let Some(clock) = system_bus.clone().filter(|_| timedated) {
+        tokio::spawn(async move {
+            if let Err(error) = ntp::monitor(clock, sender).await {
+                warn!("Not watching for clock synchronization: {:#}", error);
+            }
+        });
+    }
+
+    let (sender, mut resolved) = mpsc::unbounded_channel();
+    match (config.dns_trigger, system_bus.clone()) {
+        (true, Some(resolver)) => {
+            let link = config.dns_link.clone();
+            tokio::spawn(async move {
+                if let Err(error) = dns::monitor(resolver, link, sender).await
+                {
+                    warn!("Not watching DNS configuration: {:#}", error);
+                }
+            });
+        }
+        (true, None) => {
+            warn!("Ignoring dns_trigger: there's no system bus");
+        }
+        (false, _) => {}
+    }
+
+    let (sender, mut commands) = mpsc::unbounded_channel();
+    let listening = config.control_socket.as_ref().and_then(|path| {
+        match socket::bind(path, config.control_socket_group.as_deref()) {
+            Ok(listener) => {
+                tokio::spawn(socket::serve(listener, path.clone(), sender));
+                Some(path)
+            }
+            Err(error) => {
+                warn!("Not accepting commands: {:#}", error);
+                None
+            }
+        }
+    });
+
+    let (sender, mut events) = monitor::channel(monitor::CAPACITY);
+    let max_resubscribes = config.max_resubscribes;
+    let mut monitor = tokio::spawn(async move {
+        backend
+            .monitor(system_bus, sender, max_resubscribes, false)
+            .await
+    });
+
+    let mut connection: Option<Context> = None;
+    let mut queue = UpdateQueue::default();
+    let mut flight: Option<Flight> = None;
+    // Every way out of the loop ends up in the same shutdown below.
+    let result = loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(event) => {
+                    receive(event, &mut queue, &mut client, &mut connection)
+                }
+                // The monitor only stops for good when it gives up.
+                None => break match (&mut monitor).await {
+                    Ok(Ok(())) => Err(anyhow!("The monitor stopped")),
+                    Ok(Err(error)) => Err(error),
+                    Err(error) => Err(error.into()),
+                },
+            },
+            _ = until(client.pending_deadline()), if flight.is_none() => {
+                flight = client.apply_pending().await
+            }
+            landed = land(&mut flight) => client.land(landed).await,
+            Some(()) = synchronized.recv() => {
+                if client.failed_on_tls() {
+                    info!("Retrying now that the clock is synchronized");
+                    let context = connection.clone().unwrap_or_default();
+                    queue.push(Source::Synchronized, context, false);
+                }
+            }
+            Some(()) = resolved.recv() => {
+                let context = connection.clone().unwrap_or_default();
+                queue.push(Source::Dns, context, false);
+            }
+            Some(request) = commands.recv() => {
+                command(request, &mut queue, &client, &connection)
+            }
+            _ = until(client.retry_deadline()), if flight.is_none() => {
+                client.retry_unapplied().await
+            }
+            _ = user1.recv() => {
+                info!("Received SIGUSR1, forcing an update");
+                let context = connection.clone().unwrap_or_default();
+                queue.push(Source::Signal, context, true);
+            }
+            message = until_lost(&mut lost) => break Err(anyhow!(message)),
+            _ = terminate.recv() => break Ok("terminated"),
+            _ = interrupt.recv() => break Ok("interrupted"),
+        }
+
+        // Updates run one at a time, in the order they were asked for, and
+        // the loop above carries on while one is in flight. Take in whatever
+        // arrives in the meantime, so that it can be coalesced.
+        while flight.is_none() {
+            while let Some(event) = events.try_recv() {
+                receive(event, &mut queue, &mut client, &mut connection);
+            }
+            let Some(trigger) = queue.pop() else {
+                break;
+            };
+            client.statistics.queue_depth = queue.depth();
+            client.statistics.signals_dropped =
+                events.dropped() + signals::dropped();
+            flight = client.update(trigger);
+        }
+    };
+
+    if let Some(path) = listening {
+        let _ = fs::remove_file(path);
+    }
+    match result {
+        Ok(reason) => {
+            client.shutdown(reason);
+            Ok(())
+        }
+        Err(error) => {
+            client.shutdown(&format!("{:#}", error));
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flight that lands once `gate` is released, with a reused timezone.
+    fn gated(gate: oneshot::Receiver<()>, sequence: u64) -> Flight {
+        Box::pin(async move {
+            let _ = gate.await;
+            Landed::Update {
+                trigger: Trigger {
+                    sequence,
+                    source: Source::Socket,
+                    context: Context::default(),
+                    force: false,
+                },
+                scanned: None,
+                findings: Ok(Findings {
+                    public_ip_hash: None,
+                    egress: Egress::Unchanged,
+                    answer: Answer::Reused("Europe/Berlin".into()),
+                }),
+            }
+        })
+    }
+
+    fn sequence(landed: Landed) -> u64 {
+        match landed {
+            Landed::Update { trigger, .. } => trigger.sequence,
+            Landed::Reconfirm { .. } => panic!("Expected an update"),
+        }
+    }
+
+    #[tokio::test]
+    async fn nothing_lands_without_a_flight() {
+        let mut flight = None;
+        let waiting = Duration::from_millis(10);
+        assert!(tokio::time::timeout(waiting, land(&mut flight))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn interrupted_waits_keep_the_flight() {
+        let (release, gate) = oneshot::channel();
+        let mut flight = Some(gated(gate, 1));
+        // As when another branch of the loop wins, several times over.
+        for _ in 0..3 {
+            let waiting = Duration::from_millis(10);
+            assert!(tokio::time::timeout(waiting, land(&mut flight))
+                .await
+                .is_err());
+            assert!(flight.is_some());
+        }
+        release.send(()).unwrap();
+        assert_eq!(1, sequence(land(&mut flight).await));
+        assert!(flight.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_dropped_flight_makes_way_for_the_next() {
+        let (_release, gate) = oneshot::channel();
+        let mut flight = Some(gated(gate, 1));
+        let waiting = Duration::from_millis(10);
+        assert!(tokio::time::timeout(waiting, land(&mut flight))
+            .await
+            .is_err());
+        drop(flight.take());
+        let (release, gate) = oneshot::channel();
+        flight = Some(gated(gate, 2));
+        release.send(()).unwrap();
+        assert_eq!(2, sequence(land(&mut flight).await));
+        assert!(flight.is_none());
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
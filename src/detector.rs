@@ -0,0 +1,109 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            detector.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     One-shot detection of the timezone, without the daemon, the
+//                  system bus or anything else that sets it.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::location::Coordinates;
+use crate::provider::{Context, ProviderChain};
+use crate::statistics::Statistics;
+use crate::zone;
+use anyhow::anyhow;
+use std::time::{Duration, Instant};
+
+/// The timezone determined by a Detector, already rewritten, canonicalized
+/// and checked against the tz database and denied_timezones. More may be
+/// said about it in later versions.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Detection {
+    pub timezone: String,
+
+    /// Where the provider placed this host, if it said.
+    pub coordinates: Option<Coordinates>,
+
+    /// The provider that answered.
+    pub provider: String,
+
+    /// How long the whole chain took, failed providers included.
+    pub latency: Duration,
+}
+
+/// Consults the configured providers in order, the way the daemon does, and
+/// nothing more. This is the interface meant for embedding: its shape will
+/// only change with the major version.
+pub struct Detector {
+    providers: ProviderChain,
+}
+
+impl Detector {
+    /// Build the provider chain from `config`, which is validated first.
+    /// The tz database is looked for in its zoneinfo_directory from then on,
+    /// as with Config::load; only the first directory a process uses counts.
+    pub fn from_config(config: &Config) -> Result<Self, anyhow::Error> {
+        zone::set_directory(&config.zoneinfo_directory);
+        let problems = config.validate();
+        if !problems.is_empty() {
+            return Err(anyhow!(
+                "Invalid configuration:\n{}",
+                problems.join("\n")
+            ));
+        }
+        Ok(Self {
+            providers: ProviderChain::new(config)?,
+        })
+    }
+
+    /// Detect the timezone. Providers that fail demote as they would in the
+    /// daemon, for as long as this Detector is kept.
+    pub async fn detect(&mut self) -> Result<Detection, anyhow::Error> {
+        let started = Instant::now();
+        let detection = self
+            .providers
+            .detect(&Context::default(), &mut Statistics::default())
+            .await?;
+        Ok(Detection {
+            timezone: detection.timezone,
+            coordinates: detection
+                .location
+                .map(|location| location.coordinates),
+            provider: self
+                .providers
+                .answered()
+                .unwrap_or_default()
+                .to_string(),
+            latency: started.elapsed(),
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,149 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            dns.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Auxiliary trigger for when systemd-resolved learns DNS servers for
+//                  a link.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::signals::{self, PropertiesChanged};
+use anyhow::Context;
+use dbus::arg::RefArg;
+use dbus::message::MatchRule;
+use dbus::nonblock::{Proxy, SyncConnection};
+use dbus::Message;
+use log::{debug, info};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::Instant;
+
+const SERVICE: &str = "org.freedesktop.resolve1";
+const LINK: &str = "org.freedesktop.resolve1.Link";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the DNS configuration must be left alone before an update is
+/// triggered, since resolved often sets it a piece at a time.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// The object path resolved uses for the link named `name`.
+async fn link_path(
+    connection: Arc<SyncConnection>,
+    name: &str,
+) -> Result<dbus::Path<'static>, anyhow::Error> {
+    let index: i32 =
+        fs::read_to_string(format!("/sys/class/net/{}/ifindex", name))
+            .with_context(|| format!("No such link: {}", name))?
+            .trim()
+            .parse()?;
+    let proxy =
+        Proxy::new(SERVICE, "/org/freedesktop/resolve1", TIMEOUT, connection);
+    let (path,): (dbus::Path<'static>,) = proxy
+        .method_call("org.freedesktop.resolve1.Manager", "GetLink", (index,))
+        .await
+        .with_context(|| format!("resolved doesn't know {}", name))?;
+    Ok(path)
+}
+
+/// Whether a PropertiesChanged signal from a link sets its DNS servers, and
+/// if so, whether there are any.
+fn parse_dns(message: &Message) -> Option<bool> {
+    let properties = PropertiesChanged::read(message)?;
+    if LINK != properties.interface {
+        return None;
+    }
+    let servers = properties.changed.get("DNS")?;
+    let has_servers = servers.0.as_iter()?.next().is_some();
+    Some(has_servers)
+}
+
+/// Send to `events` whenever a link (or just the one named `link`) gains DNS
+/// servers after having none, once its configuration has settled.
+pub async fn monitor(
+    connection: Arc<SyncConnection>,
+    link: Option<String>,
+    events: UnboundedSender<()>,
+) -> Result<(), anyhow::Error> {
+    let mut rule = MatchRule::new_signal(
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+    )
+    .with_sender(SERVICE);
+    if let Some(name) = &link {
+        rule = rule.with_path(link_path(connection.clone(), name).await?);
+    }
+    let (signal, mut incoming) = signals::subscribe(&connection, rule).await?;
+
+    // Links not seen yet are taken to have no DNS servers.
+    let mut configured: HashMap<String, bool> = HashMap::new();
+    let mut deadline: Option<Instant> = None;
+    loop {
+        let sleep = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            message = incoming.recv() => {
+                let Some(message) = message else {
+                    break;
+                };
+                let (Some(path), Some(has_servers)) =
+                    (message.path(), parse_dns(&message))
+                else {
+                    continue;
+                };
+                let had_servers = configured
+                    .insert(path.to_string(), has_servers)
+                    .unwrap_or(false);
+                if has_servers && !had_servers {
+                    debug!("{} has DNS servers now", path);
+                    deadline = Some(Instant::now() + DEBOUNCE);
+                } else if deadline.is_some() {
+                    deadline = Some(Instant::now() + DEBOUNCE);
+                }
+            }
+            _ = sleep => {
+                deadline = None;
+                info!("DNS servers configured, updating");
+                events.send(())?;
+            }
+        }
+    }
+
+    if let Err(error) = connection.remove_match(signal.token()).await {
+        debug!("Couldn't remove match: {}", error);
+    }
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,254 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            egress.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Tells whether the public IP address has changed, without keeping it.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::state::write_atomically_with_mode;
+use anyhow::Context;
+use log::warn;
+use serde::Serialize;
+use siphasher::sip128::{Hasher128, SipHasher24};
+use std::fmt;
+use std::fs::{self, File, Permissions};
+use std::hash::Hasher;
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+const SALT_FILE: &str = "salt";
+
+/// Only the owner may read the salt: anyone who can could reverse the hash.
+const SALT_MODE: u32 = 0o600;
+
+/// Whether the public IP address is the one seen at the last lookup.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Egress {
+    Unchanged,
+    Changed,
+
+    /// Either address isn't known: public_ip_url isn't set, the lookup
+    /// failed, or there's been no lookup since the salt was made.
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for Egress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            Egress::Unchanged => "unchanged",
+            Egress::Changed => "changed",
+            Egress::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A secret, random per install, that keys the hash of the address. Without
+/// it, the hash of an IPv4 address could be reversed by hashing all of them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Salt([u8; 16]);
+
+impl Salt {
+    /// A new salt, from the kernel's random number generator.
+    pub fn generate() -> Result<Self, anyhow::Error> {
+        let mut bytes = [0u8; 16];
+        File::open("/dev/urandom")
+            .and_then(|mut random| random.read_exact(&mut bytes))
+            .context("Couldn't read /dev/urandom")?;
+        Ok(Self(bytes))
+    }
+
+    /// The salt as written to its file: 32 hex digits.
+    fn parse(contents: &str) -> Option<Self> {
+        let contents = contents.trim();
+        if 32 != contents.len() || !contents.is_ascii() {
+            return None;
+        }
+        let mut bytes = [0u8; 16];
+        for (byte, digits) in
+            bytes.iter_mut().zip(contents.as_bytes().chunks(2))
+        {
+            let digits = std::str::from_utf8(digits).ok()?;
+            *byte = u8::from_str_radix(digits, 16).ok()?;
+        }
+        Some(Self(bytes))
+    }
+
+    fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Read the salt from `directory`, making one if there isn't one. If it
+    /// can't be saved, it lasts only as long as this run, and the address is
+    /// counted as unknown at the start of the next.
+    pub fn load(directory: &Path) -> Result<Self, anyhow::Error> {
+        let path = directory.join(SALT_FILE);
+        match fs::read_to_string(&path) {
+            Ok(contents) => match Self::parse(&contents) {
+                Some(salt) => {
+                    // Earlier releases made the file readable by everyone.
+                    let permissions = Permissions::from_mode(SALT_MODE);
+                    if let Err(error) = fs::set_permissions(&path, permissions)
+                    {
+                        warn!(
+                            "Couldn't restrict {}: {}",
+                            path.display(),
+                            error
+                        );
+                    }
+                    return Ok(salt);
+                }
+                None if !contents.trim().is_empty() => {
+                    warn!("Replacing the invalid salt in {}", path.display())
+                }
+                None => {}
+            },
+            Err(error) if ErrorKind::NotFound == error.kind() => {}
+            Err(error) => {
+                return Err(error).with_context(|| {
+                    format!("Couldn't read {}", path.display())
+                })
+            }
+        }
+
+        let salt = Self::generate()?;
+        fs::create_dir_all(directory).with_context(|| {
+            format!("Couldn't create {}", directory.display())
+        })?;
+        write_atomically_with_mode(&path, &salt.to_hex(), SALT_MODE)?;
+        Ok(salt)
+    }
+
+    /// Hash `address`, with SipHash-2-4 keyed by the salt. Only the hash is
+    /// kept, in the state and in memory; if a new salt is made, the last
+    /// address just counts as changed.
+    pub fn hash(&self, address: &str) -> String {
+        let mut hasher = SipHasher24::new_with_key(&self.0);
+        hasher.write(address.as_bytes());
+        format!("{:032x}", hasher.finish128().as_u128())
+    }
+}
+
+/// Compare the hash of the current address with the last one recorded.
+pub fn compare(current: Option<&str>, previous: Option<&str>) -> Egress {
+    match (current, previous) {
+        (Some(current), Some(previous)) if current == previous => {
+            Egress::Unchanged
+        }
+        (Some(_), Some(_)) => Egress::Changed,
+        _ => Egress::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "egress-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn mode(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn the_salt_is_kept_across_loads() {
+        let directory = directory("kept");
+        let salt = Salt::load(&directory).unwrap();
+        assert_eq!(salt, Salt::load(&directory).unwrap());
+        assert_eq!(
+            salt.hash("192.0.2.1"),
+            Salt::load(&directory).unwrap().hash("192.0.2.1")
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn a_new_salt_changes_the_hash() {
+        let directory = directory("rotated");
+        let old = Salt::load(&directory).unwrap();
+        let previous = old.hash("192.0.2.1");
+        fs::remove_file(directory.join(SALT_FILE)).unwrap();
+        let new = Salt::load(&directory).unwrap();
+        assert_ne!(old, new);
+        assert_eq!(
+            Egress::Changed,
+            compare(Some(&new.hash("192.0.2.1")), Some(&previous))
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn an_invalid_salt_is_replaced() {
+        let directory = directory("invalid");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join(SALT_FILE), "not a salt").unwrap();
+        let salt = Salt::load(&directory).unwrap();
+        let contents = fs::read_to_string(directory.join(SALT_FILE)).unwrap();
+        assert_eq!(Some(salt), Salt::parse(&contents));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn the_salt_is_readable_only_by_its_owner() {
+        let directory = directory("private");
+        Salt::load(&directory).unwrap();
+        let path = directory.join(SALT_FILE);
+        assert_eq!(SALT_MODE, mode(&path));
+
+        // One left readable by everyone is restricted when it's loaded.
+        fs::set_permissions(&path, Permissions::from_mode(0o644)).unwrap();
+        Salt::load(&directory).unwrap();
+        assert_eq!(SALT_MODE, mode(&path));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn hashes_depend_on_the_salt_and_the_address() {
+        let salt = Salt([0; 16]);
+        let other = Salt([1; 16]);
+        assert_eq!(salt.hash("192.0.2.1"), salt.hash("192.0.2.1"));
+        assert_ne!(salt.hash("192.0.2.1"), salt.hash("192.0.2.2"));
+        assert_ne!(salt.hash("192.0.2.1"), other.hash("192.0.2.1"));
+        assert_eq!(32, salt.hash("192.0.2.1").len());
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,184 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            environment.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Works out whether timedated is there to set the timezone.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::jitter::Jitter;
+use crate::localtime::LocaltimeSetter;
+use crate::setter::{Setter, TimezoneSetter};
+use anyhow::anyhow;
+use dbus::nonblock::{Proxy, SyncConnection};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+const TIMEDATED: &str = "org.freedesktop.timedate1";
+
+/// What to do where there's no system bus, or no timedated on it, as in most
+/// containers.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WithoutTimedated {
+    /// Exit, explaining what's missing.
+    #[default]
+    Fail,
+
+    /// Write /etc/localtime directly, and without a bus, watch the routing
+    /// table for connections.
+    Localtime,
+}
+
+/// What there is to set the timezone with.
+pub enum Environment {
+    /// The system bus, with timedated on it or ready to be activated.
+    Timedated(Arc<SyncConnection>),
+
+    /// No timedated. `bus` is the system bus, if there is one, and `reason`
+    /// says what's missing.
+    Missing {
+        bus: Option<Arc<SyncConnection>>,
+        reason: String,
+    },
+}
+
+/// Whether this looks like it's running in a container, going by the marks
+/// that Docker, Podman and systemd-nspawn leave.
+pub fn in_container() -> bool {
+    Path::new("/.dockerenv").exists()
+        || Path::new("/run/.containerenv").exists()
+        || env::var_os("container").is_some()
+}
+
+async fn has_timedated(
+    connection: Arc<SyncConnection>,
+) -> Result<bool, anyhow::Error> {
+    let proxy = Proxy::new(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        Duration::from_secs(2),
+        connection,
+    );
+    let (running,): (bool,) = proxy
+        .method_call("org.freedesktop.DBus", "NameHasOwner", (TIMEDATED,))
+        .await?;
+    if running {
+        return Ok(true);
+    }
+    let (activatable,): (Vec<String>,) = proxy
+        .method_call("org.freedesktop.DBus", "ListActivatableNames", ())
+        .await?;
+    Ok(activatable.iter().any(|name| TIMEDATED == name))
+}
+
+/// Look for timedated on the system bus, given the outcome of connecting to
+/// it.
+pub async fn probe(
+    connection: Result<Arc<SyncConnection>, anyhow::Error>,
+) -> Environment {
+    let bus = match connection {
+        Ok(bus) => bus,
+        Err(error) => {
+            return Environment::Missing {
+                bus: None,
+                reason: format!("There's no system bus: {}", error),
+            }
+        }
+    };
+    let reason = match has_timedated(bus.clone()).await {
+        Ok(true) => return Environment::Timedated(bus),
+        Ok(false) => "timedated isn't on the system bus".to_string(),
+        Err(error) => format!("Couldn't look for timedated: {}", error),
+    };
+    Environment::Missing {
+        bus: Some(bus),
+        reason,
+    }
+}
+
+/// Choose how to set the timezone: through timedated if it's there, or as
+/// without_timedated says if it's not. Returns the system bus, if there is
+/// one, with the setter.
+pub async fn prepare(
+    connection: Result<Arc<SyncConnection>, anyhow::Error>,
+    without_timedated: WithoutTimedated,
+    interactive_auth: bool,
+    jitter: &Jitter,
+) -> Result<(Option<Arc<SyncConnection>>, Setter), anyhow::Error> {
+    let (bus, reason) = match probe(connection).await {
+        Environment::Timedated(bus) => {
+            let setter = TimezoneSetter::new(
+                bus.clone(),
+                interactive_auth,
+                jitter.clone(),
+            );
+            return Ok((Some(bus), Setter::Timedated(setter)));
+        }
+        Environment::Missing { bus, reason } => (bus, reason),
+    };
+
+    if WithoutTimedated::Fail == without_timedated {
+        let situation = match in_container() {
+            true => {
+                "This looks like a container, and containers often have \
+                 neither. Either provide both"
+            }
+            false => "Check that dbus and systemd-timedated are installed",
+        };
+        return Err(anyhow!(
+            "{}, so the timezone can't be set. {}, or set without_timedated \
+             = \"localtime\" to write /etc/localtime directly instead (it \
+             must be writable)",
+            reason,
+            situation
+        ));
+    }
+    match bus {
+        Some(_) => warn!(
+            "{}: writing /etc/localtime directly instead \
+             (without_timedated = \"localtime\")",
+            reason
+        ),
+        None => warn!(
+            "{}: writing /etc/localtime directly, and watching the routing \
+             table for connections instead (without_timedated = \
+             \"localtime\")",
+            reason
+        ),
+    }
+    Ok((bus, Setter::Localtime(LocaltimeSetter)))
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,47 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            exit.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Exit statuses, so that scripts can tell outcomes apart.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+/// Anything that went wrong that has no status of its own. Also returned by
+/// `verify` when the timezone doesn't match.
+pub const FAILURE: u8 = 1;
+
+/// `verify` couldn't tell whether the timezone is right.
+pub const UNKNOWN: u8 = 2;
+
+/// No connection came up within `--wait-timeout`.
+pub const NO_CONNECTIVITY: u8 = 3;
+
+/// polkit wouldn't allow the timezone to be set.
+pub const DENIED: u8 = 4;
+
+///////////////////////////////////////////////////////////////////////////////
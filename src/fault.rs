@@ -0,0 +1,175 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            fault.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Faults injected on request, to test how the daemon copes with them.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+#[cfg(feature = "faults")]
+use anyhow::anyhow;
+#[cfg(feature = "faults")]
+use log::warn;
+#[cfg(feature = "faults")]
+use std::collections::HashMap;
+#[cfg(feature = "faults")]
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// The variable the schedule is read from, e.g.
+/// `IAT_FAULTS=provider-timeout*2,connectivity-delay=5s`.
+#[cfg(feature = "faults")]
+const VARIABLE: &str = "IAT_FAULTS";
+
+/// A fault, by where it's injected.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Fault {
+    /// A provider call times out.
+    ProviderTimeout,
+
+    /// SetTimezone fails with AccessDenied.
+    SetTimezoneDenied,
+
+    /// SetTimezone fails with NoReply.
+    SetTimezoneNoReply,
+
+    /// The public IP lookup that starts a detection is delayed.
+    ConnectivityDelay,
+
+    /// The backend's signal stream is lost.
+    Disconnect,
+}
+
+#[cfg(feature = "faults")]
+impl Fault {
+    const ALL: [(Fault, &'static str); 5] = [
+        (Fault::ProviderTimeout, "provider-timeout"),
+        (Fault::SetTimezoneDenied, "set-timezone-denied"),
+        (Fault::SetTimezoneNoReply, "set-timezone-no-reply"),
+        (Fault::ConnectivityDelay, "connectivity-delay"),
+        (Fault::Disconnect, "disconnect"),
+    ];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, known)| *known == name)
+            .map(|(fault, _)| *fault)
+    }
+}
+
+/// Decides whether a fault happens, each time it could.
+trait Injector: Send + Sync {
+    /// Whether `fault` happens now, and if it takes time, how long.
+    fn take(&self, fault: Fault) -> Option<Duration>;
+}
+
+/// What's used unless a schedule is given: nothing ever goes wrong.
+struct Disabled;
+
+impl Injector for Disabled {
+    fn take(&self, _: Fault) -> Option<Duration> {
+        None
+    }
+}
+
+/// How often each fault is still to happen, and for how long.
+#[cfg(feature = "faults")]
+struct Schedule(Mutex<HashMap<Fault, (u32, Duration)>>);
+
+#[cfg(feature = "faults")]
+impl Schedule {
+    /// Parse a comma-separated list of faults, each of the form
+    /// NAME[=DURATION][*COUNT]. Each happens once, unless COUNT says
+    /// otherwise, the next COUNT times its injection point is reached.
+    fn parse(schedule: &str) -> Result<Self, anyhow::Error> {
+        let mut faults = HashMap::new();
+        for entry in schedule.split(',').filter(|entry| !entry.is_empty()) {
+            let (rest, count) = match entry.split_once('*') {
+                Some((rest, count)) => (rest, count.parse()?),
+                None => (entry, 1),
+            };
+            let (name, duration) = match rest.split_once('=') {
+                Some((name, duration)) => {
+                    (name, humantime::parse_duration(duration)?)
+                }
+                None => (rest, Duration::ZERO),
+            };
+            let fault = Fault::parse(name)
+                .ok_or_else(|| anyhow!("Unknown fault {:?}", name))?;
+            faults.insert(fault, (count, duration));
+        }
+        Ok(Self(Mutex::new(faults)))
+    }
+}
+
+#[cfg(feature = "faults")]
+impl Injector for Schedule {
+    fn take(&self, fault: Fault) -> Option<Duration> {
+        let mut faults = self.0.lock().unwrap();
+        let (count, duration) = faults.get_mut(&fault)?;
+        if 0 == *count {
+            return None;
+        }
+        *count -= 1;
+        warn!("Injecting fault {:?}", fault);
+        Some(*duration)
+    }
+}
+
+fn injector() -> &'static dyn Injector {
+    static INJECTOR: OnceLock<Box<dyn Injector>> = OnceLock::new();
+    INJECTOR
+        .get_or_init(|| {
+            #[cfg(feature = "faults")]
+            if let Ok(schedule) = std::env::var(VARIABLE) {
+                match Schedule::parse(&schedule) {
+                    Ok(schedule) => return Box::new(schedule),
+                    Err(error) => warn!("Ignoring {}: {:#}", VARIABLE, error),
+                }
+            }
+            Box::new(Disabled)
+        })
+        .as_ref()
+}
+
+/// Whether `fault` is to happen now. Always false unless built with the
+/// faults feature and asked to by IAT_FAULTS.
+pub fn inject(fault: Fault) -> bool {
+    injector().take(fault).is_some()
+}
+
+/// Wait out `fault`, if it's to happen now.
+pub async fn delay(fault: Fault) {
+    if let Some(duration) = injector().take(fault) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
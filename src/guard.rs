@@ -0,0 +1,190 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            guard.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     The guards on a change of timezone, and what each made of one.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::history::Ruling;
+
+/// One of the checks a change of timezone has to get past.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Guard {
+    /// reject_country_mismatch.
+    Country,
+
+    /// allowed_timezones.
+    Allowed,
+
+    /// min_dwell.
+    Dwell,
+
+    /// quiet_hours, which the change waits for the end of.
+    QuietHours,
+
+    /// A more recent update has already set the timezone.
+    Stale,
+
+    /// require_active_session.
+    Session,
+
+    /// max_changes_per_day.
+    Cap,
+
+    /// The --confirm prompt.
+    Confirm,
+
+    /// The system is already set to the timezone.
+    Unchanged,
+}
+
+impl Guard {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Country => "country",
+            Self::Allowed => "allowed",
+            Self::Dwell => "dwell",
+            Self::QuietHours => "quiet_hours",
+            Self::Stale => "stale",
+            Self::Session => "session",
+            Self::Cap => "cap",
+            Self::Confirm => "confirm",
+            Self::Unchanged => "unchanged",
+        }
+    }
+
+    /// Whether a forced update gets past it.
+    fn is_overridable(&self) -> bool {
+        matches!(self, Self::Dwell | Self::QuietHours | Self::Cap)
+    }
+
+    /// What the guard makes of a change that it's in the way of if `blocks`.
+    pub fn rule(&self, blocks: bool, force: bool) -> Ruling {
+        match blocks {
+            false => Ruling::Pass,
+            true if force && self.is_overridable() => Ruling::Overridden,
+            true => Ruling::Block,
+        }
+    }
+}
+
+/// Rule on a change with guards that are checked at the same point, each
+/// with whether it's in the way. Every one of them is ruled on, so that all
+/// those in the way are reported, but it's the first to block, if any, that
+/// decides.
+pub fn rule(
+    checks: &[(Guard, bool)],
+    force: bool,
+) -> (Vec<(Guard, Ruling)>, Option<Guard>) {
+    let rulings: Vec<(Guard, Ruling)> = checks
+        .iter()
+        .map(|(guard, blocks)| (*guard, guard.rule(*blocks, force)))
+        .collect();
+    let blocked = rulings
+        .iter()
+        .find(|(_, ruling)| Ruling::Block == *ruling)
+        .map(|(guard, _)| *guard);
+    (rulings, blocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Guard; 9] = [
+        Guard::Country,
+        Guard::Allowed,
+        Guard::Dwell,
+        Guard::QuietHours,
+        Guard::Stale,
+        Guard::Session,
+        Guard::Cap,
+        Guard::Confirm,
+        Guard::Unchanged,
+    ];
+
+    fn ruled(rulings: &[(Guard, Ruling)], ruling: Ruling) -> Vec<Guard> {
+        rulings
+            .iter()
+            .filter(|(_, given)| ruling == *given)
+            .map(|(guard, _)| *guard)
+            .collect()
+    }
+
+    #[test]
+    fn nothing_in_the_way_passes() {
+        let checks: Vec<(Guard, bool)> =
+            ALL.iter().map(|guard| (*guard, false)).collect();
+        let (rulings, blocked) = rule(&checks, false);
+        assert_eq!(9, rulings.len());
+        assert!(rulings.iter().all(|(_, ruling)| Ruling::Pass == *ruling));
+        assert_eq!(None, blocked);
+    }
+
+    #[test]
+    fn every_guard_in_the_way_is_reported_and_the_first_decides() {
+        let checks = [
+            (Guard::Country, false),
+            (Guard::Allowed, true),
+            (Guard::Dwell, true),
+        ];
+        let (rulings, blocked) = rule(&checks, false);
+        assert_eq!(
+            vec![Guard::Allowed, Guard::Dwell],
+            ruled(&rulings, Ruling::Block)
+        );
+        assert_eq!(Some(Guard::Allowed), blocked);
+    }
+
+    #[test]
+    fn forcing_gets_past_only_some_guards() {
+        let checks: Vec<(Guard, bool)> =
+            ALL.iter().map(|guard| (*guard, true)).collect();
+        let (rulings, blocked) = rule(&checks, true);
+        assert_eq!(
+            vec![Guard::Dwell, Guard::QuietHours, Guard::Cap],
+            ruled(&rulings, Ruling::Overridden)
+        );
+        assert_eq!(6, ruled(&rulings, Ruling::Block).len());
+        assert_eq!(Some(Guard::Country), blocked);
+
+        // Unforced, they all block.
+        let (rulings, _) = rule(&checks, false);
+        assert_eq!(9, ruled(&rulings, Ruling::Block).len());
+    }
+
+    #[test]
+    fn a_forced_update_gets_past_the_guards_it_overrides() {
+        let checks = [(Guard::Stale, false), (Guard::Cap, true)];
+        assert_eq!(None, rule(&checks, true).1);
+        assert_eq!(Some(Guard::Cap), rule(&checks, false).1);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
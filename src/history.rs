@@ -0,0 +1,207 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            history.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     The most recent detection attempts, kept in memory for diagnosis.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::location::Coordinates;
+use crate::queue::Source;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// How a detection attempt ended.
+#[derive(Clone, Debug)]
+pub enum Outcome {
+    /// A provider determined the timezone.
+    Timezone(String),
+
+    /// No provider could, for a reason of this class, e.g. "tls".
+    Error(&'static str),
+}
+
+/// What came of comparing a detected timezone with the country the access
+/// point advertises.
+#[derive(Clone, Debug)]
+pub enum CountryCheck {
+    /// The backend can't read the country access points advertise.
+    Unavailable,
+    Advertised {
+        country: String,
+
+        /// Whether the timezone is used in `country`.
+        matches: bool,
+    },
+}
+
+/// What one of the guards on a change made of a detected timezone.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ruling {
+    Pass,
+    Block,
+
+    /// It would have blocked, but the update was forced.
+    Overridden,
+}
+
+impl fmt::Display for Ruling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            Ruling::Pass => "pass",
+            Ruling::Block => "block",
+            Ruling::Overridden => "overridden",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One consultation of the providers.
+#[derive(Clone, Debug)]
+pub struct Attempt {
+    pub time: SystemTime,
+
+    /// The sequence number of the trigger it was made for.
+    pub sequence: u64,
+
+    /// What asked for it, if anything did.
+    pub source: Option<Source>,
+
+    /// The connectivity session it was made in, if any.
+    pub session: Option<String>,
+    pub ssid: Option<String>,
+
+    /// The provider that answered, if one did.
+    pub provider: Option<String>,
+    pub latency: Duration,
+    pub outcome: Outcome,
+
+    /// With a timezone, how it compared with the access point's country, if
+    /// the access point advertised one or the backend couldn't tell.
+    pub country: Option<CountryCheck>,
+
+    /// Where the provider placed this host, rounded to a tenth of a degree.
+    pub coordinates: Option<Coordinates>,
+
+    /// What each guard made of the timezone, in the order they're checked.
+    pub guards: Vec<(&'static str, Ruling)>,
+
+    /// Whether the timezone detected was then set.
+    pub applied: bool,
+
+    /// Whether it was set, then set back because a critical hook failed.
+    pub rolled_back: bool,
+}
+
+/// A ring buffer of the last few attempts. It's never written anywhere, since
+/// it may hold SSIDs and locations.
+pub struct History {
+    capacity: usize,
+    attempts: VecDeque<Attempt>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            attempts: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, mut attempt: Attempt) {
+        if 0 == self.capacity {
+            return;
+        }
+        if self.attempts.len() == self.capacity {
+            self.attempts.pop_front();
+        }
+        attempt.coordinates =
+            attempt.coordinates.map(|coordinates| Coordinates {
+                latitude: (coordinates.latitude * 10.0).round() / 10.0,
+                longitude: (coordinates.longitude * 10.0).round() / 10.0,
+            });
+        self.attempts.push_back(attempt);
+    }
+
+    /// Record that the timezone detected for the trigger `sequence` was set.
+    pub fn applied(&mut self, sequence: u64) {
+        let attempt = self
+            .attempts
+            .iter_mut()
+            .rev()
+            .find(|attempt| attempt.sequence == sequence);
+        if let Some(attempt) = attempt {
+            attempt.applied = true;
+        }
+    }
+
+    /// Record that the timezone detected for the trigger `sequence` was set
+    /// back, because a critical hook failed.
+    pub fn rolled_back(&mut self, sequence: u64) {
+        let attempt = self
+            .attempts
+            .iter_mut()
+            .rev()
+            .find(|attempt| attempt.sequence == sequence);
+        if let Some(attempt) = attempt {
+            attempt.applied = false;
+            attempt.rolled_back = true;
+        }
+    }
+
+    /// Record what `guard` made of the timezone detected for the trigger
+    /// `sequence`. A guard that's checked again, as quiet_hours are once
+    /// apply_delay has passed, keeps its place with the new ruling.
+    pub fn ruled(
+        &mut self,
+        sequence: u64,
+        guard: &'static str,
+        ruling: Ruling,
+    ) {
+        let attempt = self
+            .attempts
+            .iter_mut()
+            .rev()
+            .find(|attempt| attempt.sequence == sequence);
+        let Some(attempt) = attempt else {
+            return;
+        };
+        match attempt.guards.iter_mut().find(|(name, _)| guard == *name) {
+            Some(entry) => entry.1 = ruling,
+            None => attempt.guards.push((guard, ruling)),
+        }
+    }
+
+    /// The attempts, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Attempt> {
+        self.attempts.iter()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,185 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            hook.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Commands run after the timezone changes.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use anyhow::anyhow;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    /// The program and its arguments. As for the exec provider, `shell`
+    /// passes the words joined to `/bin/sh -c` instead.
+    pub command: Vec<String>,
+
+    #[serde(default)]
+    pub shell: bool,
+
+    #[serde(default = "default_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+
+    /// With transactional_hooks, the change is rolled back if this hook
+    /// fails or times out.
+    #[serde(default)]
+    pub critical: bool,
+}
+
+impl HookConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.timeout.is_zero() {
+            problems.push("timeout must not be zero".into());
+        }
+        match self.command.first() {
+            None => problems.push("Hook requires a command".into()),
+            Some(program) if !self.shell && program.starts_with('/') => {
+                if !Path::new(program).is_file() {
+                    problems.push(format!("{} does not exist", program));
+                }
+            }
+            Some(_) => {}
+        }
+        problems
+    }
+
+    fn name(&self) -> &str {
+        self.command.first().map(String::as_str).unwrap_or("hook")
+    }
+
+    fn command(&self) -> Command {
+        if self.shell {
+            let mut command = Command::new("/bin/sh");
+            command.arg("-c").arg(self.command.join(" "));
+            command
+        } else {
+            let mut command = Command::new(&self.command[0]);
+            command.args(&self.command[1..]);
+            command
+        }
+    }
+
+    async fn run(
+        &self,
+        timezone: &str,
+        previous: Option<&str>,
+        rollback: bool,
+    ) -> Result<(), anyhow::Error> {
+        let mut command = self.command();
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .env("TZHOOK_TIMEZONE", timezone);
+        if let Some(previous) = previous {
+            command.env("TZHOOK_PREVIOUS", previous);
+        }
+        if rollback {
+            command.env("TZHOOK_ROLLBACK", "1");
+        }
+
+        let output = tokio::time::timeout(self.timeout, command.output())
+            .await
+            .map_err(|_| anyhow!("Timed out after {:?}", self.timeout))??;
+        let name = self.name();
+        for line in String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .chain(String::from_utf8_lossy(&output.stderr).lines())
+        {
+            debug!("{}: {}", name, line);
+        }
+        if !output.status.success() {
+            return Err(anyhow!("Command exited with {}", output.status));
+        }
+        Ok(())
+    }
+}
+
+/// The hooks, run in order each time the timezone is set to something new.
+pub struct Hooks {
+    hooks: Vec<HookConfig>,
+    transactional: bool,
+}
+
+impl Hooks {
+    pub fn new(hooks: Vec<HookConfig>, transactional: bool) -> Self {
+        Self {
+            hooks,
+            transactional,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run the hooks for a change from `previous` to `timezone`. A failure
+    /// is logged and the next hook run, unless it was of a critical hook in
+    /// transactional mode, in which case the rest are skipped and the
+    /// failure returned for the change to be rolled back. A `rollback` is
+    /// never itself rolled back.
+    pub async fn run(
+        &self,
+        timezone: &str,
+        previous: Option<&str>,
+        rollback: bool,
+    ) -> Result<(), anyhow::Error> {
+        for hook in &self.hooks {
+            let name = hook.name();
+            let error = match hook.run(timezone, previous, rollback).await {
+                Ok(()) => {
+                    debug!("Hook {} succeeded", name);
+                    continue;
+                }
+                Err(error) => error,
+            };
+            if hook.critical && self.transactional && !rollback {
+                return Err(
+                    error.context(format!("Critical hook {} failed", name))
+                );
+            }
+            warn!("Hook {} failed: {:#}", name, error);
+        }
+        Ok(())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
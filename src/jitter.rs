@@ -0,0 +1,120 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            jitter.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Random variation in delays.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use std::time::Duration;
+
+/// Stretches or shrinks delays by a random amount, up to a fraction either
+/// way, so that hosts which all reconnect at once don't all reach the
+/// providers in the same second.
+#[derive(Clone, Debug)]
+pub struct Jitter {
+    fraction: f64,
+    rng: fastrand::Rng,
+}
+
+impl Jitter {
+    /// A `fraction` of zero leaves delays as they are.
+    pub fn new(fraction: f64) -> Self {
+        Self {
+            fraction,
+            rng: fastrand::Rng::new(),
+        }
+    }
+
+    /// Like [Jitter::new], but with the same random factors every time.
+    #[cfg(test)]
+    fn seeded(fraction: f64, seed: u64) -> Self {
+        Self {
+            fraction,
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+
+    pub fn apply(&self, delay: Duration) -> Duration {
+        if 0.0 == self.fraction {
+            return delay;
+        }
+        let factor = 1.0 + self.fraction * (2.0 * self.rng.f64() - 1.0);
+        delay.mul_f64(factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    const DELAY: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn no_jitter_leaves_delays_as_they_are() {
+        let jitter = Jitter::new(0.0);
+        for _ in 0..100 {
+            assert_eq!(DELAY, jitter.apply(DELAY));
+        }
+    }
+
+    #[test]
+    fn jitter_stays_within_the_band() {
+        let jitter = Jitter::seeded(0.2, 1);
+        let delays: Vec<Duration> =
+            (0..1000).map(|_| jitter.apply(DELAY)).collect();
+        assert!(delays.iter().all(|delay| *delay >= DELAY.mul_f64(0.8)
+            && *delay <= DELAY.mul_f64(1.2)));
+        // Both ways, and not all the same.
+        assert!(delays.iter().any(|delay| *delay < DELAY));
+        assert!(delays.iter().any(|delay| *delay > DELAY));
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_delays() {
+        let (first, second) = (Jitter::seeded(0.2, 7), Jitter::seeded(0.2, 7));
+        for _ in 0..100 {
+            assert_eq!(first.apply(DELAY), second.apply(DELAY));
+        }
+        let other = Jitter::seeded(0.2, 8);
+        let delays: Vec<Duration> =
+            (0..10).map(|_| first.apply(DELAY)).collect();
+        let others: Vec<Duration> =
+            (0..10).map(|_| other.apply(DELAY)).collect();
+        assert_ne!(delays, others);
+    }
+
+    #[test]
+    fn jitter_is_on_by_default() {
+        let fraction = Config::default().jitter;
+        assert!(0.0 < fraction && fraction < 1.0);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
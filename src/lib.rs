@@ -0,0 +1,93 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            lib.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     The library behind the daemon, and the API for detecting the
+//                  timezone from other crates.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+//! Updates the system timezone whenever a network connection comes up.
+//!
+//! The supported interface for embedding is [`Detector`], which consults the
+//! configured providers the way the daemon does, without D-Bus. It, and the
+//! types it takes and returns, only change with the major version. The rest
+//! is public only so that the binary can use it.
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+
+mod boot;
+mod capabilities;
+#[doc(hidden)]
+pub mod command;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod confirm;
+mod connectivity;
+mod control;
+#[doc(hidden)]
+pub mod daemon;
+mod detector;
+mod dns;
+mod egress;
+mod environment;
+mod exit;
+mod fault;
+mod guard;
+mod history;
+mod hook;
+mod jitter;
+mod localtime;
+mod location;
+mod monitor;
+mod ntp;
+mod provider;
+mod queue;
+mod quiet;
+#[cfg(feature = "tzf")]
+mod resolver;
+mod session;
+mod setter;
+mod signals;
+mod skip;
+mod socket;
+mod state;
+mod statistics;
+mod status;
+#[cfg(test)]
+mod test_bus;
+mod throttle;
+mod zone;
+
+pub use config::Config;
+pub use detector::{Detection, Detector};
+pub use location::Coordinates;
+
+///////////////////////////////////////////////////////////////////////////////
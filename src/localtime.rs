@@ -0,0 +1,138 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            localtime.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Sets the timezone by writing /etc/localtime, without timedated.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::setter::Outcome;
+use crate::zone;
+use anyhow::{anyhow, Context};
+use log::debug;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+const LOCALTIME: &str = "/etc/localtime";
+
+/// Debian and its derivatives also record the name here.
+const TIMEZONE: &str = "/etc/timezone";
+
+/// Sets the timezone the way timedated would, for systems (mostly
+/// containers) that don't have it. /etc has to be writable, or /etc/localtime
+/// bind-mounted writable.
+#[derive(Default)]
+pub struct LocaltimeSetter;
+
+/// The zone a path into the tz database names, e.g. Europe/Paris for
+/// /usr/share/zoneinfo/Europe/Paris.
+fn zone_of(path: &Path) -> Option<String> {
+    let text = path.to_str()?;
+    let (_, name) = text.rsplit_once("zoneinfo/")?;
+    Some(name.to_string())
+}
+
+impl LocaltimeSetter {
+    /// Read the timezone /etc/localtime is set to, from where it links to,
+    /// or failing that, from /etc/timezone.
+    pub fn current_timezone(&self) -> Result<String, anyhow::Error> {
+        if let Some(name) =
+            fs::read_link(LOCALTIME).ok().as_deref().and_then(zone_of)
+        {
+            return Ok(name);
+        }
+        fs::read_to_string(TIMEZONE)
+            .map(|contents| contents.trim().to_string())
+            .ok()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Couldn't read the current timezone: {} isn't a link \
+                     into the tz database, and there's no {}",
+                    LOCALTIME,
+                    TIMEZONE
+                )
+            })
+    }
+
+    /// Set the timezone to `timezone` (or the zone it's an alias for), unless
+    /// it already is.
+    pub fn apply(&self, timezone: &str) -> Result<Outcome, anyhow::Error> {
+        let timezone = zone::canonicalize(timezone);
+        zone::validate(&timezone)?;
+        match self.current_timezone() {
+            Ok(current) if zone::is_same(&current, &timezone) => {
+                return Ok(Outcome::Unchanged)
+            }
+            Ok(_) => {}
+            Err(error) => debug!("{:#}", error),
+        }
+
+        let target = zone::directory().join(&timezone);
+        if let Err(error) = Self::link(&target) {
+            // A bind-mounted /etc/localtime can't be replaced, only written
+            // through. Writing through a link would overwrite the zone it
+            // points to in the tz database, so that's only done to a file.
+            let regular = fs::symlink_metadata(LOCALTIME)
+                .is_ok_and(|metadata| metadata.file_type().is_file());
+            if !regular {
+                return Err(error);
+            }
+            debug!("Couldn't link {}: {:#}", LOCALTIME, error);
+            let contents = fs::read(&target).with_context(|| {
+                format!("Couldn't read {}", target.display())
+            })?;
+            fs::write(LOCALTIME, contents)
+                .with_context(|| format!("Couldn't write {}", LOCALTIME))?;
+        }
+        if Path::new(TIMEZONE).exists() {
+            if let Err(error) = fs::write(TIMEZONE, format!("{}\n", timezone))
+            {
+                debug!("Couldn't write {}: {}", TIMEZONE, error);
+            }
+        }
+        Ok(Outcome::Set)
+    }
+
+    /// Point /etc/localtime at `target`, replacing it atomically.
+    fn link(target: &Path) -> Result<(), anyhow::Error> {
+        let temporary = PathBuf::from(format!("{}.tmp", LOCALTIME));
+        let _ = fs::remove_file(&temporary);
+        symlink(target, &temporary).with_context(|| {
+            format!("Couldn't create {}", temporary.display())
+        })?;
+        fs::rename(&temporary, LOCALTIME).map_err(|error| {
+            let _ = fs::remove_file(&temporary);
+            anyhow::Error::new(error)
+                .context(format!("Couldn't replace {}", LOCALTIME))
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -8,7 +8,7 @@
 //
 // CREATED:         12/27/2022
 //
-// LAST EDITED:	    12/29/2022
+// LAST EDITED:	    10/14/2026
 //
 ////
 // Copyright 2022, Ethan D. Twardy
@@ -32,101 +32,188 @@
 // IN THE SOFTWARE.
 ////
 
-use core::time::Duration;
-use dbus::arg::Variant;
-use dbus::message::MatchRule;
-use dbus::nonblock::{Proxy, SyncConnection};
-use dbus::Message;
-use dbus_tokio::connection;
-use futures_channel::mpsc::UnboundedReceiver;
-use futures_util::stream::StreamExt;
-use reqwest;
-use std::collections::HashMap;
-use std::sync::Arc;
-
-struct ZoneClient<'a> {
-    proxy: Proxy<'a, Arc<SyncConnection>>,
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+
+use clap::{Parser, Subcommand};
+use iwd_auto_timezone::confirm::Confirmer;
+use iwd_auto_timezone::{command, config, daemon, Config};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Path to the configuration file.
+    #[arg(long, global = true, default_value = config::DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+
+    /// Override a setting, e.g. --set interactive_auth=true. May be given
+    /// more than once.
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+
+    /// Update the timezone once, and exit.
+    #[arg(long)]
+    once: bool,
+
+    /// With --once, wait this long for a connection to come up before
+    /// updating. Exits with status 3 if none does.
+    #[arg(long, requires = "once", value_parser = humantime::parse_duration)]
+    wait_timeout: Option<Duration>,
+
+    /// Ask before changing the timezone. Only allowed when stdin is a
+    /// terminal.
+    #[arg(long)]
+    confirm: bool,
+
+    /// How long to wait for an answer with --confirm before assuming no.
+    #[arg(long, default_value = "30s", value_parser = humantime::parse_duration)]
+    confirm_timeout: Duration,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-impl ZoneClient<'_> {
-    pub fn new(connection: Arc<SyncConnection>) -> Self {
-        Self {
-            proxy: Proxy::new(
-                "org.freedesktop.timedate1",
-                "/org/freedesktop/timedate1",
-                Duration::from_secs(2),
-                connection,
-            ),
-        }
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Validate the configuration without starting the daemon.
+    Check {
+        /// Also query each provider, to check that it is reachable.
+        #[arg(long)]
+        probe: bool,
+    },
 
-    pub async fn update_timezone(&self) -> Result<(), anyhow::Error> {
-        // Obtain timezone based on IP address, using open Geo-IP service.
-        let timezone = reqwest::get("https://ipapi.co/timezone")
-            .await?
-            .text()
-            .await?;
-        println!("Setting timezone to {}", timezone);
-
-        // Then, call SetTimezone method of interface org.freedesktop.timedate1
-        // of object /org/freedesktop/timedate1 on service
-        // org.freedesktop.timedate1
-        self.proxy
-            .method_call(
-                "org.freedesktop.timedate1",
-                "SetTimezone",
-                (timezone, false),
-            )
-            .await?;
-        Ok(())
-    }
+    /// Detect the timezone and print it, with the provider that answered,
+    /// without setting it.
+    Detect {
+        /// Print the result as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check for common setup problems: the bus, the connection manager,
+    /// timedated, polkit, the providers and the tz database. Exits 1 if any
+    /// critical check fails.
+    Doctor {
+        /// Print the results as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Probe the system and write a configuration file to start from, asking
+    /// a few questions if run on a terminal.
+    Init {
+        /// Where to write it, if not to the path given by --config.
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        /// Replace the file if it already exists.
+        #[arg(long)]
+        force: bool,
+
+        /// Don't ask anything, and write what was found.
+        #[arg(long)]
+        defaults: bool,
+    },
+
+    /// Print the effective configuration, and where each setting came from.
+    PrintConfig {
+        /// Print JSON instead of TOML.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that the system timezone matches the detected one. Exits 0 if
+    /// they match, 1 if they differ and 2 if either can't be determined.
+    Verify {
+        /// Consider zones matching if their current UTC offsets are equal.
+        #[arg(long)]
+        allow_offset_equal: bool,
+
+        /// Print a detailed report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Set the timezone, the same way the daemon would, without consulting
+    /// any providers.
+    Set {
+        /// e.g. Europe/Madrid
+        timezone: String,
+    },
+
+    /// Print the timezone at a location, using the offline boundary data.
+    #[cfg(feature = "tzf")]
+    Resolve {
+        #[arg(long, allow_negative_numbers = true)]
+        lat: f64,
+        #[arg(long, allow_negative_numbers = true)]
+        lon: f64,
+    },
 }
 
 #[tokio::main]
-pub async fn main() -> Result<(), anyhow::Error> {
-    let (resource, system_bus) = connection::new_system_sync()?;
-
-    // The resource is a task that should be spawned onto a tokio compatible
-    // reactor ASAP. If the resource ever finishes, you lost connection to
-    // D-Bus.
-    //
-    // To shut down the connection, both call _handle.abort() and drop the
-    // connection.
-    let _context = tokio::spawn(async {
-        let error = resource.await;
-        panic!("Lost connection to D-Bus: {}", error);
-    });
-
-    // Listen for changes on interface "net.connman.iwd.Station", property
-    // State (to "connected")
-    let rule = MatchRule::new_signal(
-        "org.freedesktop.DBus.Properties",
-        "PropertiesChanged",
+pub async fn main() -> Result<ExitCode, anyhow::Error> {
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("info"),
     )
-    .with_sender("net.connman.iwd");
-    let (signal, mut stream): (_, UnboundedReceiver<(Message, (String,))>) =
-        system_bus.add_match(rule).await?.stream();
-
-    let client = ZoneClient::new(system_bus.clone());
+    .init();
+    let args = Args::parse();
 
-    while let Some((signal, (_interface,))) = stream.next().await {
-        let (interface, changed): (String, HashMap<String, Variant<String>>) =
-            signal.read2()?;
-        if "net.connman.iwd.Station" != interface {
-            continue;
+    match args.command {
+        Some(Command::Check { probe }) => {
+            command::check::run(&args.config, &args.overrides, probe).await
         }
-
-        let property =
-            changed.iter().find(|(name, _)| "State" == name.as_str());
-        if let Some((_, state)) = property {
-            if "connected" == state.0 {
-                client.update_timezone().await?;
+        Some(Command::Detect { json }) => {
+            command::detect::run(&args.config, &args.overrides, json).await
+        }
+        Some(Command::Doctor { json }) => {
+            command::doctor::run(&args.config, &args.overrides, json).await
+        }
+        Some(Command::Init {
+            path,
+            force,
+            defaults,
+        }) => {
+            let path = path.unwrap_or(args.config);
+            command::init::run(&path, force, defaults).await
+        }
+        Some(Command::PrintConfig { json }) => {
+            command::print_config::run(&args.config, &args.overrides, json)
+        }
+        Some(Command::Verify {
+            allow_offset_equal,
+            json,
+        }) => {
+            command::verify::run(
+                &args.config,
+                &args.overrides,
+                allow_offset_equal,
+                json,
+            )
+            .await
+        }
+        Some(Command::Set { timezone }) => {
+            command::set::run(&args.config, &args.overrides, &timezone).await
+        }
+        #[cfg(feature = "tzf")]
+        Some(Command::Resolve { lat, lon }) => command::resolve::run(lat, lon),
+        None => {
+            let config = Config::load_valid(&args.config, &args.overrides)?;
+            let confirmer = match args.confirm {
+                true => Some(Confirmer::new(args.confirm_timeout)?),
+                false => None,
+            };
+            if args.once {
+                return daemon::once(config, confirmer, args.wait_timeout)
+                    .await;
             }
+            daemon::run(config, confirmer).await?;
+            Ok(ExitCode::SUCCESS)
         }
     }
-
-    system_bus.remove_match(signal.token()).await?;
-    unreachable!()
 }
 
 ///////////////////////////////////////////////////////////////////////////////
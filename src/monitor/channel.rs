@@ -0,0 +1,198 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            channel.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Bounded channel for connection events, which drops superseded
+//                  events instead of blocking or growing when full.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::Event;
+use anyhow::anyhow;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// How many events may wait for the daemon before older ones are dropped.
+pub const CAPACITY: usize = 64;
+
+struct Inner {
+    events: VecDeque<Event>,
+    #[cfg_attr(
+        not(any(
+            feature = "backend-iwd",
+            feature = "backend-wpa-supplicant",
+            feature = "backend-modem-manager",
+            feature = "backend-netlink"
+        )),
+        allow(dead_code)
+    )]
+    capacity: usize,
+    dropped: u64,
+    senders: usize,
+    receiving: bool,
+}
+
+impl Inner {
+    /// Make room for `event`. An event already waiting for the same station
+    /// is superseded by it; failing that, the oldest event for any station
+    /// that has a later one waiting is; failing that, the oldest event is.
+    #[cfg_attr(
+        not(any(
+            feature = "backend-iwd",
+            feature = "backend-wpa-supplicant",
+            feature = "backend-modem-manager",
+            feature = "backend-netlink"
+        )),
+        allow(dead_code)
+    )]
+    fn make_room(&mut self, event: &Event) {
+        let station = event.station();
+        let superseded = self
+            .events
+            .iter()
+            .position(|queued| queued.station() == station)
+            .or_else(|| {
+                self.events.iter().enumerate().position(|(index, queued)| {
+                    self.events
+                        .iter()
+                        .skip(index + 1)
+                        .any(|later| later.station() == queued.station())
+                })
+            })
+            .unwrap_or(0);
+        self.events.remove(superseded);
+        self.dropped += 1;
+    }
+}
+
+struct Shared {
+    inner: Mutex<Inner>,
+    notify: Notify,
+}
+
+/// Sends events to the daemon. Never waits: when the channel is full, an
+/// older event is dropped to make room.
+pub struct EventSender(Arc<Shared>);
+
+pub struct EventReceiver(Arc<Shared>);
+
+pub fn channel(capacity: usize) -> (EventSender, EventReceiver) {
+    let shared = Arc::new(Shared {
+        inner: Mutex::new(Inner {
+            events: VecDeque::new(),
+            capacity,
+            dropped: 0,
+            senders: 1,
+            receiving: true,
+        }),
+        notify: Notify::new(),
+    });
+    (EventSender(shared.clone()), EventReceiver(shared))
+}
+
+impl EventSender {
+    #[cfg_attr(
+        not(any(
+            feature = "backend-iwd",
+            feature = "backend-wpa-supplicant",
+            feature = "backend-modem-manager",
+            feature = "backend-netlink"
+        )),
+        allow(dead_code)
+    )]
+    pub fn send(&self, event: Event) -> Result<(), anyhow::Error> {
+        let mut inner = self.0.inner.lock().unwrap();
+        if !inner.receiving {
+            return Err(anyhow!("Nothing is receiving events"));
+        }
+        if inner.events.len() >= inner.capacity {
+            inner.make_room(&event);
+        }
+        inner.events.push_back(event);
+        drop(inner);
+        self.0.notify.notify_one();
+        Ok(())
+    }
+
+    /// Whether the receiver has gone away.
+    pub fn is_closed(&self) -> bool {
+        !self.0.inner.lock().unwrap().receiving
+    }
+}
+
+impl Clone for EventSender {
+    fn clone(&self) -> Self {
+        self.0.inner.lock().unwrap().senders += 1;
+        Self(self.0.clone())
+    }
+}
+
+impl Drop for EventSender {
+    fn drop(&mut self) {
+        self.0.inner.lock().unwrap().senders -= 1;
+        self.0.notify.notify_one();
+    }
+}
+
+impl EventReceiver {
+    /// Wait for the next event. Returns None once every sender has gone and
+    /// there are no events left.
+    pub async fn recv(&mut self) -> Option<Event> {
+        loop {
+            {
+                let mut inner = self.0.inner.lock().unwrap();
+                if let Some(event) = inner.events.pop_front() {
+                    return Some(event);
+                }
+                if 0 == inner.senders {
+                    return None;
+                }
+            }
+            self.0.notify.notified().await;
+        }
+    }
+
+    /// The next event, if there's one waiting.
+    pub fn try_recv(&mut self) -> Option<Event> {
+        self.0.inner.lock().unwrap().events.pop_front()
+    }
+
+    /// How many events have been dropped to make room for later ones.
+    pub fn dropped(&self) -> u64 {
+        self.0.inner.lock().unwrap().dropped
+    }
+}
+
+impl Drop for EventReceiver {
+    fn drop(&mut self) {
+        self.0.inner.lock().unwrap().receiving = false;
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,691 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            iwd.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Monitor for stations managed by iwd.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::{Event, EventSender};
+use crate::provider::Context;
+use crate::signals::{
+    self, MessageReceiver, MessageSender, PropertiesChanged,
+};
+use dbus::arg::{self, PropMap};
+use dbus::channel::{MatchingReceiver, Token};
+use dbus::message::MatchRule;
+use dbus::nonblock::stdintf::org_freedesktop_dbus::{
+    ObjectManager, Properties,
+};
+use dbus::nonblock::{MsgMatch, Proxy, SyncConnection};
+use dbus::Message;
+use log::{debug, info};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub const SERVICE: &str = "net.connman.iwd";
+const STATION: &str = "net.connman.iwd.Station";
+const OBJECT_MANAGER: &str = "org.freedesktop.DBus.ObjectManager";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StationState {
+    Connected,
+    Disconnected,
+    Roaming,
+
+    /// Any of the transitional states, or a state we couldn't read.
+    Other,
+}
+
+/// A change to the State or ConnectedNetwork properties of a station.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StationEvent {
+    pub station: dbus::Path<'static>,
+    pub state: Option<StationState>,
+
+    /// The network the station is now connected to, if that changed.
+    pub network: Option<dbus::Path<'static>>,
+}
+
+/// Interpret a PropertiesChanged signal from iwd. Returns None unless the
+/// signal is for a station and mentions its State or ConnectedNetwork.
+pub fn parse_station_event(message: &Message) -> Option<StationEvent> {
+    let PropertiesChanged {
+        interface,
+        changed,
+        invalidated,
+    } = PropertiesChanged::read(message)?;
+    if STATION != interface {
+        return None;
+    }
+
+    let state = match changed.get("State") {
+        Some(state) => Some(match state.0.as_str() {
+            Some("connected") => StationState::Connected,
+            Some("disconnected") => StationState::Disconnected,
+            Some("roaming") => StationState::Roaming,
+            _ => StationState::Other,
+        }),
+        None if invalidated.iter().any(|name| "State" == name) => {
+            Some(StationState::Other)
+        }
+        None => None,
+    };
+    let network = changed
+        .get("ConnectedNetwork")
+        .and_then(|network| network.0.as_str())
+        .and_then(|network| dbus::Path::new(network.to_string()).ok());
+    if state.is_none() && network.is_none() {
+        return None;
+    }
+
+    Some(StationEvent {
+        station: message.path()?.into_static(),
+        state,
+        network,
+    })
+}
+
+/// A match for the PropertiesChanged signals of one station.
+struct Subscription {
+    /// The rule as given to the bus, which can filter on arg0.
+    filter: String,
+    token: Token,
+}
+
+struct Monitor {
+    connection: Arc<SyncConnection>,
+    events: EventSender,
+
+    /// Everything the subscriptions receive is sent here.
+    messages: MessageSender,
+    subscriptions: HashMap<dbus::Path<'static>, Subscription>,
+
+    /// How many signals the subscriptions have delivered, to measure how
+    /// much the narrower rules save.
+    received: u64,
+
+    /// The network each connected station was connected to when we last
+    /// reported it. Lets us tell roaming within a network, which isn't worth
+    /// reporting, from switching to a different one, which is.
+    networks: HashMap<dbus::Path<'static>, dbus::Path<'static>>,
+}
+
+impl Monitor {
+    /// Start receiving changes to the properties of `station`. Only its
+    /// Station interface is of interest, so the bus is asked to filter on
+    /// arg0, which MatchRule can't express.
+    async fn subscribe(
+        &mut self,
+        station: dbus::Path<'static>,
+    ) -> Result<(), anyhow::Error> {
+        if self.subscriptions.contains_key(&station) {
+            return Ok(());
+        }
+        let rule = MatchRule::new_signal(
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+        )
+        .with_sender(SERVICE)
+        .with_path(station.clone());
+        let filter = format!("{},arg0='{}'", rule.match_str(), STATION);
+        self.connection.add_match_no_cb(&filter).await?;
+        let messages = self.messages.clone();
+        let token = self.connection.start_receive(
+            rule,
+            Box::new(move |message, _| messages.send(message)),
+        );
+        debug!("Watching station {}", station);
+        self.subscriptions
+            .insert(station, Subscription { filter, token });
+        Ok(())
+    }
+
+    async fn unsubscribe(&mut self, station: &dbus::Path<'static>) {
+        self.networks.remove(station);
+        let Some(subscription) = self.subscriptions.remove(station) else {
+            return;
+        };
+        debug!("No longer watching station {}", station);
+        self.connection.stop_receive(subscription.token);
+        let result = self
+            .connection
+            .remove_match_no_cb(&subscription.filter)
+            .await;
+        if let Err(error) = result {
+            debug!("Couldn't remove match: {}", error);
+        }
+    }
+
+    async fn get_network(
+        &self,
+        station: &dbus::Path<'_>,
+    ) -> Result<dbus::Path<'static>, anyhow::Error> {
+        let proxy =
+            Proxy::new(SERVICE, station, TIMEOUT, self.connection.clone());
+        Ok(proxy.get(STATION, "ConnectedNetwork").await?)
+    }
+
+    /// Read the name (SSID) of `network`.
+    async fn get_name(
+        &self,
+        network: &dbus::Path<'_>,
+    ) -> Result<String, anyhow::Error> {
+        let proxy =
+            Proxy::new(SERVICE, network, TIMEOUT, self.connection.clone());
+        Ok(proxy.get("net.connman.iwd.Network", "Name").await?)
+    }
+
+    /// Report that `station` has connected to `network` (which is read from
+    /// the station if it isn't known), unless we already reported it
+    /// connected to that network.
+    async fn connected(
+        &mut self,
+        station: dbus::Path<'static>,
+        network: Option<dbus::Path<'static>>,
+    ) -> Result<(), anyhow::Error> {
+        let network = match network {
+            Some(network) => Some(network),
+            None => self
+                .get_network(&station)
+                .await
+                .map_err(|error| debug!("Couldn't read network: {}", error))
+                .ok(),
+        };
+
+        let mut ssid = None;
+        if let Some(network) = network {
+            if Some(&network) == self.networks.get(&station) {
+                debug!("{} is still connected to {}", station, network);
+                return Ok(());
+            }
+            ssid = self
+                .get_name(&network)
+                .await
+                .map_err(|error| debug!("Couldn't read SSID: {}", error))
+                .ok();
+            self.networks.insert(station.clone(), network);
+        }
+        self.events.send(Event::Connected(Context {
+            station: Some(station.to_string()),
+            ssid,
+            session: None,
+        }))?;
+        Ok(())
+    }
+
+    /// Handle ConnectedNetwork changing without State changing, as when iwd
+    /// moves to a better network of its own accord.
+    async fn network_changed(
+        &mut self,
+        station: dbus::Path<'static>,
+        network: dbus::Path<'static>,
+    ) -> Result<(), anyhow::Error> {
+        match self.networks.get(&station) {
+            Some(previous) if *previous != network => {
+                info!("{} switched to network {}", station, network);
+                self.connected(station, Some(network)).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn disconnected(
+        &mut self,
+        station: dbus::Path<'static>,
+    ) -> Result<(), anyhow::Error> {
+        self.networks.remove(&station);
+        self.events.send(Event::Disconnected(Context {
+            station: Some(station.to_string()),
+            ssid: None,
+            session: None,
+        }))?;
+        Ok(())
+    }
+
+    async fn handle(
+        &mut self,
+        event: StationEvent,
+    ) -> Result<(), anyhow::Error> {
+        let StationEvent {
+            station,
+            state,
+            network,
+        } = event;
+        match (state, network) {
+            (Some(StationState::Connected), network) => {
+                self.connected(station, network).await
+            }
+            (Some(StationState::Disconnected), _) => {
+                self.disconnected(station)
+            }
+            (Some(StationState::Roaming | StationState::Other), _) => Ok(()),
+            (None, Some(network)) => {
+                self.network_changed(station, network).await
+            }
+            (None, None) => Ok(()),
+        }
+    }
+
+    /// Subscribe to every station iwd already has. When `resumed`, also
+    /// report those that are connected, since we may have missed them
+    /// connecting while we weren't subscribed.
+    async fn discover(&mut self, resumed: bool) -> Result<(), anyhow::Error> {
+        let proxy = Proxy::new(SERVICE, "/", TIMEOUT, self.connection.clone());
+        let objects = proxy.get_managed_objects().await?;
+        for (path, interfaces) in objects {
+            let Some(properties) = interfaces.get(STATION) else {
+                continue;
+            };
+            self.subscribe(path.clone()).await?;
+            let state =
+                properties.get("State").and_then(|state| state.0.as_str());
+            if resumed && Some("connected") == state {
+                self.connected(path, None).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) {
+        let stations: Vec<_> = self.subscriptions.keys().cloned().collect();
+        for station in &stations {
+            self.unsubscribe(station).await;
+        }
+    }
+}
+
+/// Every station iwd has, with its state.
+pub async fn stations(
+    connection: Arc<SyncConnection>,
+) -> Result<Vec<(String, String)>, anyhow::Error> {
+    let proxy = Proxy::new(SERVICE, "/", TIMEOUT, connection);
+    let objects = proxy.get_managed_objects().await?;
+    Ok(objects
+        .into_iter()
+        .filter_map(|(path, interfaces)| {
+            let state = interfaces
+                .get(STATION)?
+                .get("State")
+                .and_then(|state| state.0.as_str())
+                .unwrap_or("unknown")
+                .to_string();
+            Some((path.to_string(), state))
+        })
+        .collect())
+}
+
+/// The Scanning property of a station, if a PropertiesChanged signal for its
+/// Station interface changes it.
+fn scanning(message: &Message) -> Option<bool> {
+    let properties = PropertiesChanged::read(message)?;
+    if STATION != properties.interface {
+        return None;
+    }
+    arg::cast::<bool>(&properties.changed.get("Scanning")?.0).copied()
+}
+
+/// Wait for `station` to finish scanning, for no longer than `limit`. Returns
+/// how long was spent waiting, or None if it wasn't scanning.
+pub async fn wait_for_scan(
+    connection: Arc<SyncConnection>,
+    station: &str,
+    limit: Duration,
+) -> Result<Option<Duration>, anyhow::Error> {
+    let station = dbus::Path::new(station.to_string())
+        .map_err(|error| anyhow::anyhow!("Bad station path: {}", error))?;
+    // Subscribe before reading the property, so that the end of the scan
+    // can't slip between the two. The monitor has a match for the same
+    // station's signals; this one still sees them because the connection
+    // delivers each signal to every match (see daemon::connect).
+    let rule = MatchRule::new_signal(
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+    )
+    .with_sender(SERVICE)
+    .with_path(station.clone());
+    let (signal, mut incoming) = signals::subscribe(&connection, rule).await?;
+
+    let started = Instant::now();
+    let result = async {
+        let proxy = Proxy::new(SERVICE, &station, TIMEOUT, connection.clone());
+        let busy: bool = proxy.get(STATION, "Scanning").await?;
+        if !busy {
+            return Ok(None);
+        }
+        debug!("{} is scanning, waiting up to {:?}", station, limit);
+        let finished = async {
+            while let Some(message) = incoming.recv().await {
+                if Some(false) == scanning(&message) {
+                    return;
+                }
+            }
+        };
+        if tokio::time::timeout(limit, finished).await.is_err() {
+            debug!("{} is still scanning, not waiting any longer", station);
+        }
+        Ok(Some(started.elapsed()))
+    }
+    .await;
+
+    if let Err(error) = connection.remove_match(signal.token()).await {
+        debug!("Couldn't remove match: {}", error);
+    }
+    result
+}
+
+/// The station an InterfacesAdded signal announces, if it's for one.
+fn added_station(message: &Message) -> Option<dbus::Path<'static>> {
+    let (path, interfaces): (dbus::Path, HashMap<String, PropMap>) =
+        match message.read2() {
+            Ok(body) => body,
+            Err(error) => {
+                signals::skip(message, error);
+                return None;
+            }
+        };
+    interfaces.contains_key(STATION).then(|| path.into_static())
+}
+
+/// The station an InterfacesRemoved signal retires, if it's for one.
+fn removed_station(message: &Message) -> Option<dbus::Path<'static>> {
+    let (path, interfaces): (dbus::Path, Vec<String>) = match message.read2() {
+        Ok(body) => body,
+        Err(error) => {
+            signals::skip(message, error);
+            return None;
+        }
+    };
+    interfaces
+        .iter()
+        .any(|interface| STATION == interface)
+        .then(|| path.into_static())
+}
+
+/// Match one of the ObjectManager signals from iwd.
+async fn watch_objects(
+    connection: &SyncConnection,
+    member: &'static str,
+) -> Result<(MsgMatch, MessageReceiver), anyhow::Error> {
+    let rule = MatchRule::new_signal(OBJECT_MANAGER, member)
+        .with_sender(SERVICE)
+        .with_path("/");
+    signals::subscribe(connection, rule).await
+}
+
+/// Report station state changes until the signal stream ends. When
+/// `resumed`, connected stations are reported first.
+pub async fn monitor(
+    connection: Arc<SyncConnection>,
+    events: EventSender,
+    resumed: bool,
+) -> Result<(), anyhow::Error> {
+    // Stations are subscribed to individually as they come and go, rather
+    // than taking every property change iwd emits.
+    let (added, mut added_stream) =
+        watch_objects(&connection, "InterfacesAdded").await?;
+    let (removed, mut removed_stream) =
+        watch_objects(&connection, "InterfacesRemoved").await?;
+    let (messages, mut message_stream) = signals::channel(signals::CAPACITY);
+    let mut monitor = Monitor {
+        connection: connection.clone(),
+        events,
+        messages,
+        subscriptions: HashMap::new(),
+        received: 0,
+        networks: HashMap::new(),
+    };
+
+    let result = async {
+        monitor.discover(resumed).await?;
+        loop {
+            tokio::select! {
+                Some(message) = message_stream.recv() => {
+                    monitor.received += 1;
+                    if let Some(event) = parse_station_event(&message) {
+                        monitor.handle(event).await?;
+                    }
+                }
+                message = added_stream.recv() => match message {
+                    Some(message) => {
+                        if let Some(station) = added_station(&message) {
+                            monitor.subscribe(station).await?;
+                        }
+                    }
+                    None => return Ok(()),
+                },
+                message = removed_stream.recv() => match message {
+                    Some(message) => {
+                        if let Some(station) = removed_station(&message) {
+                            monitor.unsubscribe(&station).await;
+                        }
+                    }
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+    .await;
+
+    debug!(
+        "Handled {} signals from {} stations",
+        monitor.received,
+        monitor.subscriptions.len()
+    );
+    monitor.close().await;
+
+    // The matches may well have gone with whatever ended the stream.
+    for signal in [added, removed] {
+        if let Err(error) = connection.remove_match(signal.token()).await {
+            debug!("Couldn't remove match: {}", error);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::arg::{RefArg, Variant};
+
+    const PATH: &str = "/net/connman/iwd/0/3";
+
+    fn properties_changed(
+        interface: &str,
+        changed: PropMap,
+        invalidated: Vec<String>,
+    ) -> Message {
+        Message::signal(
+            &PATH.into(),
+            &"org.freedesktop.DBus.Properties".into(),
+            &"PropertiesChanged".into(),
+        )
+        .append3(interface, changed, invalidated)
+    }
+
+    fn property(name: &str, value: impl RefArg + 'static) -> PropMap {
+        let mut changed = PropMap::new();
+        changed.insert(name.to_string(), Variant(Box::new(value)));
+        changed
+    }
+
+    #[test]
+    fn scanning_reads_the_boolean() {
+        let message =
+            properties_changed(STATION, property("Scanning", false), vec![]);
+        assert_eq!(Some(false), scanning(&message));
+        let message =
+            properties_changed(STATION, property("Scanning", true), vec![]);
+        assert_eq!(Some(true), scanning(&message));
+    }
+
+    #[test]
+    fn scanning_ignores_other_properties_and_interfaces() {
+        let message = properties_changed(
+            STATION,
+            property("State", "connected".to_string()),
+            vec![],
+        );
+        assert_eq!(None, scanning(&message));
+        let message = properties_changed(
+            "net.connman.iwd.Device",
+            property("Scanning", false),
+            vec![],
+        );
+        assert_eq!(None, scanning(&message));
+    }
+
+    fn event(state: Option<StationState>) -> Option<StationEvent> {
+        Some(StationEvent {
+            station: PATH.into(),
+            state,
+            network: None,
+        })
+    }
+
+    #[test]
+    fn state_connected() {
+        let message = properties_changed(
+            STATION,
+            property("State", "connected".to_string()),
+            vec![],
+        );
+        assert_eq!(
+            event(Some(StationState::Connected)),
+            parse_station_event(&message)
+        );
+    }
+
+    #[test]
+    fn state_disconnected() {
+        let message = properties_changed(
+            STATION,
+            property("State", "disconnected".to_string()),
+            vec![],
+        );
+        assert_eq!(
+            event(Some(StationState::Disconnected)),
+            parse_station_event(&message)
+        );
+    }
+
+    #[test]
+    fn unrelated_interface() {
+        let message = properties_changed(
+            "net.connman.iwd.Device",
+            property("State", "connected".to_string()),
+            vec![],
+        );
+        assert_eq!(None, parse_station_event(&message));
+    }
+
+    #[test]
+    fn non_string_variants() {
+        let mut changed = property("State", 1u32);
+        changed.insert("ConnectedNetwork".into(), Variant(Box::new(true)));
+        let message = properties_changed(STATION, changed, vec![]);
+        assert_eq!(
+            event(Some(StationState::Other)),
+            parse_station_event(&message)
+        );
+    }
+
+    #[test]
+    fn state_missing() {
+        let message =
+            properties_changed(STATION, property("Scanning", true), vec![]);
+        assert_eq!(None, parse_station_event(&message));
+
+        // The network alone is still worth reporting.
+        let network = "/net/connman/iwd/0/3/6578616d706c65_psk".to_string();
+        let message = properties_changed(
+            STATION,
+            property(
+                "ConnectedNetwork",
+                dbus::Path::new(network.clone()).unwrap(),
+            ),
+            vec![],
+        );
+        assert_eq!(
+            Some(StationEvent {
+                station: PATH.into(),
+                state: None,
+                network: Some(dbus::Path::new(network).unwrap()),
+            }),
+            parse_station_event(&message)
+        );
+    }
+
+    #[test]
+    fn mis_shaped_bodies_are_skipped() {
+        let signal = || {
+            Message::signal(
+                &PATH.into(),
+                &"org.freedesktop.DBus.Properties".into(),
+                &"PropertiesChanged".into(),
+            )
+        };
+        for message in [
+            signal(),
+            signal().append1(STATION),
+            signal().append2(STATION, "connected"),
+            signal().append2(7u32, property("State", "connected".to_string())),
+        ] {
+            assert_eq!(None, parse_station_event(&message));
+        }
+
+        // Invalidated properties sent some other way don't matter.
+        let message = signal().append3(
+            STATION,
+            property("State", "connected".to_string()),
+            7u32,
+        );
+        assert_eq!(
+            event(Some(StationState::Connected)),
+            parse_station_event(&message)
+        );
+    }
+
+    #[test]
+    fn state_invalidated() {
+        let message = properties_changed(
+            STATION,
+            PropMap::new(),
+            vec!["State".to_string()],
+        );
+        assert_eq!(
+            event(Some(StationState::Other)),
+            parse_station_event(&message)
+        );
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
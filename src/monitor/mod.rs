@@ -0,0 +1,415 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            mod.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Monitors for the connection state of network interfaces.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::fault::{self, Fault};
+use crate::provider::Context;
+use anyhow::anyhow;
+use dbus::nonblock::{Proxy, SyncConnection};
+#[cfg(feature = "backend-netlink")]
+use log::info;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Re-subscriptions within this long of each other count towards
+/// max_resubscribes.
+const RESUBSCRIBE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Pause before re-subscribing, so that a broken bus isn't hammered.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+mod channel;
+#[cfg(feature = "backend-iwd")]
+mod iwd;
+#[cfg(feature = "backend-modem-manager")]
+mod modem_manager;
+#[cfg(feature = "backend-netlink")]
+mod netlink;
+#[cfg(feature = "backend-wpa-supplicant")]
+mod wpa_supplicant;
+
+/// The backends compiled into this binary.
+pub const COMPILED: &[&str] = &[
+    #[cfg(feature = "backend-iwd")]
+    "iwd",
+    #[cfg(feature = "backend-wpa-supplicant")]
+    "wpa_supplicant",
+    #[cfg(feature = "backend-modem-manager")]
+    "modem_manager",
+    #[cfg(feature = "backend-netlink")]
+    "netlink",
+];
+
+pub use channel::{channel, EventSender, CAPACITY};
+
+// Only the backends send events.
+#[cfg_attr(
+    not(any(
+        feature = "backend-iwd",
+        feature = "backend-wpa-supplicant",
+        feature = "backend-modem-manager",
+        feature = "backend-netlink"
+    )),
+    allow(dead_code)
+)]
+#[derive(Debug)]
+pub enum Event {
+    /// A station has connected to a network.
+    Connected(Context),
+
+    /// A station has disconnected.
+    Disconnected(Context),
+}
+
+impl Event {
+    #[cfg_attr(
+        not(any(
+            feature = "backend-iwd",
+            feature = "backend-wpa-supplicant",
+            feature = "backend-modem-manager",
+            feature = "backend-netlink"
+        )),
+        allow(dead_code)
+    )]
+    pub fn station(&self) -> &Option<String> {
+        match self {
+            Self::Connected(context) | Self::Disconnected(context) => {
+                &context.station
+            }
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendConfig {
+    /// Use the first backend whose service is present on the bus.
+    #[default]
+    Auto,
+    #[cfg(feature = "backend-iwd")]
+    Iwd,
+    #[cfg(feature = "backend-wpa-supplicant")]
+    WpaSupplicant,
+    #[cfg(feature = "backend-modem-manager")]
+    ModemManager,
+    #[cfg(feature = "backend-netlink")]
+    Netlink,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    #[cfg(feature = "backend-iwd")]
+    Iwd,
+    #[cfg(feature = "backend-wpa-supplicant")]
+    WpaSupplicant,
+    #[cfg(feature = "backend-modem-manager")]
+    ModemManager,
+    #[cfg(feature = "backend-netlink")]
+    Netlink,
+}
+
+// The matches below dereference `self` so that they are still exhaustive in
+// builds with no backends at all.
+impl Backend {
+    /// The backends that are managed by a service on the bus, in order of
+    /// preference when probing.
+    const SERVICES: &'static [(Backend, &'static str)] = &[
+        #[cfg(feature = "backend-iwd")]
+        (Backend::Iwd, iwd::SERVICE),
+        #[cfg(feature = "backend-wpa-supplicant")]
+        (Backend::WpaSupplicant, wpa_supplicant::SERVICE),
+        #[cfg(feature = "backend-modem-manager")]
+        (Backend::ModemManager, modem_manager::SERVICE),
+    ];
+
+    /// Choose the backend to use. When the configuration doesn't name one,
+    /// the first backend whose service is currently present on the bus wins.
+    /// If there are none, we fall back to watching the routing table.
+    pub async fn probe(
+        config: BackendConfig,
+        connection: Arc<SyncConnection>,
+    ) -> Result<Self, anyhow::Error> {
+        match config {
+            #[cfg(feature = "backend-iwd")]
+            BackendConfig::Iwd => return Ok(Self::Iwd),
+            #[cfg(feature = "backend-wpa-supplicant")]
+            BackendConfig::WpaSupplicant => return Ok(Self::WpaSupplicant),
+            #[cfg(feature = "backend-modem-manager")]
+            BackendConfig::ModemManager => return Ok(Self::ModemManager),
+            #[cfg(feature = "backend-netlink")]
+            BackendConfig::Netlink => return Ok(Self::Netlink),
+            BackendConfig::Auto => {}
+        }
+
+        let proxy = Proxy::new(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            Duration::from_secs(2),
+            connection,
+        );
+        for (backend, service) in Self::SERVICES {
+            let (has_owner,): (bool,) = proxy
+                .method_call(
+                    "org.freedesktop.DBus",
+                    "NameHasOwner",
+                    (service,),
+                )
+                .await?;
+            if has_owner {
+                return Ok(*backend);
+            }
+        }
+
+        #[cfg(feature = "backend-netlink")]
+        {
+            info!("No supported connection manager is running");
+            Ok(Self::Netlink)
+        }
+        #[cfg(not(feature = "backend-netlink"))]
+        Err(anyhow!("No supported connection manager is running"))
+    }
+
+    /// Choose the backend to use where there's no system bus, which leaves
+    /// only the routing table.
+    pub fn without_bus(config: BackendConfig) -> Result<Self, anyhow::Error> {
+        match config {
+            #[cfg(feature = "backend-netlink")]
+            BackendConfig::Auto | BackendConfig::Netlink => Ok(Self::Netlink),
+            // Only unreachable in builds with netlink and nothing else.
+            #[allow(unreachable_patterns)]
+            _ => Err(anyhow!(
+                "Without a system bus, only the netlink backend can be used"
+            )),
+        }
+    }
+
+    /// The stations the backend manages and their states, for backends that
+    /// have a notion of stations.
+    #[cfg_attr(not(feature = "backend-iwd"), allow(unused_variables))]
+    pub async fn stations(
+        &self,
+        connection: Arc<SyncConnection>,
+    ) -> Result<Option<Vec<(String, String)>>, anyhow::Error> {
+        match *self {
+            #[cfg(feature = "backend-iwd")]
+            Self::Iwd => Ok(Some(iwd::stations(connection).await?)),
+            #[cfg(feature = "backend-wpa-supplicant")]
+            Self::WpaSupplicant => Ok(None),
+            #[cfg(feature = "backend-modem-manager")]
+            Self::ModemManager => Ok(None),
+            #[cfg(feature = "backend-netlink")]
+            Self::Netlink => Ok(None),
+        }
+    }
+
+    /// Wait for `station` to finish an active scan, for no longer than
+    /// `limit`. Returns how long was spent waiting, or None if there was no
+    /// scan to wait for, as is always the case for backends that don't
+    /// report scanning.
+    #[cfg_attr(not(feature = "backend-iwd"), allow(unused_variables))]
+    pub async fn wait_for_scan(
+        &self,
+        connection: Arc<SyncConnection>,
+        station: &str,
+        limit: Duration,
+    ) -> Result<Option<Duration>, anyhow::Error> {
+        match *self {
+            #[cfg(feature = "backend-iwd")]
+            Self::Iwd => iwd::wait_for_scan(connection, station, limit).await,
+            #[cfg(feature = "backend-wpa-supplicant")]
+            Self::WpaSupplicant => Ok(None),
+            #[cfg(feature = "backend-modem-manager")]
+            Self::ModemManager => Ok(None),
+            #[cfg(feature = "backend-netlink")]
+            Self::Netlink => Ok(None),
+        }
+    }
+
+    /// Whether the backend can read the country access points advertise.
+    /// Only wpa_supplicant can: iwd's API has no way to, as neither its BSS
+    /// objects nor its station diagnostics carry the Country element.
+    pub fn reads_country(&self) -> bool {
+        #[cfg(feature = "backend-wpa-supplicant")]
+        if Self::WpaSupplicant == *self {
+            return true;
+        }
+        false
+    }
+
+    /// The regulatory country advertised by the access point `station` is
+    /// connected to, for backends that report it (see reads_country).
+    #[cfg_attr(
+        not(feature = "backend-wpa-supplicant"),
+        allow(unused_variables)
+    )]
+    pub async fn country(
+        &self,
+        connection: Arc<SyncConnection>,
+        station: &str,
+    ) -> Result<Option<String>, anyhow::Error> {
+        match *self {
+            #[cfg(feature = "backend-iwd")]
+            Self::Iwd => Ok(None),
+            #[cfg(feature = "backend-wpa-supplicant")]
+            Self::WpaSupplicant => {
+                wpa_supplicant::country(connection, station).await
+            }
+            #[cfg(feature = "backend-modem-manager")]
+            Self::ModemManager => Ok(None),
+            #[cfg(feature = "backend-netlink")]
+            Self::Netlink => Ok(None),
+        }
+    }
+
+    #[cfg_attr(
+        not(any(
+            feature = "backend-iwd",
+            feature = "backend-wpa-supplicant",
+            feature = "backend-modem-manager",
+            feature = "backend-netlink"
+        )),
+        allow(unused_variables)
+    )]
+    async fn watch(
+        &self,
+        connection: Option<Arc<SyncConnection>>,
+        events: EventSender,
+        resumed: bool,
+    ) -> Result<(), anyhow::Error> {
+        #[cfg_attr(
+            not(any(
+                feature = "backend-iwd",
+                feature = "backend-wpa-supplicant",
+                feature = "backend-modem-manager"
+            )),
+            allow(unused_variables)
+        )]
+        let bus = || {
+            connection
+                .clone()
+                .ok_or_else(|| anyhow!("{} needs the system bus", self))
+        };
+        match *self {
+            #[cfg(feature = "backend-iwd")]
+            Self::Iwd => iwd::monitor(bus()?, events, resumed).await,
+            #[cfg(feature = "backend-wpa-supplicant")]
+            Self::WpaSupplicant => {
+                wpa_supplicant::monitor(bus()?, events, resumed).await
+            }
+            #[cfg(feature = "backend-modem-manager")]
+            Self::ModemManager => {
+                modem_manager::monitor(bus()?, events, resumed).await
+            }
+            #[cfg(feature = "backend-netlink")]
+            Self::Netlink => netlink::monitor(events, resumed).await,
+        }
+    }
+
+    /// Report connection state changes to `events`. Whenever the backend's
+    /// signal stream ends, it's re-subscribed, unless that has already
+    /// happened `max_resubscribes` times in the last RESUBSCRIBE_WINDOW.
+    /// When `seed`, connections that are already up are reported first.
+    /// Only netlink works without `connection`.
+    pub async fn monitor(
+        &self,
+        connection: Option<Arc<SyncConnection>>,
+        events: EventSender,
+        max_resubscribes: usize,
+        seed: bool,
+    ) -> Result<(), anyhow::Error> {
+        let mut failures: VecDeque<Instant> = VecDeque::new();
+        let mut resumed = seed;
+        loop {
+            let result = match fault::inject(Fault::Disconnect) {
+                true => Err(anyhow!("Lost the bus (injected fault)")),
+                false => {
+                    self.watch(connection.clone(), events.clone(), resumed)
+                        .await
+                }
+            };
+            let error = match result {
+                Ok(()) => anyhow!("The signal stream from {} ended", self),
+                Err(error) => error,
+            };
+            if events.is_closed() {
+                return Err(error);
+            }
+
+            let now = Instant::now();
+            failures
+                .retain(|time| now.duration_since(*time) < RESUBSCRIBE_WINDOW);
+            if failures.len() >= max_resubscribes {
+                return Err(error.context(format!(
+                    "Giving up after re-subscribing {} times",
+                    failures.len()
+                )));
+            }
+            failures.push_back(now);
+            warn!("{:#}, re-subscribing", error);
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            resumed = true;
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    #[cfg_attr(
+        not(any(
+            feature = "backend-iwd",
+            feature = "backend-wpa-supplicant",
+            feature = "backend-modem-manager",
+            feature = "backend-netlink"
+        )),
+        allow(unused_variables)
+    )]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "backend-iwd")]
+            Self::Iwd => write!(f, "iwd"),
+            #[cfg(feature = "backend-wpa-supplicant")]
+            Self::WpaSupplicant => write!(f, "wpa_supplicant"),
+            #[cfg(feature = "backend-modem-manager")]
+            Self::ModemManager => write!(f, "modem_manager"),
+            #[cfg(feature = "backend-netlink")]
+            Self::Netlink => write!(f, "netlink"),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,350 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            modem_manager.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Monitor mobile connections managed by ModemManager.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::{Event, EventSender};
+use crate::provider::Context;
+use crate::signals::{self, PropertiesChanged};
+use anyhow::anyhow;
+use dbus::arg::{PropMap, RefArg};
+use dbus::message::MatchRule;
+use dbus::nonblock::stdintf::org_freedesktop_dbus::{
+    ObjectManager, Properties,
+};
+use dbus::nonblock::{Proxy, SyncConnection};
+use dbus::{Message, Path};
+use log::{debug, info};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const SERVICE: &str = "org.freedesktop.ModemManager1";
+const ROOT: &str = "/org/freedesktop/ModemManager1";
+const MODEM: &str = "org.freedesktop.ModemManager1.Modem";
+const MODEM_3GPP: &str = "org.freedesktop.ModemManager1.Modem.Modem3gpp";
+const BEARER: &str = "org.freedesktop.ModemManager1.Bearer";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// MM_MODEM_STATE_CONNECTED.
+const CONNECTED: i32 = 11;
+
+/// The "method" of an IP configuration dictionary, if it has one.
+/// MM_BEARER_IP_METHOD_UNKNOWN (0) means there's no configuration.
+fn method(config: &dyn RefArg) -> Option<u64> {
+    let mut items = config.as_iter()?;
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        if Some("method") == key.as_str() {
+            return value.as_u64();
+        }
+    }
+    None
+}
+
+/// Whether a bearer, given its properties, is connected and has an IPv4 or
+/// IPv6 configuration.
+fn is_configured(properties: &PropMap) -> bool {
+    let connected = properties
+        .get("Connected")
+        .and_then(|connected| connected.0.as_u64())
+        .is_some_and(|connected| 0 != connected);
+    connected
+        && ["Ip4Config", "Ip6Config"].iter().any(|name| {
+            properties
+                .get(*name)
+                .and_then(|config| method(&config.0))
+                .is_some_and(|method| 0 != method)
+        })
+}
+
+/// Whether any of the bearers of `modem` is up and configured.
+async fn has_address(
+    connection: &Arc<SyncConnection>,
+    modem: &Path<'_>,
+) -> Result<bool, anyhow::Error> {
+    let proxy = Proxy::new(SERVICE, modem, TIMEOUT, connection.clone());
+    let bearers: Vec<Path<'static>> = proxy.get(MODEM, "Bearers").await?;
+    for bearer in bearers {
+        let proxy = Proxy::new(SERVICE, bearer, TIMEOUT, connection.clone());
+        if is_configured(&proxy.get_all(BEARER).await?) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Report that `modem` connected, unless none of its bearers has an address
+/// yet, in which case it's up to the caller to try again later. Whether it
+/// was reported.
+async fn connected(
+    connection: &Arc<SyncConnection>,
+    modem: &Path<'static>,
+    events: &EventSender,
+) -> Result<bool, anyhow::Error> {
+    match has_address(connection, modem).await {
+        Ok(true) => {}
+        Ok(false) => {
+            debug!("{} connected, but has no IP configuration yet", modem);
+            return Ok(false);
+        }
+        Err(error) => {
+            debug!("Couldn't read the bearers of {}: {}", modem, error)
+        }
+    }
+
+    // Only 3GPP modems have an operator name.
+    let proxy = Proxy::new(SERVICE, modem, TIMEOUT, connection.clone());
+    let operator: Option<String> = proxy
+        .get(MODEM_3GPP, "OperatorName")
+        .await
+        .ok()
+        .filter(|name: &String| !name.is_empty());
+    info!(
+        "{} connected to {}",
+        modem,
+        operator.as_deref().unwrap_or("an unknown operator")
+    );
+    events.send(Event::Connected(Context {
+        station: Some(modem.to_string()),
+        ssid: None,
+        session: None,
+    }))?;
+    Ok(true)
+}
+
+/// Whether a PropertiesChanged signal says something about a bearer that
+/// could make it usable.
+fn bearer_changed(message: &Message) -> bool {
+    let Some(properties) = PropertiesChanged::read(message) else {
+        return false;
+    };
+    BEARER == properties.interface
+        && ["Connected", "Ip4Config", "Ip6Config"]
+            .iter()
+            .any(|name| properties.changed.contains_key(*name))
+}
+
+/// Extract the old and new states from a StateChanged signal.
+fn parse_state(message: &Message) -> Option<(Path<'static>, i32, i32)> {
+    let (old, new, _reason): (i32, i32, u32) = match message.read3() {
+        Ok(body) => body,
+        Err(error) => {
+            signals::skip(message, error);
+            return None;
+        }
+    };
+    Some((message.path()?.into_static(), old, new))
+}
+
+/// Report modems connecting and disconnecting until the signal stream ends.
+/// When `resumed`, modems that are already connected are reported first.
+pub async fn monitor(
+    connection: Arc<SyncConnection>,
+    events: EventSender,
+    resumed: bool,
+) -> Result<(), anyhow::Error> {
+    let (messages, mut incoming) = signals::channel(signals::CAPACITY);
+    let rule =
+        MatchRule::new_signal(MODEM, "StateChanged").with_sender(SERVICE);
+    let sender = messages.clone();
+    let states = connection
+        .add_match(rule)
+        .await?
+        .msg_cb(move |message| sender.send(message));
+    // A bearer may only get its IP configuration after the modem reports
+    // being connected.
+    let rule = MatchRule::new_signal(
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+    )
+    .with_sender(SERVICE);
+    let bearers = match connection.add_match(rule).await {
+        Ok(signal) => signal.msg_cb(move |message| messages.send(message)),
+        Err(error) => {
+            let _ = connection.remove_match(states.token()).await;
+            return Err(error.into());
+        }
+    };
+
+    // Modems that are connected, but were waiting on a bearer when they
+    // said so.
+    let mut waiting: HashSet<Path<'static>> = HashSet::new();
+    let result = async {
+        if resumed {
+            let root = Proxy::new(SERVICE, ROOT, TIMEOUT, connection.clone());
+            for (path, interfaces) in root.get_managed_objects().await? {
+                let state = interfaces
+                    .get(MODEM)
+                    .and_then(|properties| properties.get("State"))
+                    .and_then(|state| state.0.as_i64());
+                if Some(i64::from(CONNECTED)) == state
+                    && !connected(&connection, &path, &events).await?
+                {
+                    waiting.insert(path);
+                }
+            }
+        }
+
+        while let Some(message) = incoming.recv().await {
+            if Some("PropertiesChanged") == message.member().as_deref() {
+                if waiting.is_empty() || !bearer_changed(&message) {
+                    continue;
+                }
+                for modem in waiting.clone() {
+                    if connected(&connection, &modem, &events).await? {
+                        waiting.remove(&modem);
+                    }
+                }
+                continue;
+            }
+            let Some((modem, old, new)) = parse_state(&message) else {
+                continue;
+            };
+            if CONNECTED == new && CONNECTED != old {
+                if !connected(&connection, &modem, &events).await? {
+                    waiting.insert(modem);
+                }
+            } else if CONNECTED == old && CONNECTED != new {
+                waiting.remove(&modem);
+                events.send(Event::Disconnected(Context {
+                    station: Some(modem.to_string()),
+                    ssid: None,
+                    session: None,
+                }))?;
+            }
+        }
+        Err(anyhow!("Lost the signal stream from ModemManager"))
+    }
+    .await;
+
+    // The matches may well have gone with whatever ended the stream.
+    for signal in [states, bearers] {
+        if let Err(error) = connection.remove_match(signal.token()).await {
+            debug!("Couldn't remove match: {}", error);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::arg::Variant;
+
+    const PATH: &str = "/org/freedesktop/ModemManager1/Bearer/0";
+
+    /// An IP configuration with the given method; 0 is none.
+    fn ip_config(method: u32) -> Variant<Box<dyn RefArg>> {
+        let mut config = PropMap::new();
+        config.insert("method".into(), Variant(Box::new(method)));
+        Variant(Box::new(config))
+    }
+
+    fn properties_changed(interface: &str, changed: PropMap) -> Message {
+        Message::signal(
+            &PATH.into(),
+            &"org.freedesktop.DBus.Properties".into(),
+            &"PropertiesChanged".into(),
+        )
+        .append3(interface, changed, Vec::<String>::new())
+    }
+
+    #[test]
+    fn a_bearer_needs_to_be_connected_with_an_ip_method() {
+        let mut properties = PropMap::new();
+        properties.insert("Connected".into(), Variant(Box::new(true)));
+        properties.insert("Ip4Config".into(), ip_config(0));
+        assert!(!is_configured(&properties));
+        properties.insert("Ip6Config".into(), ip_config(3));
+        assert!(is_configured(&properties));
+        properties.insert("Connected".into(), Variant(Box::new(false)));
+        assert!(!is_configured(&properties));
+    }
+
+    #[test]
+    fn bearer_ip_changes_are_noticed() {
+        let mut changed = PropMap::new();
+        changed.insert("Ip4Config".into(), ip_config(3));
+        assert!(bearer_changed(&properties_changed(BEARER, changed)));
+        let mut changed = PropMap::new();
+        changed.insert("Connected".into(), Variant(Box::new(true)));
+        assert!(bearer_changed(&properties_changed(BEARER, changed)));
+    }
+
+    #[test]
+    fn other_changes_are_not() {
+        let mut changed = PropMap::new();
+        changed.insert(
+            "Interface".into(),
+            Variant(Box::new("wwan0".to_string())),
+        );
+        assert!(!bearer_changed(&properties_changed(BEARER, changed)));
+        let mut changed = PropMap::new();
+        changed.insert("Ip4Config".into(), ip_config(3));
+        assert!(!bearer_changed(&properties_changed(MODEM, changed)));
+    }
+
+    fn state_changed() -> Message {
+        Message::signal(
+            &"/org/freedesktop/ModemManager1/Modem/0".into(),
+            &MODEM.into(),
+            &"StateChanged".into(),
+        )
+    }
+
+    #[test]
+    fn state_changes_are_read() {
+        let message = state_changed().append3(8i32, 11i32, 1u32);
+        assert_eq!(
+            Some((
+                Path::from("/org/freedesktop/ModemManager1/Modem/0"),
+                8,
+                11
+            )),
+            parse_state(&message)
+        );
+    }
+
+    #[test]
+    fn mis_shaped_state_changes_are_skipped() {
+        for message in [
+            state_changed(),
+            state_changed().append2(8i32, 11i32),
+            state_changed().append3(8u32, 11u32, 1u32),
+            state_changed().append3("registered", "connected", "user"),
+        ] {
+            assert_eq!(None, parse_state(&message));
+        }
+        assert!(!bearer_changed(&state_changed().append1(7u32)));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
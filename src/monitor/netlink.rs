@@ -0,0 +1,124 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            netlink.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Monitor for default routes, using rtnetlink directly.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::{Event, EventSender};
+use crate::provider::Context;
+use anyhow::anyhow;
+use futures_util::stream::{StreamExt, TryStreamExt};
+use log::debug;
+use rtnetlink::packet_route::route::RouteType;
+use rtnetlink::{Handle, MulticastGroup, RouteMessageBuilder};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Route changes come in bursts while an interface is being configured. Wait
+/// for it to settle for this long before deciding whether we're connected.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// The task driving the netlink socket. It's stopped once the monitor
+/// returns or is dropped, so that resubscribing doesn't leave the old one
+/// running.
+struct Connection(JoinHandle<()>);
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+async fn count_default_routes(
+    handle: &Handle,
+) -> Result<usize, anyhow::Error> {
+    let mut count = 0;
+    let queries = [
+        RouteMessageBuilder::<Ipv4Addr>::new().build(),
+        RouteMessageBuilder::<Ipv6Addr>::new().build(),
+    ];
+    for query in queries {
+        let mut routes = handle.route().get(query).execute();
+        while let Some(route) = routes.try_next().await? {
+            if 0 == route.header.destination_prefix_length
+                && RouteType::Unicast == route.header.kind
+            {
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Report the default route coming and going until the netlink socket
+/// closes. When `resumed`, a default route that's already present is
+/// reported first.
+pub async fn monitor(
+    events: EventSender,
+    resumed: bool,
+) -> Result<(), anyhow::Error> {
+    let (connection, handle, mut messages) =
+        rtnetlink::new_multicast_connection(&[
+            MulticastGroup::Link,
+            MulticastGroup::Ipv4Route,
+            MulticastGroup::Ipv6Route,
+        ])?;
+    let _connection = Connection(tokio::spawn(connection));
+
+    let mut connected = count_default_routes(&handle).await? > 0;
+    debug!("Default route present at startup: {}", connected);
+    if resumed && connected {
+        events.send(Event::Connected(Context::default()))?;
+    }
+
+    while messages.next().await.is_some() {
+        while let Ok(Some(_)) =
+            tokio::time::timeout(DEBOUNCE, messages.next()).await
+        {}
+
+        let routes = count_default_routes(&handle).await?;
+        if connected == (routes > 0) {
+            continue;
+        }
+        connected = routes > 0;
+
+        // There's no station or network to speak of here.
+        let context = Context::default();
+        if connected {
+            events.send(Event::Connected(context))?;
+        } else {
+            events.send(Event::Disconnected(context))?;
+        }
+    }
+    Err(anyhow!("Lost the netlink socket"))
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,561 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            wpa_supplicant.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Monitor for interfaces managed by wpa_supplicant.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::{Event, EventSender};
+use crate::provider::Context;
+use crate::signals::{self, PropertiesChanged, WeakMessageSender};
+use anyhow::anyhow;
+use dbus::arg::{PropMap, RefArg};
+use dbus::channel::{MatchingReceiver, Token};
+use dbus::message::MatchRule;
+use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
+use dbus::nonblock::{Proxy, SyncConnection};
+use dbus::{Message, Path};
+use log::debug;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const SERVICE: &str = "fi.w1.wpa_supplicant1";
+const ROOT: &str = "/fi/w1/wpa_supplicant1";
+const INTERFACE: &str = "fi.w1.wpa_supplicant1.Interface";
+const NETWORK: &str = "fi.w1.wpa_supplicant1.Network";
+const BSS: &str = "fi.w1.wpa_supplicant1.BSS";
+
+/// The ID of the 802.11d Country element in a beacon.
+const COUNTRY_ELEMENT: u8 = 7;
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Decode the "ssid" entry of a network's properties. wpa_supplicant reports
+/// this the way it appears in its configuration file: a quoted string for
+/// printable SSIDs, or a bare string of hex digits for everything else.
+/// Some versions report the raw bytes instead.
+fn decode_ssid(value: &dyn RefArg) -> Result<String, anyhow::Error> {
+    let bytes = if let Some(text) = value.as_str() {
+        if let Some(quoted) = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+        {
+            return Ok(quoted.to_string());
+        }
+        if 0 != text.len() % 2 {
+            return Err(anyhow!("Malformed SSID {:?}", text));
+        }
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| anyhow!("Malformed SSID {:?}", text))?
+    } else {
+        value
+            .as_iter()
+            .ok_or_else(|| anyhow!("Unexpected SSID type"))?
+            .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| anyhow!("Unexpected SSID type"))?
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The country an access point advertises in the Country element of its
+/// beacons, given the raw elements. None if it doesn't advertise one, or
+/// advertises "00" (the world).
+fn advertised_country(elements: &[u8]) -> Option<String> {
+    let mut rest = elements;
+    while let [id, length, body @ ..] = rest {
+        let length = usize::from(*length);
+        let element = body.get(..length)?;
+        if COUNTRY_ELEMENT == *id {
+            let code = element.get(..2)?;
+            return code
+                .iter()
+                .all(u8::is_ascii_alphabetic)
+                .then(|| String::from_utf8_lossy(code).to_ascii_uppercase());
+        }
+        rest = &body[length..];
+    }
+    None
+}
+
+/// The country advertised by the access point `interface` is associated
+/// with, if it advertises one.
+pub async fn country(
+    connection: Arc<SyncConnection>,
+    interface: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    let interface = Path::new(interface.to_string())
+        .map_err(|error| anyhow!("Bad interface path: {}", error))?;
+    let proxy = Proxy::new(SERVICE, interface, TIMEOUT, connection.clone());
+    let bss: Path = proxy.get(INTERFACE, "CurrentBSS").await?;
+    if "/" == &*bss {
+        return Ok(None);
+    }
+    let proxy = Proxy::new(SERVICE, bss, TIMEOUT, connection);
+    let elements: Vec<u8> = proxy.get(BSS, "IEs").await?;
+    Ok(advertised_country(&elements))
+}
+
+struct Monitor {
+    connection: Arc<SyncConnection>,
+
+    /// Every match forwards what it receives into the same stream. Only the
+    /// matches keep it open, so it ends if the connection drops them.
+    messages: WeakMessageSender,
+
+    /// The matches for interfaces coming and going.
+    root: Vec<Token>,
+
+    /// The match installed for each interface we are watching.
+    interfaces: HashMap<Path<'static>, Token>,
+
+    /// The network each connected interface was on when we last reported it,
+    /// or None if that couldn't be read. wpa_supplicant goes back through
+    /// "completed" on every rekey and roam, so this is what tells those,
+    /// which aren't worth reporting, from joining a different network.
+    networks: HashMap<Path<'static>, Option<Path<'static>>>,
+}
+
+impl Monitor {
+    /// Install `rule`, forwarding every message it matches to the monitor.
+    /// The callback is registered with start_receive, not add_match, since
+    /// add_match's callback goes with the MsgMatch it returns, and we keep
+    /// only the token.
+    async fn forward(
+        &self,
+        rule: MatchRule<'static>,
+    ) -> Result<Token, anyhow::Error> {
+        let messages = self
+            .messages
+            .upgrade()
+            .ok_or_else(|| anyhow!("The signal stream has ended"))?;
+        self.connection.add_match_no_cb(&rule.match_str()).await?;
+        Ok(self.connection.start_receive(
+            rule,
+            Box::new(move |message, _| messages.send(message)),
+        ))
+    }
+
+    async fn add_interface(
+        &mut self,
+        path: Path<'static>,
+    ) -> Result<(), anyhow::Error> {
+        if self.interfaces.contains_key(&path) {
+            return Ok(());
+        }
+        let rule = MatchRule::new_signal(
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+        )
+        .with_sender(SERVICE)
+        .with_path(path.clone());
+        let token = self.forward(rule).await?;
+        debug!("Watching interface {}", path);
+        self.interfaces.insert(path, token);
+        Ok(())
+    }
+
+    /// Stop watching `path`, reporting it disconnected if it was connected,
+    /// since it won't say so itself once it's gone.
+    async fn remove_interface(
+        &mut self,
+        path: Path<'static>,
+        events: &EventSender,
+    ) -> Result<(), anyhow::Error> {
+        if self.networks.contains_key(&path) {
+            self.disconnected(path.clone(), events)?;
+        }
+        if let Some(token) = self.interfaces.remove(&path) {
+            debug!("No longer watching interface {}", path);
+            self.connection.remove_match(token).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove every match, e.g. before subscribing again. They may well have
+    /// gone with whatever ended the stream.
+    async fn close(&mut self) {
+        let interfaces = self.interfaces.drain().map(|(_, token)| token);
+        let tokens: Vec<Token> =
+            self.root.drain(..).chain(interfaces).collect();
+        for token in tokens {
+            if let Err(error) = self.connection.remove_match(token).await {
+                debug!("Couldn't remove match: {}", error);
+            }
+        }
+    }
+
+    async fn get_network(
+        &self,
+        interface: &Path<'_>,
+    ) -> Result<Path<'static>, anyhow::Error> {
+        let proxy =
+            Proxy::new(SERVICE, interface, TIMEOUT, self.connection.clone());
+        Ok(proxy.get(INTERFACE, "CurrentNetwork").await?)
+    }
+
+    async fn get_ssid(
+        &self,
+        network: &Path<'_>,
+    ) -> Result<String, anyhow::Error> {
+        let proxy =
+            Proxy::new(SERVICE, network, TIMEOUT, self.connection.clone());
+        let properties: PropMap = proxy.get(NETWORK, "Properties").await?;
+        let ssid = properties
+            .get("ssid")
+            .ok_or_else(|| anyhow!("Network has no SSID"))?;
+        decode_ssid(&ssid.0)
+    }
+
+    /// Report that `interface` has connected, unless we already reported it
+    /// connected to the network it's on now.
+    async fn connected(
+        &mut self,
+        interface: Path<'static>,
+        events: &EventSender,
+    ) -> Result<(), anyhow::Error> {
+        let network = self
+            .get_network(&interface)
+            .await
+            .map_err(|error| debug!("Couldn't read network: {}", error))
+            .ok();
+        let mut ssid = None;
+        if let Some(network) = &network {
+            let previous = self.networks.get(&interface);
+            if previous
+                .is_some_and(|previous| Some(network) == previous.as_ref())
+            {
+                debug!("{} is still connected to {}", interface, network);
+                return Ok(());
+            }
+            ssid = self
+                .get_ssid(network)
+                .await
+                .map_err(|error| debug!("Couldn't read SSID: {}", error))
+                .ok();
+        }
+        self.networks.insert(interface.clone(), network);
+        events.send(Event::Connected(Context {
+            station: Some(interface.to_string()),
+            ssid,
+            session: None,
+        }))?;
+        Ok(())
+    }
+
+    fn disconnected(
+        &mut self,
+        interface: Path<'static>,
+        events: &EventSender,
+    ) -> Result<(), anyhow::Error> {
+        self.networks.remove(&interface);
+        events.send(Event::Disconnected(Context {
+            station: Some(interface.to_string()),
+            ssid: None,
+            session: None,
+        }))?;
+        Ok(())
+    }
+
+    async fn state_changed(
+        &mut self,
+        path: Path<'static>,
+        state: &str,
+        events: &EventSender,
+    ) -> Result<(), anyhow::Error> {
+        if "completed" == state {
+            self.connected(path, events).await
+        } else if "disconnected" == state {
+            self.disconnected(path, events)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Extract the interface path and new state from a PropertiesChanged signal,
+/// if it carries one.
+fn parse_state(message: &Message) -> Option<(Path<'static>, String)> {
+    let properties = PropertiesChanged::read(message)?;
+    if INTERFACE != properties.interface {
+        return None;
+    }
+    let state = properties.changed.get("State")?.0.as_str()?.to_string();
+    Some((message.path()?.into_static(), state))
+}
+
+/// Report interface state changes until the signal stream ends. When
+/// `resumed`, interfaces that are already connected are reported first.
+pub async fn monitor(
+    connection: Arc<SyncConnection>,
+    events: EventSender,
+    resumed: bool,
+) -> Result<(), anyhow::Error> {
+    let (messages, mut incoming) = signals::channel(signals::CAPACITY);
+    let mut monitor = Monitor {
+        connection: connection.clone(),
+        messages: messages.downgrade(),
+        root: Vec::new(),
+        interfaces: HashMap::new(),
+        networks: HashMap::new(),
+    };
+
+    let result = async {
+        // Interfaces come and go as wpa_supplicant is told to manage them.
+        for member in ["InterfaceAdded", "InterfaceRemoved"] {
+            let rule = MatchRule::new_signal(SERVICE, member)
+                .with_sender(SERVICE)
+                .with_path(ROOT);
+            let token = monitor.forward(rule).await?;
+            monitor.root.push(token);
+        }
+        // From here on, the matches hold the stream open.
+        drop(messages);
+
+        let root = Proxy::new(SERVICE, ROOT, TIMEOUT, connection.clone());
+        let interfaces: Vec<Path<'static>> =
+            root.get(SERVICE, "Interfaces").await?;
+        for interface in interfaces {
+            monitor.add_interface(interface.clone()).await?;
+            if resumed {
+                let proxy = Proxy::new(
+                    SERVICE,
+                    interface.clone(),
+                    TIMEOUT,
+                    connection.clone(),
+                );
+                let state: String = proxy.get(INTERFACE, "State").await?;
+                monitor.state_changed(interface, &state, &events).await?;
+            }
+        }
+
+        while let Some(message) = incoming.recv().await {
+            match message.member().as_deref() {
+                Some("InterfaceAdded") => match message.read1() {
+                    Ok(path) => monitor.add_interface(path).await?,
+                    Err(error) => signals::skip(&message, error),
+                },
+                Some("InterfaceRemoved") => match message.read1() {
+                    Ok(path) => {
+                        monitor.remove_interface(path, &events).await?
+                    }
+                    Err(error) => signals::skip(&message, error),
+                },
+                Some("PropertiesChanged") => {
+                    if let Some((path, state)) = parse_state(&message) {
+                        monitor.state_changed(path, &state, &events).await?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(anyhow!("Lost the signal stream from wpa_supplicant"))
+    }
+    .await;
+
+    monitor.close().await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::channel::EventReceiver;
+    use crate::test_bus::Bus;
+    use dbus::arg::Variant;
+    use dbus::channel::Sender;
+    use dbus_crossroads::{Crossroads, IfaceBuilder};
+    use std::sync::Mutex;
+
+    const PATH: &str = "/fi/w1/wpa_supplicant1/Interfaces/0";
+    const HOME: &str = "/fi/w1/wpa_supplicant1/Interfaces/0/Networks/0";
+    const WORK: &str = "/fi/w1/wpa_supplicant1/Interfaces/0/Networks/1";
+
+    /// The network the fake interface is on.
+    type Current = Arc<Mutex<Path<'static>>>;
+
+    /// Serve a fake wpa_supplicant on `connection`, with one interface,
+    /// connected to the network in `current`, and two networks.
+    async fn wpa_supplicant(
+        connection: &Arc<SyncConnection>,
+        current: Current,
+    ) {
+        let mut crossroads = Crossroads::new();
+        let root = crossroads.register(SERVICE, |b: &mut IfaceBuilder<()>| {
+            b.property("Interfaces")
+                .get(|_, _| Ok(vec![Path::from(PATH)]));
+        });
+        let interface =
+            crossroads.register(INTERFACE, |b: &mut IfaceBuilder<Current>| {
+                b.property("State").get(|_, _| Ok("completed".to_string()));
+                b.property("CurrentNetwork")
+                    .get(|_, current| Ok(current.lock().unwrap().clone()));
+            });
+        let network =
+            crossroads.register(NETWORK, |b: &mut IfaceBuilder<String>| {
+                b.property("Properties").get(|_, ssid| {
+                    let mut properties = PropMap::new();
+                    properties.insert(
+                        "ssid".to_string(),
+                        Variant(Box::new(format!("\"{}\"", ssid))),
+                    );
+                    Ok(properties)
+                });
+            });
+        crossroads.insert(ROOT, &[root], ());
+        crossroads.insert(PATH, &[interface], current);
+        crossroads.insert(HOME, &[network], "home".to_string());
+        crossroads.insert(WORK, &[network], "work".to_string());
+        connection.start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |message, connection| {
+                let _ = crossroads.handle_message(message, connection);
+                true
+            }),
+        );
+        connection
+            .request_name(SERVICE, false, true, false)
+            .await
+            .unwrap();
+    }
+
+    fn changed(connection: &SyncConnection, to: &str) {
+        let message = signal().append3(
+            INTERFACE,
+            state(to.to_string()),
+            Vec::<String>::new(),
+        );
+        connection.send(message).unwrap();
+    }
+
+    async fn next(events: &mut EventReceiver) -> Event {
+        tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    /// The SSID the interface connected to, if `event` says it connected.
+    fn joined(event: Event) -> Option<String> {
+        match event {
+            Event::Connected(context) => {
+                assert_eq!(Some(PATH.to_string()), context.station);
+                context.ssid
+            }
+            Event::Disconnected(_) => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn only_joining_a_different_network_is_reported() {
+        let bus = Bus::start();
+        let connection = bus.connect();
+        let current = Current::new(Mutex::new(Path::from(HOME)));
+        wpa_supplicant(&connection, current.clone()).await;
+        let (sender, mut events) = super::super::channel(16);
+        let task = tokio::spawn(monitor(bus.connect(), sender, true));
+
+        // Resuming reports what the interface is already connected to, which
+        // also shows the signals are being watched from here on.
+        assert_eq!(Some("home".to_string()), joined(next(&mut events).await));
+        // A rekey, then a roam to another access point on the same network.
+        for to in ["group_handshake", "completed", "associating", "completed"]
+        {
+            changed(&connection, to);
+        }
+        *current.lock().unwrap() = Path::from(WORK);
+        changed(&connection, "completed");
+        assert_eq!(Some("work".to_string()), joined(next(&mut events).await));
+
+        let removed = Message::signal(
+            &ROOT.into(),
+            &SERVICE.into(),
+            &"InterfaceRemoved".into(),
+        )
+        .append1(Path::from(PATH));
+        connection.send(removed).unwrap();
+        let event = next(&mut events).await;
+        assert!(matches!(event, Event::Disconnected(_)), "{:?}", event);
+        assert!(events.try_recv().is_none());
+        task.abort();
+    }
+
+    fn signal() -> Message {
+        Message::signal(
+            &PATH.into(),
+            &"org.freedesktop.DBus.Properties".into(),
+            &"PropertiesChanged".into(),
+        )
+    }
+
+    fn state(value: impl RefArg + 'static) -> PropMap {
+        let mut changed = PropMap::new();
+        changed.insert("State".into(), Variant(Box::new(value)));
+        changed
+    }
+
+    #[test]
+    fn state_changes_are_read() {
+        let message = signal().append3(
+            INTERFACE,
+            state("completed".to_string()),
+            Vec::<String>::new(),
+        );
+        assert_eq!(
+            Some((Path::from(PATH), "completed".to_string())),
+            parse_state(&message)
+        );
+    }
+
+    #[test]
+    fn mis_shaped_state_changes_are_skipped() {
+        for message in [
+            signal(),
+            signal().append1(INTERFACE),
+            signal().append2(INTERFACE, "completed"),
+            signal().append2(INTERFACE, state(7u32)),
+            signal().append2(
+                "fi.w1.wpa_supplicant1.BSS",
+                state("completed".to_string()),
+            ),
+        ] {
+            assert_eq!(None, parse_state(&message));
+        }
+
+        // Only the changed properties are needed.
+        let message =
+            signal().append3(INTERFACE, state("completed".to_string()), 7u32);
+        assert!(parse_state(&message).is_some());
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
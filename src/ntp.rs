@@ -0,0 +1,108 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            ntp.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Notices when timedated reports that the clock has been
+//                  synchronized.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::signals::{self, PropertiesChanged};
+use dbus::arg::RefArg;
+use dbus::message::MatchRule;
+use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
+use dbus::nonblock::{Proxy, SyncConnection};
+use log::{debug, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+const SERVICE: &str = "org.freedesktop.timedate1";
+const PATH: &str = "/org/freedesktop/timedate1";
+const PROPERTY: &str = "NTPSynchronized";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+async fn is_synchronized(
+    connection: Arc<SyncConnection>,
+) -> Result<bool, anyhow::Error> {
+    let proxy = Proxy::new(SERVICE, PATH, TIMEOUT, connection);
+    Ok(proxy.get(SERVICE, PROPERTY).await?)
+}
+
+/// The value of NTPSynchronized carried by a PropertiesChanged signal, if
+/// it's there. Some versions of timedated only invalidate the property, or
+/// don't mention it at all, so None means it has to be read.
+fn parse_synchronized(properties: &PropertiesChanged) -> Option<bool> {
+    properties
+        .changed
+        .get(PROPERTY)
+        .and_then(|value| value.0.as_u64())
+        .map(|value| 0 != value)
+}
+
+/// Send to `events` each time NTPSynchronized goes from false to true, until
+/// the signal stream ends.
+pub async fn monitor(
+    connection: Arc<SyncConnection>,
+    events: UnboundedSender<()>,
+) -> Result<(), anyhow::Error> {
+    let rule = MatchRule::new_signal(
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+    )
+    .with_sender(SERVICE)
+    .with_path(PATH);
+    let (signal, mut incoming) = signals::subscribe(&connection, rule).await?;
+
+    let mut synchronized = is_synchronized(connection.clone()).await?;
+    debug!("Clock synchronized: {}", synchronized);
+    while let Some(message) = incoming.recv().await {
+        let Some(properties) = PropertiesChanged::read(&message) else {
+            continue;
+        };
+        if SERVICE != properties.interface {
+            continue;
+        }
+        let now = match parse_synchronized(&properties) {
+            Some(now) => now,
+            None => is_synchronized(connection.clone()).await?,
+        };
+        if now && !synchronized {
+            info!("The clock is now synchronized");
+            events.send(())?;
+        }
+        synchronized = now;
+    }
+
+    if let Err(error) = connection.remove_match(signal.token()).await {
+        debug!("Couldn't remove match: {}", error);
+    }
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////
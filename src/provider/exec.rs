@@ -0,0 +1,148 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            exec.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Provider running an external command.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::Context;
+use anyhow::anyhow;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecConfig {
+    /// The program and its arguments. The program is executed directly,
+    /// unless `shell` is set, in which case the words are joined and passed
+    /// to `/bin/sh -c`.
+    pub command: Vec<String>,
+
+    #[serde(default)]
+    pub shell: bool,
+
+    #[serde(default = "default_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl ExecConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = super::validate_timeout(self.timeout);
+        match self.command.first() {
+            None => problems.push("Exec provider requires a command".into()),
+            Some(program) if !self.shell && program.starts_with('/') => {
+                if !Path::new(program).is_file() {
+                    problems.push(format!("{} does not exist", program));
+                }
+            }
+            Some(_) => {}
+        }
+        problems
+    }
+}
+
+pub struct ExecProvider {
+    name: String,
+    command: Vec<String>,
+    shell: bool,
+    timeout: Duration,
+}
+
+impl ExecProvider {
+    pub fn new(config: &ExecConfig) -> Result<Self, anyhow::Error> {
+        let name = config
+            .command
+            .first()
+            .ok_or_else(|| anyhow!("Exec provider requires a command"))?
+            .clone();
+        Ok(Self {
+            name,
+            command: config.command.clone(),
+            shell: config.shell,
+            timeout: config.timeout,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn command(&self) -> Command {
+        if self.shell {
+            let mut command = Command::new("/bin/sh");
+            command.arg("-c").arg(self.command.join(" "));
+            command
+        } else {
+            let mut command = Command::new(&self.command[0]);
+            command.args(&self.command[1..]);
+            command
+        }
+    }
+
+    pub async fn detect(
+        &self,
+        context: &Context,
+    ) -> Result<String, anyhow::Error> {
+        let mut command = self.command();
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(ssid) = &context.ssid {
+            command.env("TZHOOK_SSID", ssid);
+        }
+        if let Some(station) = &context.station {
+            command.env("TZHOOK_STATION", station);
+        }
+
+        let output = tokio::time::timeout(self.timeout, command.output())
+            .await
+            .map_err(|_| anyhow!("Timed out after {:?}", self.timeout))??;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        for line in stderr.lines() {
+            debug!("{}: {}", self.name, line);
+        }
+        if !output.status.success() {
+            return Err(anyhow!("Command exited with {}", output.status));
+        }
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,122 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            file.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Providers that read the timezone from a file, or from standard
+//                  input.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::io::AsyncReadExt;
+use tokio::sync::OnceCell;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    /// The file to read. It's read afresh on every detection, so another
+    /// process can decide the timezone and just write it here.
+    pub path: PathBuf,
+}
+
+impl FileConfig {
+    pub fn validate(&self) -> Vec<String> {
+        if self.path.as_os_str().is_empty() {
+            vec!["File provider requires a path".into()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Take the timezone from `contents`, where empty means there isn't one.
+fn parse(contents: &str) -> Result<String, anyhow::Error> {
+    match contents.trim() {
+        "" => Err(anyhow!("No timezone given")),
+        timezone => Ok(timezone.to_string()),
+    }
+}
+
+pub struct FileProvider {
+    name: String,
+    path: PathBuf,
+}
+
+impl FileProvider {
+    pub fn new(config: &FileConfig) -> Self {
+        Self {
+            name: config.path.display().to_string(),
+            path: config.path.clone(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub async fn detect(&self) -> Result<String, anyhow::Error> {
+        match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => parse(&contents),
+            Err(error) if ErrorKind::NotFound == error.kind() => {
+                Err(anyhow!("{} does not exist", self.name))
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// Reads the timezone from standard input, e.g. for
+/// `echo Europe/Berlin | iwd-auto-timezone --once`. Standard input is read
+/// to the end the first time, and that answer is given every time after.
+#[derive(Default)]
+pub struct StdinProvider {
+    contents: OnceCell<String>,
+}
+
+impl StdinProvider {
+    pub fn name(&self) -> &str {
+        "stdin"
+    }
+
+    pub async fn detect(&self) -> Result<String, anyhow::Error> {
+        let contents = self
+            .contents
+            .get_or_try_init(|| async {
+                let mut contents = String::new();
+                tokio::io::stdin().read_to_string(&mut contents).await?;
+                Ok::<_, anyhow::Error>(contents)
+            })
+            .await?;
+        parse(contents)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,177 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            gpsd.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Provider using the position reported by gpsd.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::Detection;
+use crate::location::{Coordinates, Location};
+use crate::resolver::CoordinateResolver;
+use anyhow::anyhow;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+const WATCH: &[u8] = b"?WATCH={\"enable\":true,\"json\":true}\n";
+
+fn default_address() -> String {
+    "localhost:2947".to_string()
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_max_error() -> f64 {
+    100.0
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GpsdConfig {
+    #[serde(default = "default_address")]
+    pub address: String,
+
+    /// How long to wait for gpsd to report a usable fix.
+    #[serde(default = "default_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+
+    /// Largest acceptable horizontal error estimate, in metres.
+    #[serde(default = "default_max_error")]
+    pub max_error: f64,
+}
+
+impl GpsdConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = super::validate_timeout(self.timeout);
+        if self.max_error <= 0.0 {
+            problems.push("max_error must be positive".into());
+        }
+        problems
+    }
+}
+
+/// The parts of a gpsd TPV (time-position-velocity) report we care about.
+#[derive(Deserialize)]
+struct Report {
+    class: String,
+    #[serde(default)]
+    mode: u8,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    eph: Option<f64>,
+    epx: Option<f64>,
+    epy: Option<f64>,
+}
+
+impl Report {
+    /// Horizontal error estimate, preferring gpsd's own figure when the
+    /// receiver provides it.
+    fn error(&self) -> Option<f64> {
+        self.eph.or(match (self.epx, self.epy) {
+            (Some(x), Some(y)) => Some(x.max(y)),
+            _ => None,
+        })
+    }
+}
+
+pub struct GpsdProvider {
+    address: String,
+    timeout: Duration,
+    max_error: f64,
+    resolver: CoordinateResolver,
+}
+
+impl GpsdProvider {
+    pub fn new(config: &GpsdConfig) -> Self {
+        Self {
+            address: config.address.clone(),
+            timeout: config.timeout,
+            max_error: config.max_error,
+            resolver: CoordinateResolver::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        "gpsd"
+    }
+
+    pub async fn detect(&self) -> Result<Detection, anyhow::Error> {
+        let location = tokio::time::timeout(self.timeout, self.get_fix())
+            .await
+            .map_err(|_| {
+                anyhow!("No usable fix after {:?}", self.timeout)
+            })??;
+        debug!("gpsd reported a fix at {}", location.coordinates);
+        Ok(Detection {
+            timezone: self.resolver.resolve(location.coordinates)?,
+            location: Some(location),
+            rewritten_from: None,
+        })
+    }
+
+    async fn get_fix(&self) -> Result<Location, anyhow::Error> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+        stream.write_all(WATCH).await?;
+        let mut lines = BufReader::new(stream).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            let report: Report = match serde_json::from_str(&line) {
+                Ok(report) => report,
+                Err(_) => continue,
+            };
+            // Mode 2 is a 2D fix, mode 3 is a 3D fix.
+            if "TPV" != report.class || report.mode < 2 {
+                continue;
+            }
+            if let Some(error) = report.error() {
+                if error > self.max_error {
+                    debug!("Ignoring fix with error estimate {}m", error);
+                    continue;
+                }
+            }
+            if let (Some(latitude), Some(longitude)) = (report.lat, report.lon)
+            {
+                return Ok(Location {
+                    coordinates: Coordinates {
+                        latitude,
+                        longitude,
+                    },
+                    accuracy: report.error(),
+                });
+            }
+        }
+        Err(anyhow!("gpsd closed the connection"))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
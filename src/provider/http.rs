@@ -0,0 +1,556 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            http.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Provider querying a Geo-IP web service over HTTP.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::Detection;
+use crate::location::{Coordinates, Location};
+use anyhow::{anyhow, Context};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// The response body is the timezone name.
+    Text,
+
+    /// The response body is a JSON document containing the timezone name.
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    Ipapi,
+    IpApi,
+    Ipinfo,
+}
+
+impl Preset {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Ipapi => "ipapi",
+            Self::IpApi => "ip-api",
+            Self::Ipinfo => "ipinfo",
+        }
+    }
+
+    fn config(&self) -> HttpConfig {
+        let (url, latitude, longitude, key) = match self {
+            Self::Ipapi => {
+                ("https://ipapi.co/json/", "latitude", "longitude", "key")
+            }
+            // TLS is reserved for paying customers of ip-api.
+            Self::IpApi => ("http://ip-api.com/json/", "lat", "lon", "key"),
+            // ipinfo reports the position as a single "lat,lon" string.
+            Self::Ipinfo => ("https://ipinfo.io/json", "", "", "token"),
+        };
+        let field = |name: &str| match name {
+            "" => None,
+            name => Some(name.to_string()),
+        };
+        HttpConfig {
+            preset: None,
+            url: Some(url.to_string()),
+            format: Some(Format::Json),
+            field: Some("timezone".to_string()),
+            latitude_field: field(latitude),
+            longitude_field: field(longitude),
+            api_key_parameter: field(key),
+            ..HttpConfig::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpConfig {
+    /// One of the well-known services. Any other field that is also set
+    /// overrides the value provided by the preset.
+    pub preset: Option<Preset>,
+    pub url: Option<String>,
+    pub format: Option<Format>,
+
+    /// Dotted path to the timezone name in a JSON response, e.g.
+    /// `location.time_zone`.
+    pub field: Option<String>,
+
+    /// Dotted paths to the latitude and longitude in a JSON response, if the
+    /// service reports them.
+    pub latitude_field: Option<String>,
+    pub longitude_field: Option<String>,
+
+    /// Query parameter to send the API key in.
+    pub api_key_parameter: Option<String>,
+    pub api_key: Option<String>,
+
+    /// Name of a systemd credential (see LoadCredential=) holding the API
+    /// key. Takes precedence over api_key when running under systemd.
+    pub api_key_credential: Option<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            preset: Some(Preset::Ipapi),
+            url: None,
+            format: None,
+            field: None,
+            latitude_field: None,
+            longitude_field: None,
+            api_key_parameter: None,
+            api_key: None,
+            api_key_credential: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// The API key, from the credential if there is one, or else from the
+    /// configuration.
+    fn api_key(&self) -> Result<Option<String>, anyhow::Error> {
+        if let Some(name) = &self.api_key_credential {
+            match env::var_os("CREDENTIALS_DIRECTORY") {
+                Some(directory) => {
+                    let key =
+                        fs::read_to_string(Path::new(&directory).join(name))
+                            .with_context(|| {
+                            format!("Couldn't read credential {}", name)
+                        })?;
+                    return Ok(Some(key.trim().to_string()));
+                }
+                None => debug!(
+                    "CREDENTIALS_DIRECTORY is not set, ignoring credential {}",
+                    name
+                ),
+            }
+        }
+        Ok(self.api_key.clone())
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        match HttpProvider::new(self) {
+            Ok(_) => Vec::new(),
+            Err(error) => vec![format!("{:#}", error)],
+        }
+    }
+}
+
+pub struct HttpProvider {
+    client: reqwest::Client,
+    name: String,
+    url: String,
+    format: Format,
+    field: Vec<String>,
+    latitude_field: Vec<String>,
+    longitude_field: Vec<String>,
+}
+
+fn split_path(path: Option<&String>) -> Vec<String> {
+    match path {
+        Some(path) => path.split('.').map(|key| key.to_string()).collect(),
+        None => Vec::new(),
+    }
+}
+
+impl HttpProvider {
+    pub fn new(config: &HttpConfig) -> Result<Self, anyhow::Error> {
+        let preset = config.preset.map(|preset| preset.config());
+        let preset = preset.as_ref();
+        let url = config
+            .url
+            .as_ref()
+            .or_else(|| preset.and_then(|preset| preset.url.as_ref()))
+            .ok_or_else(|| anyhow!("HTTP provider requires a url"))?
+            .clone();
+        let format = config
+            .format
+            .or_else(|| preset.and_then(|preset| preset.format))
+            .unwrap_or(Format::Text);
+        let field = config
+            .field
+            .as_ref()
+            .or_else(|| preset.and_then(|preset| preset.field.as_ref()));
+        let latitude_field = config.latitude_field.as_ref().or_else(|| {
+            preset.and_then(|preset| preset.latitude_field.as_ref())
+        });
+        let longitude_field = config.longitude_field.as_ref().or_else(|| {
+            preset.and_then(|preset| preset.longitude_field.as_ref())
+        });
+
+        let field = match (format, field) {
+            (Format::Text, _) => Vec::new(),
+            (Format::Json, Some(field)) => split_path(Some(field)),
+            (Format::Json, None) => {
+                return Err(anyhow!(
+                    "HTTP provider {} requires a field for JSON responses",
+                    url
+                ))
+            }
+        };
+
+        let mut parsed = reqwest::Url::parse(&url)
+            .with_context(|| format!("Invalid url {:?}", url))?;
+        let name = match config.preset {
+            Some(preset) => preset.name().to_string(),
+            None => parsed.host_str().unwrap_or(&url).to_string(),
+        };
+
+        let parameter = config.api_key_parameter.as_ref().or_else(|| {
+            preset.and_then(|preset| preset.api_key_parameter.as_ref())
+        });
+        if let Some(key) = config.api_key()? {
+            let parameter = parameter.ok_or_else(|| {
+                anyhow!("HTTP provider {} requires an api_key_parameter", name)
+            })?;
+            parsed.query_pairs_mut().append_pair(parameter, &key);
+        }
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            name,
+            url: parsed.to_string(),
+            format,
+            field,
+            latitude_field: split_path(latitude_field),
+            longitude_field: split_path(longitude_field),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub async fn detect(&self) -> Result<Detection, anyhow::Error> {
+        // The URL may contain the API key, so it's left out of errors.
+        let body = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| error.without_url())?
+            .text()
+            .await
+            .map_err(|error| error.without_url())?;
+        match self.format {
+            Format::Text => Ok(Detection {
+                timezone: body.trim().to_string(),
+                location: None,
+                rewritten_from: None,
+            }),
+            Format::Json => {
+                let document: Value = serde_json::from_str(&body)
+                    .context("Response is not valid JSON")?;
+                Ok(Detection {
+                    timezone: self.timezone(&document)?,
+                    location: self.location(&document),
+                    rewritten_from: None,
+                })
+            }
+        }
+    }
+
+    /// The timezone name in a JSON response.
+    fn timezone(&self, document: &Value) -> Result<String, anyhow::Error> {
+        Ok(resolve(document, &self.field)?
+            .as_str()
+            .ok_or_else(|| {
+                anyhow!("Field {:?} is not a string", self.field.join("."))
+            })?
+            .to_string())
+    }
+
+    /// The position reported in the response, if any.
+    fn location(&self, document: &Value) -> Option<Location> {
+        if self.latitude_field.is_empty() || self.longitude_field.is_empty() {
+            return None;
+        }
+        let coordinate = |path: &[String]| {
+            let value = resolve(document, path)
+                .map_err(|error| debug!("No position: {:#}", error))
+                .ok()?;
+            // Some services send numbers as strings.
+            value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        };
+        Some(Location {
+            coordinates: Coordinates {
+                latitude: coordinate(&self.latitude_field)?,
+                longitude: coordinate(&self.longitude_field)?,
+            },
+            accuracy: None,
+        })
+    }
+}
+
+/// Whether `cause` is a failed TLS handshake or certificate check. reqwest
+/// reports those as a connect error caused by the native-tls error, which
+/// some connectors wrap in an io::Error first.
+pub fn is_tls(cause: &(dyn Error + 'static)) -> bool {
+    let wrapped = cause
+        .downcast_ref::<io::Error>()
+        .and_then(|error| error.get_ref())
+        .is_some_and(|inner| inner.is::<native_tls::Error>());
+    wrapped || cause.is::<native_tls::Error>()
+}
+
+/// Walk a dotted field path through nested JSON objects.
+fn resolve<'a>(
+    document: &'a Value,
+    path: &[String],
+) -> Result<&'a Value, anyhow::Error> {
+    let mut value = document;
+    for key in path {
+        value = value
+            .as_object()
+            .and_then(|object| object.get(key))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Response has no field {:?} (in {:?})",
+                    key,
+                    path.join(".")
+                )
+            })?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::Failure;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn path(path: &str) -> Vec<String> {
+        split_path(Some(&path.to_string()))
+    }
+
+    fn provider(preset: Option<Preset>) -> HttpProvider {
+        HttpProvider::new(&HttpConfig {
+            preset,
+            url: Some("https://geoip.example/".to_string()),
+            format: Some(Format::Json),
+            field: Some("location.time_zone".to_string()),
+            ..HttpConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn nested_objects_are_walked() {
+        let document = json!({"location": {"time_zone": "Europe/Paris"}});
+        assert_eq!(
+            "Europe/Paris",
+            resolve(&document, &path("location.time_zone")).unwrap()
+        );
+        assert_eq!(
+            json!({"time_zone": "Europe/Paris"}),
+            *resolve(&document, &path("location")).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_missing_element_is_named() {
+        let document = json!({"location": {"tz": "Europe/Paris"}});
+        let error = resolve(&document, &path("location.time_zone"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"time_zone\""), "{}", error);
+
+        let error = resolve(&document, &path("place.time_zone"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"place\""), "{}", error);
+    }
+
+    #[test]
+    fn array_indices_are_not_followed() {
+        let document = json!({"zones": ["Europe/Paris"]});
+        let error = resolve(&document, &path("zones.0"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"0\""), "{}", error);
+    }
+
+    #[test]
+    fn a_leaf_that_is_not_a_string_is_an_error() {
+        let provider = provider(None);
+        for leaf in [json!(1), json!(null), json!(["Europe/Paris"])] {
+            let document = json!({"location": {"time_zone": leaf}});
+            let error = provider.timezone(&document).unwrap_err().to_string();
+            assert!(error.contains("is not a string"), "{}", error);
+        }
+        let document = json!({"location": {"time_zone": "Europe/Paris"}});
+        assert_eq!("Europe/Paris", provider.timezone(&document).unwrap());
+    }
+
+    /// Serve one connection on a local port, answering whatever is asked
+    /// with `response`.
+    async fn serve(response: &'static [u8]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            let _ = stream.write_all(response).await;
+        });
+        port
+    }
+
+    fn text(url: String) -> HttpProvider {
+        HttpProvider::new(&HttpConfig {
+            preset: None,
+            url: Some(url),
+            format: Some(Format::Text),
+            ..HttpConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_failed_handshake_is_a_tls_failure() {
+        let port = serve(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        let url = format!("https://127.0.0.1:{}/", port);
+        let error = text(url).detect().await.unwrap_err();
+        assert_eq!(Failure::Tls, Failure::of(&error), "{:#}", error);
+    }
+
+    #[tokio::test]
+    async fn only_tls_errors_are_tls_failures() {
+        // Refused, since nothing listens there once the listener has gone.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("https://127.0.0.1:{}/tls/certificate", port);
+        let error = text(url).detect().await.unwrap_err();
+        assert_eq!(Failure::Other, Failure::of(&error), "{:#}", error);
+
+        // Mentioning TLS doesn't make it a TLS failure.
+        let body = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        let port = serve(body).await;
+        let provider = HttpProvider::new(&HttpConfig {
+            preset: None,
+            url: Some(format!("http://127.0.0.1:{}/", port)),
+            format: Some(Format::Json),
+            field: Some("ssl_certificate".to_string()),
+            ..HttpConfig::default()
+        })
+        .unwrap();
+        let error = provider.detect().await.unwrap_err();
+        assert!(format!("{:#}", error).contains("ssl_certificate"));
+        assert_eq!(Failure::Other, Failure::of(&error), "{:#}", error);
+    }
+
+    #[test]
+    fn presets_map_their_fields() {
+        let provider = |preset| {
+            HttpProvider::new(&HttpConfig {
+                preset: Some(preset),
+                ..HttpConfig::default()
+            })
+            .unwrap()
+        };
+
+        let ipapi = provider(Preset::Ipapi);
+        assert_eq!("ipapi", ipapi.name());
+        assert_eq!("https://ipapi.co/json/", ipapi.url);
+        assert_eq!(Format::Json, ipapi.format);
+        assert_eq!(path("timezone"), ipapi.field);
+        assert_eq!(path("latitude"), ipapi.latitude_field);
+        assert_eq!(path("longitude"), ipapi.longitude_field);
+
+        let ip_api = provider(Preset::IpApi);
+        assert_eq!("ip-api", ip_api.name());
+        assert_eq!("http://ip-api.com/json/", ip_api.url);
+        assert_eq!(path("timezone"), ip_api.field);
+        assert_eq!(path("lat"), ip_api.latitude_field);
+        assert_eq!(path("lon"), ip_api.longitude_field);
+
+        let ipinfo = provider(Preset::Ipinfo);
+        assert_eq!("ipinfo", ipinfo.name());
+        assert_eq!("https://ipinfo.io/json", ipinfo.url);
+        assert_eq!(path("timezone"), ipinfo.field);
+        assert!(ipinfo.latitude_field.is_empty());
+        let document = json!({"timezone": "Europe/Paris", "loc": "1,2"});
+        assert!(ipinfo.location(&document).is_none());
+    }
+
+    #[test]
+    fn fields_that_are_set_override_the_preset() {
+        let provider = provider(Some(Preset::IpApi));
+        assert_eq!("ip-api", provider.name());
+        assert_eq!("https://geoip.example/", provider.url);
+        assert_eq!(path("location.time_zone"), provider.field);
+        assert_eq!(path("lat"), provider.latitude_field);
+    }
+
+    #[test]
+    fn preset_api_keys_go_in_the_preset_parameter() {
+        let provider = HttpProvider::new(&HttpConfig {
+            preset: Some(Preset::Ipinfo),
+            api_key: Some("secret".to_string()),
+            ..HttpConfig::default()
+        })
+        .unwrap();
+        assert_eq!("https://ipinfo.io/json?token=secret", provider.url);
+    }
+
+    #[test]
+    fn positions_may_be_numbers_or_strings() {
+        let provider = HttpProvider::new(&HttpConfig {
+            preset: Some(Preset::Ipapi),
+            ..HttpConfig::default()
+        })
+        .unwrap();
+        let document = json!({"latitude": 48.85, "longitude": "2.35"});
+        let location = provider.location(&document).unwrap();
+        assert_eq!(48.85, location.coordinates.latitude);
+        assert_eq!(2.35, location.coordinates.longitude);
+        assert!(provider.location(&json!({"latitude": 48.85})).is_none());
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,569 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            mod.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Sources of timezone information.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::config::Config;
+use crate::fault::{self, Fault};
+use crate::location::Location;
+use crate::statistics::Statistics;
+use crate::status::timestamp;
+use crate::throttle::{Throttle, Verdict};
+use crate::zone::{self, Pattern};
+use anyhow::anyhow;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+
+#[cfg(feature = "provider-exec")]
+mod exec;
+#[cfg(feature = "provider-file")]
+mod file;
+#[cfg(feature = "provider-gpsd")]
+mod gpsd;
+#[cfg(feature = "provider-http")]
+mod http;
+mod worker;
+
+#[cfg(feature = "provider-exec")]
+pub use exec::ExecConfig;
+#[cfg(feature = "provider-exec")]
+use exec::ExecProvider;
+#[cfg(feature = "provider-file")]
+pub use file::FileConfig;
+#[cfg(feature = "provider-file")]
+use file::{FileProvider, StdinProvider};
+#[cfg(feature = "provider-gpsd")]
+pub use gpsd::GpsdConfig;
+#[cfg(feature = "provider-gpsd")]
+use gpsd::GpsdProvider;
+#[cfg(feature = "provider-http")]
+pub use http::HttpConfig;
+#[cfg(feature = "provider-http")]
+use http::HttpProvider;
+pub use worker::{Handle, Report, Worker};
+
+/// The kinds of provider compiled into this binary.
+pub const COMPILED: &[&str] = &[
+    #[cfg(feature = "provider-http")]
+    "http",
+    #[cfg(feature = "provider-exec")]
+    "exec",
+    #[cfg(feature = "provider-file")]
+    "file",
+    #[cfg(feature = "provider-file")]
+    "stdin",
+    #[cfg(feature = "provider-gpsd")]
+    "gpsd",
+];
+
+/// What is known about the connection that triggered a detection.
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    /// D-Bus object path of the station that connected.
+    pub station: Option<String>,
+    pub ssid: Option<String>,
+
+    /// The ID of the connectivity session the trigger belongs to, once the
+    /// daemon has started one.
+    pub session: Option<String>,
+}
+
+/// The outcome of a successful detection.
+#[derive(Clone, Debug)]
+pub struct Detection {
+    pub timezone: String,
+
+    /// Where the provider thinks this host is, if it said.
+    pub location: Option<Location>,
+
+    /// What the provider said, if `timezone` is a rewrite of it.
+    pub rewritten_from: Option<String>,
+}
+
+/// Why a detection failed, as far as it matters to when it's worth trying
+/// again.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Failure {
+    /// A TLS handshake or certificate check failed, most likely because the
+    /// clock is wrong.
+    Tls,
+    Other,
+}
+
+impl Failure {
+    /// Classify `error` by the types of its causes, rather than their
+    /// wording, which changes between releases and can quote URLs.
+    #[cfg_attr(not(feature = "provider-http"), allow(unused_variables))]
+    pub fn of(error: &anyhow::Error) -> Self {
+        #[cfg(feature = "provider-http")]
+        if error.chain().any(http::is_tls) {
+            return Self::Tls;
+        }
+        Self::Other
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Tls => "tls",
+            Self::Other => "other",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProviderConfig {
+    #[cfg(feature = "provider-http")]
+    Http(HttpConfig),
+    #[cfg(feature = "provider-exec")]
+    Exec(ExecConfig),
+    #[cfg(feature = "provider-file")]
+    File(FileConfig),
+    #[cfg(feature = "provider-file")]
+    Stdin,
+    #[cfg(feature = "provider-gpsd")]
+    Gpsd(GpsdConfig),
+}
+
+/// Longest timeout that we consider plausible for a single provider.
+#[cfg(any(feature = "provider-exec", feature = "provider-gpsd"))]
+const MAX_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[cfg(any(feature = "provider-exec", feature = "provider-gpsd"))]
+fn validate_timeout(timeout: Duration) -> Vec<String> {
+    if timeout.is_zero() {
+        vec!["timeout must not be zero".into()]
+    } else if timeout > MAX_TIMEOUT {
+        vec![format!("timeout must be at most {:?}", MAX_TIMEOUT)]
+    } else {
+        Vec::new()
+    }
+}
+
+impl ProviderConfig {
+    pub fn validate(&self) -> Vec<String> {
+        match *self {
+            #[cfg(feature = "provider-http")]
+            Self::Http(ref config) => config.validate(),
+            #[cfg(feature = "provider-exec")]
+            Self::Exec(ref config) => config.validate(),
+            #[cfg(feature = "provider-file")]
+            Self::File(ref config) => config.validate(),
+            #[cfg(feature = "provider-file")]
+            Self::Stdin => Vec::new(),
+            #[cfg(feature = "provider-gpsd")]
+            Self::Gpsd(ref config) => config.validate(),
+        }
+    }
+
+    /// The providers used when the configuration doesn't list any.
+    pub fn defaults() -> Vec<Self> {
+        #[cfg(feature = "provider-http")]
+        return vec![Self::Http(HttpConfig::default())];
+        #[cfg(not(feature = "provider-http"))]
+        return Vec::new();
+    }
+}
+
+enum Provider {
+    #[cfg(feature = "provider-http")]
+    Http(HttpProvider),
+    #[cfg(feature = "provider-exec")]
+    Exec(ExecProvider),
+    #[cfg(feature = "provider-file")]
+    File(FileProvider),
+    #[cfg(feature = "provider-file")]
+    Stdin(StdinProvider),
+    #[cfg(feature = "provider-gpsd")]
+    Gpsd(GpsdProvider),
+}
+
+// The matches below dereference `self` so that they are still exhaustive in
+// builds with no providers at all.
+impl Provider {
+    fn new(config: &ProviderConfig) -> Result<Self, anyhow::Error> {
+        match *config {
+            #[cfg(feature = "provider-http")]
+            ProviderConfig::Http(ref config) => {
+                Ok(Self::Http(HttpProvider::new(config)?))
+            }
+            #[cfg(feature = "provider-exec")]
+            ProviderConfig::Exec(ref config) => {
+                Ok(Self::Exec(ExecProvider::new(config)?))
+            }
+            #[cfg(feature = "provider-file")]
+            ProviderConfig::File(ref config) => {
+                Ok(Self::File(FileProvider::new(config)))
+            }
+            #[cfg(feature = "provider-file")]
+            ProviderConfig::Stdin => Ok(Self::Stdin(StdinProvider::default())),
+            #[cfg(feature = "provider-gpsd")]
+            ProviderConfig::Gpsd(ref config) => {
+                Ok(Self::Gpsd(GpsdProvider::new(config)))
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        match *self {
+            #[cfg(feature = "provider-http")]
+            Self::Http(ref provider) => provider.name(),
+            #[cfg(feature = "provider-exec")]
+            Self::Exec(ref provider) => provider.name(),
+            #[cfg(feature = "provider-file")]
+            Self::File(ref provider) => provider.name(),
+            #[cfg(feature = "provider-file")]
+            Self::Stdin(ref provider) => provider.name(),
+            #[cfg(feature = "provider-gpsd")]
+            Self::Gpsd(ref provider) => provider.name(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "provider-exec"), allow(unused_variables))]
+    #[cfg_attr(
+        not(any(
+            feature = "provider-http",
+            feature = "provider-exec",
+            feature = "provider-file",
+            feature = "provider-gpsd"
+        )),
+        allow(unreachable_code)
+    )]
+    async fn detect(
+        &self,
+        context: &Context,
+    ) -> Result<Detection, anyhow::Error> {
+        let mut detection: Detection = match *self {
+            #[cfg(feature = "provider-http")]
+            Self::Http(ref provider) => provider.detect().await?,
+            #[cfg(feature = "provider-exec")]
+            Self::Exec(ref provider) => Detection {
+                timezone: provider.detect(context).await?,
+                location: None,
+                rewritten_from: None,
+            },
+            #[cfg(feature = "provider-file")]
+            Self::File(ref provider) => Detection {
+                timezone: provider.detect().await?,
+                location: None,
+                rewritten_from: None,
+            },
+            #[cfg(feature = "provider-file")]
+            Self::Stdin(ref provider) => Detection {
+                timezone: provider.detect().await?,
+                location: None,
+                rewritten_from: None,
+            },
+            #[cfg(feature = "provider-gpsd")]
+            Self::Gpsd(ref provider) => provider.detect().await?,
+        };
+        detection.timezone = zone::canonicalize(&detection.timezone);
+        Ok(detection)
+    }
+}
+
+/// How a provider has fared recently.
+#[derive(Clone, Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    last_success: Option<SystemTime>,
+    last_failure: Option<Instant>,
+
+    /// The provider is skipped until then.
+    demoted_until: Option<(Instant, SystemTime)>,
+}
+
+impl Health {
+    fn is_demoted(&self, now: Instant) -> bool {
+        self.demoted_until
+            .is_some_and(|(deadline, _)| now < deadline)
+    }
+}
+
+/// The health of a provider, for the status file.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProviderHealth {
+    pub name: String,
+    pub consecutive_failures: u32,
+
+    /// When the provider last gave an answer, in RFC 3339 format.
+    pub last_success: Option<String>,
+
+    /// When the provider will next be consulted, if it's been demoted.
+    pub demoted_until: Option<String>,
+}
+
+/// The configured providers, consulted in order until one of them produces a
+/// timezone. A provider that fails demote_after_failures times in a row is
+/// skipped for demote_cooldown, so that a provider which is down doesn't
+/// cost its whole timeout every time.
+pub struct ProviderChain {
+    providers: Vec<Provider>,
+    health: Vec<Health>,
+    demote_after_failures: u32,
+    demote_cooldown: Duration,
+
+    /// Rules replacing detected timezones, the first match winning.
+    rewrites: Vec<(Pattern, String)>,
+
+    /// Timezones that are never a plausible answer, such as the UTC some
+    /// providers return when they have no data.
+    denied: Vec<Pattern>,
+
+    /// The index of the provider that answered the last detection, if one
+    /// did.
+    answered: Option<usize>,
+
+    /// Keeps a provider that's down for long from filling the log.
+    throttle: Throttle,
+}
+
+impl ProviderChain {
+    pub fn new(config: &Config) -> Result<Self, anyhow::Error> {
+        let providers: Vec<Provider> = config
+            .providers
+            .iter()
+            .map(Provider::new)
+            .collect::<Result<_, _>>()?;
+        let health = vec![Health::default(); providers.len()];
+        let denied = config
+            .denied_timezones
+            .iter()
+            .map(|pattern| Pattern::new(pattern))
+            .collect();
+        Ok(Self {
+            providers,
+            health,
+            demote_after_failures: config.demote_after_failures,
+            demote_cooldown: config.demote_cooldown,
+            rewrites: config
+                .rewrites
+                .iter()
+                .map(|rule| {
+                    (Pattern::new(&rule.from), zone::canonicalize(&rule.to))
+                })
+                .collect(),
+            denied,
+            answered: None,
+            throttle: Throttle::default(),
+        })
+    }
+
+    /// Query `provider`, treating a denied timezone as a failure.
+    async fn query(
+        &self,
+        provider: &Provider,
+        context: &Context,
+    ) -> Result<Detection, anyhow::Error> {
+        if fault::inject(Fault::ProviderTimeout) {
+            return Err(anyhow!("Timed out (injected fault)"));
+        }
+        let mut detection = provider.detect(context).await?;
+        let rewrite = self
+            .rewrites
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&detection.timezone));
+        if let Some((_, replacement)) = rewrite {
+            info!(
+                "Rewriting {} from {} to {}",
+                detection.timezone,
+                provider.name(),
+                replacement
+            );
+            detection.rewritten_from = Some(std::mem::replace(
+                &mut detection.timezone,
+                replacement.clone(),
+            ));
+        }
+        zone::validate(&detection.timezone)?;
+
+        let timezone = &detection.timezone;
+        if zone::is_denied(&self.denied, timezone) {
+            return Err(anyhow!("{} is in denied_timezones", timezone));
+        }
+        Ok(detection)
+    }
+
+    /// Record the outcome of consulting the provider at `index`.
+    fn record(
+        &mut self,
+        index: usize,
+        succeeded: bool,
+        statistics: &mut Statistics,
+    ) {
+        let health = &mut self.health[index];
+        if succeeded {
+            *health = Health {
+                last_success: Some(SystemTime::now()),
+                ..Health::default()
+            };
+            return;
+        }
+
+        health.consecutive_failures += 1;
+        health.last_failure = Some(Instant::now());
+        if 0 != self.demote_after_failures
+            && health.consecutive_failures >= self.demote_after_failures
+        {
+            warn!(
+                "Skipping provider {} for {}s after {} failures in a row",
+                self.providers[index].name(),
+                self.demote_cooldown.as_secs(),
+                health.consecutive_failures
+            );
+            health.demoted_until = Some((
+                Instant::now() + self.demote_cooldown,
+                SystemTime::now() + self.demote_cooldown,
+            ));
+            statistics.demotions += 1;
+        }
+    }
+
+    /// The provider to consult anyway when every one of them is demoted: the
+    /// one that failed least recently.
+    fn fallback(&self, now: Instant) -> Option<usize> {
+        if !self.health.iter().all(|health| health.is_demoted(now)) {
+            return None;
+        }
+        (0..self.health.len())
+            .min_by_key(|&index| self.health[index].last_failure)
+    }
+
+    pub async fn detect(
+        &mut self,
+        context: &Context,
+        statistics: &mut Statistics,
+    ) -> Result<Detection, anyhow::Error> {
+        let now = Instant::now();
+        let fallback = self.fallback(now);
+        self.answered = None;
+
+        // A TLS failure is kept in preference to any other, since it means
+        // trying again may help once the clock is right.
+        let mut failure: Option<anyhow::Error> = None;
+        for index in 0..self.providers.len() {
+            let provider = &self.providers[index];
+            if self.health[index].is_demoted(now) && Some(index) != fallback {
+                debug!("Skipping demoted provider {}", provider.name());
+                continue;
+            }
+            let name = provider.name().to_string();
+            match self.query(provider, context).await {
+                Ok(detection) => {
+                    self.record(index, true, statistics);
+                    if detection.rewritten_from.is_some() {
+                        statistics.rewrites += 1;
+                    }
+                    self.answered = Some(index);
+                    if let Some(streak) = self.throttle.succeeded(&name) {
+                        info!("Provider {} recovered after {}", name, streak);
+                    }
+                    return Ok(detection);
+                }
+                Err(error) => {
+                    let class = Failure::of(&error).name();
+                    match self.throttle.failed(&name, class) {
+                        Verdict::Log => {
+                            warn!("Provider {} failed: {:#}", name, error)
+                        }
+                        Verdict::Summarize(streak) => warn!(
+                            "Provider {} still failing: {}, last error: {:#}",
+                            name, streak, error
+                        ),
+                        Verdict::Suppress => {
+                            debug!("Provider {} failed: {:#}", name, error)
+                        }
+                    }
+                    self.record(index, false, statistics);
+                    let tls = Failure::Tls == Failure::of(&error);
+                    if tls || failure.is_none() {
+                        failure = Some(error);
+                    }
+                }
+            }
+        }
+        let message = "No provider could determine the timezone";
+        Err(match failure {
+            Some(error) => error.context(message),
+            None => anyhow!(message),
+        })
+    }
+
+    /// Query every provider, regardless of whether an earlier one succeeded.
+    pub async fn probe(
+        &self,
+        context: &Context,
+    ) -> Vec<(String, Result<String, anyhow::Error>)> {
+        let mut results = Vec::new();
+        for provider in &self.providers {
+            let result = self
+                .query(provider, context)
+                .await
+                .map(|detection| detection.timezone);
+            results.push((provider.name().to_string(), result));
+        }
+        results
+    }
+
+    /// The name of the provider that answered the last detection, if one
+    /// did.
+    pub fn answered(&self) -> Option<&str> {
+        self.answered.map(|index| self.providers[index].name())
+    }
+
+    /// The names of the providers, in the order they're consulted.
+    pub fn names(&self) -> Vec<String> {
+        self.providers
+            .iter()
+            .map(|provider| provider.name().to_string())
+            .collect()
+    }
+
+    /// How each provider has fared, in the order they're consulted.
+    pub fn health(&self) -> Vec<ProviderHealth> {
+        self.providers
+            .iter()
+            .zip(&self.health)
+            .map(|(provider, health)| ProviderHealth {
+                name: provider.name().to_string(),
+                consecutive_failures: health.consecutive_failures,
+                last_success: health.last_success.map(timestamp),
+                demoted_until: health
+                    .demoted_until
+                    .map(|(_, time)| timestamp(time)),
+            })
+            .collect()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
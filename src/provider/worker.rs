@@ -0,0 +1,250 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            worker.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Runs detections on a task of their own.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use super::{Context, Detection, ProviderChain, ProviderHealth};
+use crate::config::Config;
+use crate::statistics::Statistics;
+use anyhow::anyhow;
+use log::warn;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// The outcome of a detection, with what the chain learned along the way.
+pub struct Report {
+    pub result: Result<Detection, anyhow::Error>,
+
+    /// The provider that answered, if one did.
+    pub answered: Option<String>,
+    pub health: Vec<ProviderHealth>,
+
+    /// The counters the detection touched, starting from zero.
+    pub statistics: Statistics,
+}
+
+struct Request {
+    context: Context,
+    reply: oneshot::Sender<Report>,
+}
+
+/// Owns the provider chain on a spawned task, so that TLS handshakes and
+/// parsing never hold up the task handling D-Bus signals. The daemon's state
+/// stays with the caller, and all that crosses over is the context and the
+/// report; so abandoning a detection (or the task dying mid-request) only
+/// ever fails that detection.
+pub struct Worker {
+    config: Config,
+    names: Vec<String>,
+    requests: mpsc::UnboundedSender<Request>,
+    task: JoinHandle<()>,
+}
+
+async fn serve(
+    mut chain: ProviderChain,
+    mut requests: mpsc::UnboundedReceiver<Request>,
+) {
+    while let Some(mut request) = requests.recv().await {
+        let mut statistics = Statistics::default();
+        // A detection nobody's waiting for any more is abandoned, rather
+        // than holding up the next one.
+        let result = tokio::select! {
+            result = chain.detect(&request.context, &mut statistics) => result,
+            _ = request.reply.closed() => continue,
+        };
+        let _ = request.reply.send(Report {
+            result,
+            answered: chain.answered().map(str::to_string),
+            health: chain.health(),
+            statistics,
+        });
+    }
+}
+
+impl Worker {
+    pub fn spawn(config: &Config) -> Result<Self, anyhow::Error> {
+        let chain = ProviderChain::new(config)?;
+        let names = chain.names();
+        let (requests, task) = Self::start(chain);
+        Ok(Self {
+            config: config.clone(),
+            names,
+            requests,
+            task,
+        })
+    }
+
+    fn start(
+        chain: ProviderChain,
+    ) -> (mpsc::UnboundedSender<Request>, JoinHandle<()>) {
+        let (requests, receiver) = mpsc::unbounded_channel();
+        (requests, tokio::spawn(serve(chain, receiver)))
+    }
+
+    /// The names of the providers, in the order they're consulted.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// A handle to ask the task for detections with. If the task has died
+    /// since the last detection, it's started again, without the health it
+    /// had built up.
+    pub fn handle(&mut self) -> Result<Handle, anyhow::Error> {
+        if self.task.is_finished() {
+            warn!("The provider task stopped, restarting it");
+            (self.requests, self.task) =
+                Self::start(ProviderChain::new(&self.config)?);
+        }
+        Ok(Handle(self.requests.clone()))
+    }
+}
+
+/// Asks the provider task for detections without borrowing the worker, so
+/// that a detection can be waited on alongside everything else.
+#[derive(Clone)]
+pub struct Handle(mpsc::UnboundedSender<Request>);
+
+impl Handle {
+    /// Consult the providers. Dropping the future abandons the detection.
+    pub async fn detect(
+        &self,
+        context: &Context,
+    ) -> Result<Report, anyhow::Error> {
+        let (reply, response) = oneshot::channel();
+        self.0
+            .send(Request {
+                context: context.clone(),
+                reply,
+            })
+            .map_err(|_| anyhow!("The provider task has stopped"))?;
+        response
+            .await
+            .map_err(|_| anyhow!("The provider task stopped mid-detection"))
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(all(test, feature = "provider-exec"))]
+mod tests {
+    use super::*;
+    use crate::provider::{ExecConfig, ProviderConfig};
+    use std::time::Duration;
+
+    /// A worker whose only provider answers Europe/Berlin after `delay`.
+    fn worker(delay: &str) -> Worker {
+        let config = Config {
+            providers: vec![ProviderConfig::Exec(ExecConfig {
+                command: vec![format!("sleep {}; echo Europe/Berlin", delay)],
+                shell: true,
+                timeout: Duration::from_secs(5),
+            })],
+            ..Config::default()
+        };
+        Worker::spawn(&config).unwrap()
+    }
+
+    async fn abort(worker: &Worker) {
+        worker.task.abort();
+        while !worker.task.is_finished() {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn detects(worker: &mut Worker) -> bool {
+        let handle = worker.handle().unwrap();
+        match handle.detect(&Context::default()).await {
+            Ok(report) => report
+                .result
+                .is_ok_and(|detection| "Europe/Berlin" == detection.timezone),
+            Err(_) => false,
+        }
+    }
+
+    #[tokio::test]
+    async fn recovers_when_aborted_before_the_first_detection() {
+        let mut worker = worker("0");
+        abort(&worker).await;
+        assert!(detects(&mut worker).await);
+    }
+
+    #[tokio::test]
+    async fn recovers_when_aborted_between_detections() {
+        let mut worker = worker("0");
+        assert!(detects(&mut worker).await);
+        abort(&worker).await;
+        assert!(detects(&mut worker).await);
+    }
+
+    #[tokio::test]
+    async fn recovers_when_aborted_mid_detection() {
+        let mut worker = worker("0.2");
+        let handle = worker.handle().unwrap();
+        let detection = tokio::spawn(async move {
+            handle.detect(&Context::default()).await.map(|_| ())
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        abort(&worker).await;
+        let error = detection.await.unwrap().unwrap_err();
+        assert!(format!("{}", error).contains("mid-detection"));
+        assert!(detects(&mut worker).await);
+    }
+
+    #[tokio::test]
+    async fn a_stale_handle_fails_rather_than_waiting() {
+        let mut worker = worker("0");
+        let handle = worker.handle().unwrap();
+        abort(&worker).await;
+        assert!(handle.detect(&Context::default()).await.is_err());
+        assert!(detects(&mut worker).await);
+    }
+
+    #[tokio::test]
+    async fn an_abandoned_detection_doesnt_hold_up_the_next() {
+        let mut worker = worker("0.5");
+        let handle = worker.handle().unwrap();
+        let context = Context::default();
+        let abandoned = tokio::time::timeout(
+            Duration::from_millis(50),
+            handle.detect(&context),
+        );
+        assert!(abandoned.await.is_err());
+        let started = std::time::Instant::now();
+        assert!(detects(&mut worker).await);
+        assert!(started.elapsed() < Duration::from_millis(900));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
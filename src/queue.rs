@@ -0,0 +1,233 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            queue.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Orders and coalesces requests to update the timezone.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::provider::Context;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// What asked for an update.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Source {
+    /// A station connected.
+    Connection,
+
+    /// The clock was synchronized after a TLS failure.
+    Synchronized,
+
+    /// SIGUSR1.
+    Signal,
+
+    /// systemd-resolved learned DNS servers for a link.
+    Dns,
+
+    /// A command on the control socket.
+    Socket,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connection => write!(f, "connection"),
+            Self::Synchronized => write!(f, "clock synchronization"),
+            Self::Signal => write!(f, "signal"),
+            Self::Dns => write!(f, "DNS configuration"),
+            Self::Socket => write!(f, "control socket"),
+        }
+    }
+}
+
+/// A request to update the timezone.
+#[derive(Clone, Debug)]
+pub struct Trigger {
+    /// Increases with every trigger, so that the result of an older one can
+    /// be told from that of a newer one.
+    pub sequence: u64,
+    pub source: Source,
+    pub context: Context,
+    pub force: bool,
+}
+
+/// The sequence number of the trigger whose result was last applied, so
+/// that the result of an older one, finishing later, isn't applied after it.
+#[derive(Default)]
+pub struct Applied(u64);
+
+impl Applied {
+    /// Whether the result of the trigger numbered `sequence` is older than
+    /// the one last applied.
+    pub fn is_stale(&self, sequence: u64) -> bool {
+        sequence < self.0
+    }
+
+    pub fn record(&mut self, sequence: u64) {
+        self.0 = self.0.max(sequence);
+    }
+}
+
+/// Triggers waiting for the update in progress to finish. A trigger for a
+/// station that already has one waiting replaces it.
+#[derive(Default)]
+pub struct UpdateQueue {
+    next: u64,
+    triggers: VecDeque<Trigger>,
+}
+
+impl UpdateQueue {
+    /// Queue an update. Returns true if it replaced one already waiting for
+    /// the same station, in which case the update is forced if either was.
+    pub fn push(
+        &mut self,
+        source: Source,
+        context: Context,
+        force: bool,
+    ) -> bool {
+        self.next += 1;
+        let mut trigger = Trigger {
+            sequence: self.next,
+            source,
+            context,
+            force,
+        };
+        let existing = self.triggers.iter().position(|queued| {
+            queued.context.station == trigger.context.station
+        });
+        let coalesced = match existing {
+            Some(index) => {
+                let queued = self.triggers.remove(index);
+                trigger.force |= queued.is_some_and(|queued| queued.force);
+                true
+            }
+            None => false,
+        };
+        self.triggers.push_back(trigger);
+        coalesced
+    }
+
+    /// Drop any unforced update waiting for `station`, which has
+    /// disconnected.
+    pub fn cancel(&mut self, station: &Option<String>) {
+        self.triggers.retain(|trigger| {
+            trigger.force || trigger.context.station != *station
+        });
+    }
+
+    pub fn pop(&mut self) -> Option<Trigger> {
+        self.triggers.pop_front()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.triggers.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(station: &str) -> Context {
+        Context {
+            station: Some(station.to_string()),
+            ..Context::default()
+        }
+    }
+
+    fn drain(queue: &mut UpdateQueue) -> Vec<Trigger> {
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn triggers_for_a_station_are_coalesced() {
+        let mut queue = UpdateQueue::default();
+        assert!(!queue.push(Source::Connection, context("wlan0"), false));
+        assert!(!queue.push(Source::Dns, context("wlan1"), false));
+        assert!(queue.push(Source::Socket, context("wlan0"), true));
+        assert!(queue.push(Source::Dns, context("wlan0"), false));
+        assert_eq!(2, queue.depth());
+
+        // The latest takes the place of the others, forced if any of them
+        // was, and goes to the back.
+        let triggers = drain(&mut queue);
+        assert_eq!(Some("wlan1"), triggers[0].context.station.as_deref());
+        assert_eq!(Some("wlan0"), triggers[1].context.station.as_deref());
+        assert_eq!(Source::Dns, triggers[1].source);
+        assert!(triggers[1].force);
+        assert_eq!(0, queue.depth());
+    }
+
+    #[test]
+    fn sequence_numbers_increase_with_every_trigger() {
+        let mut queue = UpdateQueue::default();
+        queue.push(Source::Connection, context("wlan0"), false);
+        queue.push(Source::Connection, context("wlan0"), false);
+        queue.push(Source::Connection, context("wlan1"), false);
+        let sequences: Vec<u64> = drain(&mut queue)
+            .iter()
+            .map(|trigger| trigger.sequence)
+            .collect();
+        assert_eq!(vec![2, 3], sequences);
+        queue.push(Source::Signal, Context::default(), false);
+        assert_eq!(4, queue.pop().unwrap().sequence);
+    }
+
+    #[test]
+    fn disconnecting_cancels_all_but_forced_triggers() {
+        let mut queue = UpdateQueue::default();
+        queue.push(Source::Connection, context("wlan0"), false);
+        queue.push(Source::Socket, context("wlan1"), true);
+        queue.push(Source::Connection, context("wlan2"), false);
+        queue.cancel(&Some("wlan0".to_string()));
+        queue.cancel(&Some("wlan1".to_string()));
+        let stations: Vec<Option<String>> = drain(&mut queue)
+            .into_iter()
+            .map(|trigger| trigger.context.station)
+            .collect();
+        assert_eq!(
+            vec![Some("wlan1".to_string()), Some("wlan2".to_string())],
+            stations
+        );
+    }
+
+    #[test]
+    fn results_finishing_in_order_are_all_applied() {
+        let mut applied = Applied::default();
+        for sequence in 1..=3 {
+            assert!(!applied.is_stale(sequence));
+            applied.record(sequence);
+        }
+        // A trigger's result may be applied again, e.g. on reconfirmation.
+        assert!(!applied.is_stale(3));
+        assert!(applied.is_stale(2));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
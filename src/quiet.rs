@@ -0,0 +1,116 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            quiet.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Hours of the day during which the timezone isn't changed.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveTime, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+const DAY: u32 = 24 * 60 * 60;
+
+/// A window of local time, e.g. "01:00-05:00", that may wrap past midnight,
+/// e.g. "23:00-06:00".
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TryFrom<String> for QuietHours {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (start, end) = value.split_once('-').ok_or_else(|| {
+            anyhow!(
+                "Expected quiet hours like \"01:00-05:00\", not {:?}",
+                value
+            )
+        })?;
+        let time = |text: &str| {
+            NaiveTime::parse_from_str(text.trim(), "%H:%M").map_err(|_| {
+                anyhow!("Expected a time like \"01:00\", not {:?}", text)
+            })
+        };
+        let (start, end) = (time(start)?, time(end)?);
+        if start == end {
+            return Err(anyhow!("Quiet hours {:?} are empty", value));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl From<QuietHours> for String {
+    fn from(value: QuietHours) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl QuietHours {
+    /// How long until the window ends, if `now` falls in it, in `timezone`
+    /// (UTC if it's not known). A DST transition within the window may make
+    /// this an hour out.
+    pub fn remaining(
+        &self,
+        timezone: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Option<Duration> {
+        let timezone = timezone
+            .and_then(|name| name.parse::<Tz>().ok())
+            .unwrap_or(Tz::UTC);
+        let now = now.with_timezone(&timezone).time();
+        let seconds = |time: NaiveTime| time.num_seconds_from_midnight();
+        let (now, start, end) =
+            (seconds(now), seconds(self.start), seconds(self.end));
+        let quiet = match start < end {
+            true => start <= now && now < end,
+            false => start <= now || now < end,
+        };
+        quiet.then(|| Duration::from_secs(u64::from((end + DAY - now) % DAY)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
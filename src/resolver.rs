@@ -0,0 +1,74 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            resolver.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Offline resolution of coordinates to timezones.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::location::Coordinates;
+use anyhow::anyhow;
+use tzf_rs::DefaultFinder;
+
+/// Maps a latitude/longitude to an IANA timezone using the boundary data
+/// embedded in the binary.
+pub struct CoordinateResolver {
+    finder: DefaultFinder,
+}
+
+impl CoordinateResolver {
+    pub fn new() -> Self {
+        Self {
+            finder: DefaultFinder::new(),
+        }
+    }
+
+    pub fn resolve(
+        &self,
+        coordinates: Coordinates,
+    ) -> Result<String, anyhow::Error> {
+        let Coordinates {
+            latitude,
+            longitude,
+        } = coordinates;
+        if !(-90.0..=90.0).contains(&latitude)
+            || !(-180.0..=180.0).contains(&longitude)
+        {
+            return Err(anyhow!("Invalid coordinates {}", coordinates));
+        }
+
+        // Points in international waters, or in areas with no agreed upon
+        // boundary, have no timezone.
+        match self.finder.get_tz_name(longitude, latitude) {
+            "" => Err(anyhow!("No timezone at {}", coordinates)),
+            timezone => Ok(timezone.to_string()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,69 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            session.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Whether the logind session the daemon runs in is the active one.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use anyhow::Context;
+use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
+use dbus::nonblock::{Proxy, SyncConnection};
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SERVICE: &str = "org.freedesktop.login1";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Whether the session this process belongs to is the active session of
+/// its seat, i.e. the one in the foreground.
+pub async fn is_active(
+    connection: Arc<SyncConnection>,
+) -> Result<bool, anyhow::Error> {
+    let manager = Proxy::new(
+        SERVICE,
+        "/org/freedesktop/login1",
+        TIMEOUT,
+        connection.clone(),
+    );
+    let (path,): (dbus::Path<'static>,) = manager
+        .method_call(
+            "org.freedesktop.login1.Manager",
+            "GetSessionByPID",
+            (process::id(),),
+        )
+        .await
+        .context("Couldn't find the session this process belongs to")?;
+    let session = Proxy::new(SERVICE, path, TIMEOUT, connection);
+    Ok(session
+        .get("org.freedesktop.login1.Session", "Active")
+        .await?)
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,648 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            setter.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Sets the system timezone through systemd-timedated.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::fault::{self, Fault};
+use crate::jitter::Jitter;
+use crate::localtime::LocaltimeSetter;
+use crate::signals::PropertiesChanged;
+use crate::statistics::Statistics;
+use crate::zone;
+use anyhow::{anyhow, Context};
+use dbus::arg::{prop_cast, PropMap, Variant};
+use dbus::message::MatchRule;
+use dbus::nonblock::stdintf::org_freedesktop_dbus::Properties;
+use dbus::nonblock::{MsgMatch, Proxy, SyncConnection};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const SERVICE: &str = "org.freedesktop.timedate1";
+const PATH: &str = "/org/freedesktop/timedate1";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long timedated's properties are served from the cache. Our own writes
+/// and its PropertiesChanged signals clear it sooner.
+const CACHE_FOR: Duration = Duration::from_secs(5);
+
+/// Interactive authorization waits on a human to answer a polkit prompt.
+const INTERACTIVE_TIMEOUT: Duration = Duration::from_secs(300);
+
+const ACTION: &str = "org.freedesktop.timedate1.set-timezone";
+const INTERACTIVE_AUTHORIZATION_REQUIRED: &str =
+    "org.freedesktop.DBus.Error.InteractiveAuthorizationRequired";
+
+/// Delays between successive attempts after a transient failure, before
+/// jitter.
+const BACKOFF: [Duration; 3] = [
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+];
+
+/// Errors that are expected to go away on their own, e.g. while timedated is
+/// being restarted.
+const TRANSIENT_ERRORS: [&str; 5] = [
+    "org.freedesktop.DBus.Error.NoReply",
+    "org.freedesktop.DBus.Error.Timeout",
+    "org.freedesktop.DBus.Error.TimedOut",
+    "org.freedesktop.DBus.Error.ServiceUnknown",
+    "org.freedesktop.DBus.Error.Disconnected",
+];
+
+fn is_transient(error: &dbus::Error) -> bool {
+    error
+        .name()
+        .is_some_and(|name| TRANSIENT_ERRORS.contains(&name))
+}
+
+/// polkit refused to let us set the timezone.
+#[derive(Debug)]
+pub struct Denied(String);
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Denied {}
+
+/// Turn an error from timedated into something a human can act on.
+fn explain(error: dbus::Error, timezone: &str) -> anyhow::Error {
+    match error.name() {
+        Some("org.freedesktop.DBus.Error.AccessDenied") => {
+            anyhow::Error::new(Denied(format!(
+                "Not authorized to set the timezone. Check that polkit \
+                 permits {} for this user",
+                ACTION
+            )))
+        }
+        Some(INTERACTIVE_AUTHORIZATION_REQUIRED) => {
+            anyhow::Error::new(Denied(format!(
+                "Setting the timezone requires authorization for the polkit \
+                 action {}. Either add a polkit rule granting it, or set \
+                 interactive_auth = true to be prompted",
+                ACTION
+            )))
+        }
+        Some("org.freedesktop.DBus.Error.InvalidArgs") => {
+            anyhow!("timedated rejected the timezone {}", timezone)
+        }
+        _ => anyhow!("Couldn't set the timezone: {}", error),
+    }
+}
+
+/// What polkit would say to a request to set the timezone.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Authorization {
+    Authorized,
+
+    /// Allowed, once someone answers a prompt.
+    Challenge,
+    Denied,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// The timezone was changed.
+    Set,
+
+    /// The system was already set to the timezone.
+    Unchanged,
+}
+
+/// timedated's properties as of the last GetAll, so that the reads around a
+/// trigger cost one round trip between them.
+#[derive(Default)]
+struct Cache {
+    properties: Option<(PropMap, Instant)>,
+
+    /// Incremented each time the cache is cleared, so that a GetAll that was
+    /// in flight meanwhile isn't stored.
+    generation: u64,
+}
+
+impl Cache {
+    fn clear(&mut self) {
+        self.properties = None;
+        self.generation += 1;
+    }
+}
+
+pub struct TimezoneSetter {
+    connection: Arc<SyncConnection>,
+    interactive_auth: bool,
+    jitter: Jitter,
+    cache: Arc<Mutex<Cache>>,
+    signal: Option<MsgMatch>,
+}
+
+impl TimezoneSetter {
+    pub fn new(
+        connection: Arc<SyncConnection>,
+        interactive_auth: bool,
+        jitter: Jitter,
+    ) -> Self {
+        Self {
+            connection,
+            interactive_auth,
+            jitter,
+            cache: Arc::default(),
+            signal: None,
+        }
+    }
+
+    /// Clear the cache each time timedated reports its properties changed.
+    /// Until this is called, the cache is only cleared by our own writes and
+    /// by expiring.
+    pub async fn watch(&mut self) -> Result<(), anyhow::Error> {
+        let rule = MatchRule::new_signal(
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+        )
+        .with_sender(SERVICE)
+        .with_path(PATH);
+        let cache = self.cache.clone();
+        let signal =
+            self.connection
+                .add_match(rule)
+                .await?
+                .msg_cb(move |message| {
+                    let properties = PropertiesChanged::read(&message);
+                    if properties.is_some_and(|properties| {
+                        SERVICE == properties.interface
+                    }) {
+                        cache.lock().unwrap().clear();
+                    }
+                    true
+                });
+        self.signal = Some(signal);
+        Ok(())
+    }
+
+    /// Read one of timedated's properties, from the cache if it's fresh.
+    async fn property<T: Clone + 'static>(
+        &self,
+        name: &str,
+    ) -> Result<T, anyhow::Error> {
+        let generation = {
+            let cache = self.cache.lock().unwrap();
+            if let Some((properties, fetched)) = &cache.properties {
+                if fetched.elapsed() < CACHE_FOR {
+                    if let Some(value) = prop_cast::<T>(properties, name) {
+                        return Ok(value.clone());
+                    }
+                }
+            }
+            cache.generation
+        };
+
+        let properties =
+            Proxy::new(SERVICE, PATH, TIMEOUT, self.connection.clone())
+                .get_all(SERVICE)
+                .await?;
+        let value = prop_cast::<T>(&properties, name)
+            .cloned()
+            .ok_or_else(|| anyhow!("timedated has no property {}", name))?;
+        let mut cache = self.cache.lock().unwrap();
+        if generation == cache.generation {
+            cache.properties = Some((properties, Instant::now()));
+        }
+        Ok(value)
+    }
+
+    /// Call SetTimezone method of interface org.freedesktop.timedate1 of
+    /// object /org/freedesktop/timedate1 on service org.freedesktop.timedate1
+    async fn call(
+        &self,
+        timezone: &str,
+        interactive: bool,
+    ) -> Result<(), dbus::Error> {
+        if fault::inject(Fault::SetTimezoneDenied) {
+            return Err(dbus::Error::new_custom(
+                "org.freedesktop.DBus.Error.AccessDenied",
+                "Injected fault",
+            ));
+        }
+        if fault::inject(Fault::SetTimezoneNoReply) {
+            return Err(dbus::Error::new_custom(
+                "org.freedesktop.DBus.Error.NoReply",
+                "Injected fault",
+            ));
+        }
+        let timeout = match interactive {
+            true => INTERACTIVE_TIMEOUT,
+            false => TIMEOUT,
+        };
+        let result =
+            Proxy::new(SERVICE, PATH, timeout, self.connection.clone())
+                .method_call(SERVICE, "SetTimezone", (timezone, interactive))
+                .await;
+        // Even a call that failed may have changed something.
+        self.cache.lock().unwrap().clear();
+        result
+    }
+
+    /// Ask the bus to start timedated, in case it needs activating again
+    /// after being restarted.
+    async fn activate(&self) {
+        let proxy = Proxy::new(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            TIMEOUT,
+            self.connection.clone(),
+        );
+        let result: Result<(u32,), _> = proxy
+            .method_call(
+                "org.freedesktop.DBus",
+                "StartServiceByName",
+                (SERVICE, 0u32),
+            )
+            .await;
+        if let Err(error) = result {
+            debug!("Couldn't activate {}: {}", SERVICE, error);
+        }
+    }
+
+    /// Ask polkit whether we may set the timezone, without setting it.
+    pub async fn authorization(&self) -> Result<Authorization, anyhow::Error> {
+        let mut details = PropMap::new();
+        details.insert(
+            "name".to_string(),
+            Variant(Box::new(self.connection.unique_name().to_string())),
+        );
+        let subject = ("system-bus-name", details);
+        let proxy = Proxy::new(
+            "org.freedesktop.PolicyKit1",
+            "/org/freedesktop/PolicyKit1/Authority",
+            TIMEOUT,
+            self.connection.clone(),
+        );
+        let ((authorized, challenge, _),): ((
+            bool,
+            bool,
+            HashMap<String, String>,
+        ),) = proxy
+            .method_call(
+                "org.freedesktop.PolicyKit1.Authority",
+                "CheckAuthorization",
+                (subject, ACTION, HashMap::<&str, &str>::new(), 0u32, ""),
+            )
+            .await
+            .context("Couldn't ask polkit")?;
+        Ok(match (authorized, challenge) {
+            (true, _) => Authorization::Authorized,
+            (false, true) => Authorization::Challenge,
+            (false, false) => Authorization::Denied,
+        })
+    }
+
+    /// Read the timezone the system is currently set to.
+    pub async fn current_timezone(&self) -> Result<String, anyhow::Error> {
+        self.property("Timezone")
+            .await
+            .context("Couldn't read the current timezone")
+    }
+
+    /// Set the timezone to `timezone` (or the zone it's an alias for), unless
+    /// it already is.
+    pub async fn apply(
+        &self,
+        timezone: &str,
+        statistics: &mut Statistics,
+    ) -> Result<Outcome, anyhow::Error> {
+        let timezone = zone::canonicalize(timezone);
+        zone::validate(&timezone)?;
+        match self.current_timezone().await {
+            Ok(current) if zone::is_same(&current, &timezone) => {
+                return Ok(Outcome::Unchanged)
+            }
+            Ok(_) => {}
+            Err(error) => debug!("{:#}", error),
+        }
+        self.set_timezone(&timezone, statistics).await?;
+        Ok(Outcome::Set)
+    }
+
+    async fn set_timezone(
+        &self,
+        timezone: &str,
+        statistics: &mut Statistics,
+    ) -> Result<(), anyhow::Error> {
+        let mut backoff = BACKOFF.iter();
+        loop {
+            let error = match self.call(timezone, false).await {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+
+            if self.interactive_auth
+                && Some(INTERACTIVE_AUTHORIZATION_REQUIRED) == error.name()
+            {
+                info!("Waiting for interactive authorization");
+                return self
+                    .call(timezone, true)
+                    .await
+                    .map_err(|error| explain(error, timezone));
+            }
+
+            let delay = match backoff.next() {
+                Some(delay) if is_transient(&error) => delay,
+                _ => return Err(explain(error, timezone)),
+            };
+            warn!("SetTimezone failed, retrying: {}", error);
+            statistics.set_timezone_retries += 1;
+            tokio::time::sleep(self.jitter.apply(*delay)).await;
+            if Some("org.freedesktop.DBus.Error.ServiceUnknown")
+                == error.name()
+            {
+                self.activate().await;
+            }
+        }
+    }
+}
+
+/// Whatever sets the timezone for the daemon: timedated, or where there's
+/// none (see without_timedated), /etc/localtime directly.
+pub enum Setter {
+    Timedated(TimezoneSetter),
+    Localtime(LocaltimeSetter),
+}
+
+impl Setter {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Timedated(_) => "timedated",
+            Self::Localtime(_) => "localtime",
+        }
+    }
+
+    pub async fn watch(&mut self) -> Result<(), anyhow::Error> {
+        match *self {
+            Self::Timedated(ref mut setter) => setter.watch().await,
+            Self::Localtime(_) => Ok(()),
+        }
+    }
+
+    pub async fn current_timezone(&self) -> Result<String, anyhow::Error> {
+        match *self {
+            Self::Timedated(ref setter) => setter.current_timezone().await,
+            Self::Localtime(ref setter) => setter.current_timezone(),
+        }
+    }
+
+    pub async fn apply(
+        &self,
+        timezone: &str,
+        statistics: &mut Statistics,
+    ) -> Result<Outcome, anyhow::Error> {
+        match *self {
+            Self::Timedated(ref setter) => {
+                setter.apply(timezone, statistics).await
+            }
+            Self::Localtime(ref setter) => setter.apply(timezone),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::Bus;
+    use dbus::channel::{MatchingReceiver, Sender};
+    use dbus::Message;
+    use dbus_crossroads::{Crossroads, IfaceBuilder, MethodErr};
+
+    /// The calls made to SetTimezone, as (timezone, interactive).
+    type Calls = Arc<Mutex<Vec<(String, bool)>>>;
+
+    /// What the fake timedated has been asked for.
+    struct Made {
+        calls: Calls,
+
+        /// How many times its properties have been read with GetAll.
+        reads: Arc<Mutex<usize>>,
+    }
+
+    struct Timedated {
+        timezone: String,
+
+        /// How many calls are answered InteractiveAuthorizationRequired,
+        /// if they don't allow it, before they succeed.
+        refusals: usize,
+        calls: Calls,
+    }
+
+    /// Serve a fake timedated on `connection`.
+    async fn timedated(
+        connection: &Arc<SyncConnection>,
+        refusals: usize,
+    ) -> Made {
+        let calls = Calls::default();
+        let reads: Arc<Mutex<usize>> = Arc::default();
+        let mut crossroads = Crossroads::new();
+        let interface =
+            crossroads.register(SERVICE, |b: &mut IfaceBuilder<Timedated>| {
+                b.property("Timezone")
+                    .get(|_, timedated| Ok(timedated.timezone.clone()));
+                b.method(
+                    "SetTimezone",
+                    ("timezone", "interactive"),
+                    (),
+                    |_, timedated, (timezone, interactive): (String, bool)| {
+                        timedated
+                            .calls
+                            .lock()
+                            .unwrap()
+                            .push((timezone.clone(), interactive));
+                        if !interactive && 0 < timedated.refusals {
+                            timedated.refusals -= 1;
+                            return Err(MethodErr::from((
+                                INTERACTIVE_AUTHORIZATION_REQUIRED,
+                                "Interactive authentication required.",
+                            )));
+                        }
+                        timedated.timezone = timezone;
+                        Ok(())
+                    },
+                );
+            });
+        let fake = Timedated {
+            timezone: "UTC".to_string(),
+            refusals,
+            calls: calls.clone(),
+        };
+        crossroads.insert(PATH, &[interface], fake);
+        let counted = reads.clone();
+        connection.start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |message, connection| {
+                if Some("GetAll") == message.member().as_deref() {
+                    *counted.lock().unwrap() += 1;
+                }
+                let _ = crossroads.handle_message(message, connection);
+                true
+            }),
+        );
+        connection
+            .request_name(SERVICE, false, true, false)
+            .await
+            .unwrap();
+        Made { calls, reads }
+    }
+
+    fn calls(made: &Made) -> Vec<(String, bool)> {
+        made.calls.lock().unwrap().clone()
+    }
+
+    fn reads(made: &Made) -> usize {
+        *made.reads.lock().unwrap()
+    }
+
+    fn call(interactive: bool) -> (String, bool) {
+        ("Europe/Berlin".to_string(), interactive)
+    }
+
+    #[tokio::test]
+    async fn interactive_authorization_is_retried_when_allowed() {
+        let bus = Bus::start();
+        let made = timedated(&bus.connect(), usize::MAX).await;
+        let setter =
+            TimezoneSetter::new(bus.connect(), true, Jitter::new(0.0));
+        let outcome = setter
+            .apply("Europe/Berlin", &mut Statistics::default())
+            .await
+            .unwrap();
+        assert_eq!(Outcome::Set, outcome);
+        assert_eq!(vec![call(false), call(true)], calls(&made));
+        assert_eq!("Europe/Berlin", setter.current_timezone().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn interactive_authorization_is_explained_when_not_allowed() {
+        let bus = Bus::start();
+        let made = timedated(&bus.connect(), usize::MAX).await;
+        let setter =
+            TimezoneSetter::new(bus.connect(), false, Jitter::new(0.0));
+        let error = setter
+            .apply("Europe/Berlin", &mut Statistics::default())
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<Denied>().is_some());
+        let message = format!("{:#}", error);
+        assert!(message.contains(ACTION), "{}", message);
+        assert!(message.contains("interactive_auth"), "{}", message);
+        assert_eq!(vec![call(false)], calls(&made));
+    }
+
+    #[tokio::test]
+    async fn only_calls_that_need_it_are_made_interactive() {
+        let bus = Bus::start();
+        let made = timedated(&bus.connect(), 0).await;
+        let setter =
+            TimezoneSetter::new(bus.connect(), true, Jitter::new(0.0));
+        setter
+            .apply("Europe/Berlin", &mut Statistics::default())
+            .await
+            .unwrap();
+        assert_eq!(vec![call(false)], calls(&made));
+    }
+
+    #[tokio::test]
+    async fn reads_within_cache_for_share_one_get_all() {
+        let bus = Bus::start();
+        let made = timedated(&bus.connect(), 0).await;
+        let setter =
+            TimezoneSetter::new(bus.connect(), false, Jitter::new(0.0));
+        for _ in 0..3 {
+            assert_eq!("UTC", setter.current_timezone().await.unwrap());
+        }
+        assert_eq!(1, reads(&made));
+    }
+
+    #[tokio::test]
+    async fn our_own_writes_clear_the_cache() {
+        let bus = Bus::start();
+        let made = timedated(&bus.connect(), 0).await;
+        let setter =
+            TimezoneSetter::new(bus.connect(), false, Jitter::new(0.0));
+        assert_eq!("UTC", setter.current_timezone().await.unwrap());
+        // Whether it's unchanged is checked from the cache.
+        setter
+            .apply("Europe/Berlin", &mut Statistics::default())
+            .await
+            .unwrap();
+        assert_eq!(1, reads(&made));
+        for _ in 0..3 {
+            let current = setter.current_timezone().await.unwrap();
+            assert_eq!("Europe/Berlin", current);
+        }
+        assert_eq!(2, reads(&made));
+    }
+
+    #[tokio::test]
+    async fn properties_changed_clears_the_cache() {
+        let bus = Bus::start();
+        let service = bus.connect();
+        let made = timedated(&service, 0).await;
+        let mut setter =
+            TimezoneSetter::new(bus.connect(), false, Jitter::new(0.0));
+        setter.watch().await.unwrap();
+        setter.current_timezone().await.unwrap();
+        setter.current_timezone().await.unwrap();
+        assert_eq!(1, reads(&made));
+
+        let signal = Message::new_signal(
+            PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+        )
+        .unwrap()
+        .append3(SERVICE, PropMap::new(), vec!["Timezone"]);
+        service.send(signal).unwrap();
+        let cleared = tokio::time::timeout(Duration::from_secs(5), async {
+            while 0 == setter.cache.lock().unwrap().generation {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+        assert!(cleared.await.is_ok());
+        setter.current_timezone().await.unwrap();
+        setter.current_timezone().await.unwrap();
+        assert_eq!(2, reads(&made));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,826 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            signals.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Reception and reading of D-Bus signals, such that a body of an
+//                  unexpected shape is logged and skipped.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use dbus::arg::{PropMap, TypeMismatchError};
+use dbus::message::MatchRule;
+use dbus::nonblock::{MsgMatch, SyncConnection};
+use dbus::Message;
+use log::debug;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// How many signals may wait on one match before they're merged or dropped.
+pub const CAPACITY: usize = 256;
+
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+/// Signals merged into later ones, or dropped, to make room, across every
+/// match.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// How many signals have been merged into later ones or dropped, since the
+/// daemon started.
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Whether `message` is a PropertiesChanged signal.
+fn is_properties_changed(message: &Message) -> bool {
+    message.interface().as_deref() == Some(PROPERTIES)
+        && message.member().as_deref() == Some("PropertiesChanged")
+}
+
+/// What decides whether two PropertiesChanged signals can be merged, read
+/// once as each is queued, rather than every time room is made.
+struct Topic {
+    path: String,
+    interface: String,
+
+    /// What the signal says about State: None if nothing, Some(None) if it's
+    /// invalidated, or else its value.
+    state: Option<Option<String>>,
+}
+
+impl Topic {
+    /// The topic of `message`, if it's a PropertiesChanged signal. Read
+    /// quietly, since the parsers log bodies they can't read.
+    fn of(message: &Message) -> Option<Self> {
+        if !is_properties_changed(message) {
+            return None;
+        }
+        let mut body = message.iter_init();
+        let interface: String = body.read().ok()?;
+        let changed: PropMap = body.read().ok()?;
+        let state = match changed.get("State") {
+            Some(value) => Some(Some(format!("{:?}", value.0))),
+            None => {
+                let invalidated: Vec<String> = body.read().unwrap_or_default();
+                invalidated
+                    .iter()
+                    .any(|name| "State" == name)
+                    .then_some(None)
+            }
+        };
+        Some(Self {
+            path: message.path()?.to_string(),
+            interface,
+            state,
+        })
+    }
+
+    /// Whether a later signal on `later` can be merged into this one: for
+    /// the same interface on the same object, and not disagreeing about
+    /// State, since a quick disconnect and reconnect would otherwise read as
+    /// no change at all.
+    fn mergeable(&self, later: &Topic) -> bool {
+        self.path == later.path
+            && self.interface == later.interface
+            && match (&self.state, &later.state) {
+                (Some(earlier), Some(later)) => earlier == later,
+                _ => true,
+            }
+    }
+}
+
+/// `message` as bytes, the way it came or will go over the bus.
+fn marshal(message: &Message) -> Option<Vec<u8>> {
+    let mut copy = message.duplicate().ok()?;
+    copy.set_serial(message.get_serial().unwrap_or(1));
+    let mut bytes = Vec::new();
+    copy.marshal(|data| {
+        bytes.extend_from_slice(data);
+        Ok::<(), ()>(())
+    })
+    .ok()?;
+    Some(bytes)
+}
+
+/// Where the body starts in a marshalled message: after the fixed part of
+/// the header and the array of header fields, padded to eight bytes.
+fn body_offset(bytes: &[u8]) -> Option<usize> {
+    let length: [u8; 4] = bytes.get(12..16)?.try_into().ok()?;
+    let length = match bytes[0] {
+        b'l' => u32::from_le_bytes(length),
+        b'B' => u32::from_be_bytes(length),
+        _ => return None,
+    };
+    Some((16 + length as usize).next_multiple_of(8))
+}
+
+/// The body of `body` under the header of `header`, which keeps what
+/// Message::signal() can't set, such as the sender. None if the two don't
+/// fit together, which libdbus checks on the way back in.
+fn with_header(header: &Message, body: &Message) -> Option<Message> {
+    let header = marshal(header)?;
+    let body = marshal(body)?;
+    if header[0] != body[0] {
+        return None;
+    }
+    let mut bytes = header[..body_offset(&header)?].to_vec();
+    let body = &body[body_offset(&body)?..];
+    let length = match bytes[0] {
+        b'l' => (body.len() as u32).to_le_bytes(),
+        _ => (body.len() as u32).to_be_bytes(),
+    };
+    bytes[4..8].copy_from_slice(&length);
+    bytes.extend_from_slice(body);
+    Message::demarshal(&bytes).ok()
+}
+
+/// A PropertiesChanged signal saying what `earlier` and `later` say between
+/// them, where `later` wins, from the sender of `later`. None if either body
+/// can't be read.
+fn merge(earlier: &Message, later: &Message) -> Option<Message> {
+    let earlier_body = PropertiesChanged::read(earlier)?;
+    let later_body = PropertiesChanged::read(later)?;
+    let mut changed = earlier_body.changed;
+    for name in &later_body.invalidated {
+        changed.remove(name);
+    }
+    let mut invalidated: Vec<String> = earlier_body
+        .invalidated
+        .into_iter()
+        .filter(|name| !later_body.changed.contains_key(name))
+        .collect();
+    for name in later_body.invalidated {
+        if !invalidated.contains(&name) {
+            invalidated.push(name);
+        }
+    }
+    changed.extend(later_body.changed);
+    let merged = Message::signal(
+        &later.path()?,
+        &PROPERTIES.into(),
+        &"PropertiesChanged".into(),
+    );
+    let merged = merged.append3(later_body.interface, changed, invalidated);
+    with_header(later, &merged)
+}
+
+/// A waiting signal, and its topic if it's a PropertiesChanged.
+type Queued = (Message, Option<Topic>);
+
+struct Inner {
+    messages: VecDeque<Queued>,
+    capacity: usize,
+
+    /// Whether no two waiting signals are known to be mergeable, so that,
+    /// while a flood keeps the queue full, they aren't compared again every
+    /// time room is made.
+    unpaired: bool,
+    senders: usize,
+    receiving: bool,
+}
+
+impl Inner {
+    /// Make room for `message`, possibly by merging a waiting one into it.
+    /// What's merged is, in order: the waiting PropertiesChanged for the
+    /// same properties, so that nothing is lost and the latest value of each
+    /// wins; failing that, the oldest two waiting signals that can be
+    /// merged. Failing both, the oldest signal is dropped.
+    fn make_room(&mut self, message: Queued) -> Queued {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+        let waiting = message.1.as_ref().and_then(|topic| {
+            self.messages.iter().rposition(|(_, queued)| {
+                queued
+                    .as_ref()
+                    .is_some_and(|queued| queued.mergeable(topic))
+            })
+        });
+        if let Some(index) = waiting {
+            let earlier = self.messages.remove(index);
+            self.unpaired = false;
+            return match earlier
+                .and_then(|(earlier, _)| merge(&earlier, &message.0))
+            {
+                Some(merged) => {
+                    let topic = Topic::of(&merged);
+                    (merged, topic)
+                }
+                None => message,
+            };
+        }
+
+        let pair = match self.unpaired {
+            true => None,
+            false => (0..self.messages.len()).find_map(|index| {
+                let topic = self.messages[index].1.as_ref()?;
+                let later =
+                    (index + 1..self.messages.len()).find(|&later| {
+                        self.messages[later]
+                            .1
+                            .as_ref()
+                            .is_some_and(|later| topic.mergeable(later))
+                    })?;
+                Some((index, later))
+            }),
+        };
+        // Whatever's queued next can't be merged with anything waiting, or
+        // it would have been, above.
+        self.unpaired = pair.is_none();
+        if let Some((index, later)) = pair {
+            if let Some(merged) =
+                merge(&self.messages[index].0, &self.messages[later].0)
+            {
+                let topic = Topic::of(&merged);
+                self.messages[later] = (merged, topic);
+            }
+            self.messages.remove(index);
+        } else {
+            self.messages.pop_front();
+        }
+        message
+    }
+}
+
+struct Shared {
+    inner: Mutex<Inner>,
+    notify: Notify,
+}
+
+/// Queues the signals a match receives. Never waits: see make_room() for
+/// what happens when the queue is full.
+pub struct MessageSender(Arc<Shared>);
+
+pub struct MessageReceiver(Arc<Shared>);
+
+pub fn channel(capacity: usize) -> (MessageSender, MessageReceiver) {
+    let shared = Arc::new(Shared {
+        inner: Mutex::new(Inner {
+            messages: VecDeque::new(),
+            capacity,
+            unpaired: false,
+            senders: 1,
+            receiving: true,
+        }),
+        notify: Notify::new(),
+    });
+    (MessageSender(shared.clone()), MessageReceiver(shared))
+}
+
+impl MessageSender {
+    /// Queue `message`. False once the receiver has gone, which also tells
+    /// the connection to stop calling the match's callback.
+    pub fn send(&self, message: Message) -> bool {
+        let topic = Topic::of(&message);
+        let mut inner = self.0.inner.lock().unwrap();
+        if !inner.receiving {
+            return false;
+        }
+        let message = match inner.messages.len() >= inner.capacity {
+            true => inner.make_room((message, topic)),
+            false => {
+                inner.unpaired = false;
+                (message, topic)
+            }
+        };
+        inner.messages.push_back(message);
+        drop(inner);
+        self.0.notify.notify_one();
+        true
+    }
+}
+
+#[cfg(any(feature = "backend-wpa-supplicant", test))]
+impl MessageSender {
+    /// A handle that can make more senders while this one, or another, is
+    /// still around, without keeping the channel open itself.
+    pub fn downgrade(&self) -> WeakMessageSender {
+        WeakMessageSender(self.0.clone())
+    }
+}
+
+/// Makes senders for a channel, without counting as one.
+#[cfg(any(feature = "backend-wpa-supplicant", test))]
+pub struct WeakMessageSender(Arc<Shared>);
+
+#[cfg(any(feature = "backend-wpa-supplicant", test))]
+impl WeakMessageSender {
+    /// Another sender, unless every sender has gone already, in which case
+    /// the channel stays closed.
+    pub fn upgrade(&self) -> Option<MessageSender> {
+        let mut inner = self.0.inner.lock().unwrap();
+        if 0 == inner.senders {
+            return None;
+        }
+        inner.senders += 1;
+        Some(MessageSender(self.0.clone()))
+    }
+}
+
+impl Clone for MessageSender {
+    fn clone(&self) -> Self {
+        self.0.inner.lock().unwrap().senders += 1;
+        Self(self.0.clone())
+    }
+}
+
+impl Drop for MessageSender {
+    fn drop(&mut self) {
+        self.0.inner.lock().unwrap().senders -= 1;
+        self.0.notify.notify_one();
+    }
+}
+
+impl MessageReceiver {
+    /// Wait for the next signal. Returns None once every sender has gone and
+    /// there are no signals left.
+    pub async fn recv(&mut self) -> Option<Message> {
+        loop {
+            {
+                let mut inner = self.0.inner.lock().unwrap();
+                if let Some((message, _)) = inner.messages.pop_front() {
+                    return Some(message);
+                }
+                if 0 == inner.senders {
+                    return None;
+                }
+            }
+            self.0.notify.notified().await;
+        }
+    }
+}
+
+impl Drop for MessageReceiver {
+    fn drop(&mut self) {
+        self.0.inner.lock().unwrap().receiving = false;
+    }
+}
+
+/// Receive every message matching `rule`. Bodies aren't read on the way in
+/// (short of merging signals when too many are waiting), since the dbus
+/// crate drops those it can't read as the expected type without saying so;
+/// that's left to the parsers, which log what they skip.
+pub async fn subscribe(
+    connection: &SyncConnection,
+    rule: MatchRule<'static>,
+) -> Result<(MsgMatch, MessageReceiver), anyhow::Error> {
+    let (messages, incoming) = channel(CAPACITY);
+    let signal = connection
+        .add_match(rule)
+        .await?
+        .msg_cb(move |message| messages.send(message));
+    Ok((signal, incoming))
+}
+
+/// Log that `message` is being skipped because its body couldn't be read.
+pub fn skip(message: &Message, error: impl fmt::Display) {
+    debug!(
+        "Ignoring malformed {} from {} on {}: {}",
+        message.member().as_deref().unwrap_or("signal"),
+        message.sender().as_deref().unwrap_or("an unknown sender"),
+        message.path().as_deref().unwrap_or("no path"),
+        error
+    );
+}
+
+/// The body of an org.freedesktop.DBus.Properties.PropertiesChanged signal.
+pub struct PropertiesChanged {
+    pub interface: String,
+    pub changed: PropMap,
+
+    /// Empty if the signal has none, or they couldn't be read as a list of
+    /// names, since not every service sends them the same way.
+    pub invalidated: Vec<String>,
+}
+
+impl PropertiesChanged {
+    /// Read the body of `message`, or log why it can't be read and return
+    /// None.
+    pub fn read(message: &Message) -> Option<Self> {
+        let mut body = message.iter_init();
+        let result: Result<(String, PropMap), TypeMismatchError> =
+            (|| Ok((body.read()?, body.read()?)))();
+        let (interface, changed) = match result {
+            Ok(head) => head,
+            Err(error) => {
+                skip(message, error);
+                return None;
+            }
+        };
+        Some(Self {
+            interface,
+            changed,
+            invalidated: body.read().unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::Bus;
+    use dbus::arg::{self, RefArg, Variant};
+    use dbus::channel::Sender;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    const STATION: &str = "net.connman.iwd.Station";
+
+    fn properties_changed(
+        path: &str,
+        changed: Vec<(&str, Box<dyn RefArg>)>,
+        invalidated: Vec<&str>,
+    ) -> Message {
+        let changed: PropMap = changed
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), Variant(value)))
+            .collect();
+        let invalidated: Vec<String> =
+            invalidated.into_iter().map(str::to_string).collect();
+        Message::signal(
+            &path.into(),
+            &PROPERTIES.into(),
+            &"PropertiesChanged".into(),
+        )
+        .append3(STATION, changed, invalidated)
+    }
+
+    fn state(path: &str, state: &str) -> Message {
+        properties_changed(
+            path,
+            vec![("State", Box::new(state.to_string()))],
+            vec![],
+        )
+    }
+
+    fn scanning(path: &str, scanning: bool) -> Message {
+        properties_changed(
+            path,
+            vec![("Scanning", Box::new(scanning))],
+            vec![],
+        )
+    }
+
+    fn waiting(receiver: &MessageReceiver) -> usize {
+        receiver.0.inner.lock().unwrap().messages.len()
+    }
+
+    async fn drain(receiver: &mut MessageReceiver) -> Vec<Message> {
+        let mut messages = Vec::new();
+        while 0 != waiting(receiver) {
+            messages.push(receiver.recv().await.unwrap());
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn a_flood_stays_bounded_and_the_latest_state_wins() {
+        let stations = ["/net/connman/iwd/0/3", "/net/connman/iwd/0/4"];
+        let (sender, mut receiver) = channel(CAPACITY);
+        let before = dropped();
+        let mut latest: HashMap<&str, (String, bool)> = HashMap::new();
+        for index in 0..10_000 {
+            let station = stations[index % stations.len()];
+            let value = format!("state-{}", index);
+            let scanning = 0 == index % 3;
+            assert!(sender.send(properties_changed(
+                station,
+                vec![
+                    ("State", Box::new(value.clone())),
+                    ("Scanning", Box::new(scanning)),
+                ],
+                vec![],
+            )));
+            latest.insert(station, (value, scanning));
+            assert!(waiting(&receiver) <= CAPACITY);
+        }
+        assert!(dropped() - before >= 10_000 - CAPACITY as u64);
+
+        // Replaying what's left in order ends up where the flood did.
+        let mut replayed: HashMap<String, PropMap> = HashMap::new();
+        for message in drain(&mut receiver).await {
+            let body = PropertiesChanged::read(&message).unwrap();
+            let path = message.path().unwrap().to_string();
+            replayed.entry(path).or_default().extend(body.changed);
+        }
+        for (station, (value, scanning)) in latest {
+            let properties = &replayed[station];
+            assert_eq!(Some(value.as_str()), properties["State"].0.as_str());
+            assert_eq!(
+                Some(&scanning),
+                arg::cast::<bool>(&properties["Scanning"].0)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn merging_keeps_what_only_the_earlier_signal_said() {
+        let station = "/net/connman/iwd/0/3";
+        let (sender, mut receiver) = channel(1);
+        sender.send(properties_changed(
+            station,
+            vec![
+                ("State", Box::new("connected".to_string())),
+                ("Scanning", Box::new(true)),
+                (
+                    "ConnectedNetwork",
+                    Box::new("/net/connman/iwd/0/3/a".to_string()),
+                ),
+            ],
+            vec![],
+        ));
+        sender.send(properties_changed(
+            station,
+            vec![("Scanning", Box::new(false))],
+            vec!["ConnectedNetwork"],
+        ));
+        let messages = drain(&mut receiver).await;
+        assert_eq!(1, messages.len());
+        let body = PropertiesChanged::read(&messages[0]).unwrap();
+        assert_eq!(STATION, body.interface);
+        assert_eq!(Some("connected"), body.changed["State"].0.as_str());
+        assert_eq!(
+            Some(&false),
+            arg::cast::<bool>(&body.changed["Scanning"].0)
+        );
+        assert!(!body.changed.contains_key("ConnectedNetwork"));
+        assert_eq!(vec!["ConnectedNetwork".to_string()], body.invalidated);
+    }
+
+    #[tokio::test]
+    async fn only_the_same_interface_on_the_same_object_is_merged() {
+        let (sender, mut receiver) = channel(2);
+        sender.send(state("/net/connman/iwd/0/3", "connected"));
+        sender.send(state("/net/connman/iwd/0/4", "connected"));
+        // Room is made by dropping the oldest, since nothing can be merged.
+        let other = Message::signal(
+            &"/".into(),
+            &"org.freedesktop.DBus.ObjectManager".into(),
+            &"InterfacesAdded".into(),
+        );
+        sender.send(other);
+        let messages = drain(&mut receiver).await;
+        assert_eq!(2, messages.len());
+        assert_eq!("/net/connman/iwd/0/4", &*messages[0].path().unwrap());
+        assert_eq!(Some("InterfacesAdded"), messages[1].member().as_deref());
+    }
+
+    /// The path of each message, with what it says: the State, or else
+    /// "scanning" or "idle".
+    async fn summary(receiver: &mut MessageReceiver) -> Vec<(String, String)> {
+        drain(receiver)
+            .await
+            .iter()
+            .map(|message| {
+                let body = PropertiesChanged::read(message).unwrap();
+                let said = match body.changed.get("State") {
+                    Some(state) => state.0.as_str().unwrap(),
+                    None => {
+                        match arg::cast::<bool>(&body.changed["Scanning"].0) {
+                            Some(true) => "scanning",
+                            _ => "idle",
+                        }
+                    }
+                };
+                (message.path().unwrap().to_string(), said.to_string())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn room_is_made_by_merging_a_waiting_pair_first() {
+        let (sender, mut receiver) = channel(3);
+        sender.send(scanning("/net/connman/iwd/0/3", true));
+        sender.send(state("/net/connman/iwd/0/4", "connected"));
+        sender.send(scanning("/net/connman/iwd/0/3", false));
+        sender.send(state("/net/connman/iwd/0/5", "connected"));
+        assert_eq!(
+            vec![
+                ("/net/connman/iwd/0/4".into(), "connected".into()),
+                ("/net/connman/iwd/0/3".into(), "idle".into()),
+                ("/net/connman/iwd/0/5".into(), "connected".into()),
+            ],
+            summary(&mut receiver).await
+        );
+    }
+
+    #[tokio::test]
+    async fn a_change_of_state_is_never_merged() {
+        let (sender, mut receiver) = channel(3);
+        sender.send(state("/net/connman/iwd/0/3", "disconnected"));
+        sender.send(state("/net/connman/iwd/0/3", "connected"));
+        sender.send(scanning("/net/connman/iwd/0/4", true));
+        sender.send(scanning("/net/connman/iwd/0/4", false));
+        assert_eq!(
+            vec![
+                ("/net/connman/iwd/0/3".into(), "disconnected".into()),
+                ("/net/connman/iwd/0/3".into(), "connected".into()),
+                ("/net/connman/iwd/0/4".into(), "idle".into()),
+            ],
+            summary(&mut receiver).await
+        );
+
+        // Nor is invalidating it.
+        let (sender, mut receiver) = channel(1);
+        sender.send(state("/net/connman/iwd/0/3", "connected"));
+        sender.send(properties_changed(
+            "/net/connman/iwd/0/3",
+            vec![],
+            vec!["State"],
+        ));
+        let messages = drain(&mut receiver).await;
+        assert_eq!(1, messages.len());
+        let body = PropertiesChanged::read(&messages[0]).unwrap();
+        assert!(body.changed.is_empty());
+        assert_eq!(vec!["State".to_string()], body.invalidated);
+    }
+
+    #[tokio::test]
+    async fn merging_keeps_the_sender() {
+        let bus = Bus::start();
+        let service = bus.connect();
+        let listener = bus.connect();
+        let rule = MatchRule::new_signal(PROPERTIES, "PropertiesChanged");
+        let (_signal, mut incoming) =
+            subscribe(&listener, rule).await.unwrap();
+        let station = "/net/connman/iwd/0/3";
+        service.send(state(station, "connected")).unwrap();
+        service.send(scanning(station, true)).unwrap();
+
+        let (sender, mut receiver) = channel(1);
+        for _ in 0..2 {
+            let message =
+                tokio::time::timeout(Duration::from_secs(5), incoming.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+            sender.send(message);
+        }
+        let messages = drain(&mut receiver).await;
+        assert_eq!(1, messages.len());
+        assert_eq!(
+            Some(service.unique_name()),
+            messages[0].sender().map(|sender| sender.into_static())
+        );
+        assert_eq!(station, &*messages[0].path().unwrap());
+        let body = PropertiesChanged::read(&messages[0]).unwrap();
+        assert_eq!(Some("connected"), body.changed["State"].0.as_str());
+        assert_eq!(
+            Some(&true),
+            arg::cast::<bool>(&body.changed["Scanning"].0)
+        );
+    }
+
+    #[tokio::test]
+    async fn ends_once_the_senders_have_gone() {
+        let (sender, mut receiver) = channel(CAPACITY);
+        let clone = sender.clone();
+        sender.send(state("/net/connman/iwd/0/3", "connected"));
+        drop(sender);
+        drop(clone);
+        assert!(receiver.recv().await.is_some());
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_weak_sender_doesnt_hold_the_stream_open() {
+        let (sender, mut receiver) = channel(CAPACITY);
+        let weak = sender.downgrade();
+        let forwarded = weak.upgrade().unwrap();
+        drop(sender);
+        forwarded.send(state("/net/connman/iwd/0/3", "connected"));
+        drop(forwarded);
+        assert!(receiver.recv().await.is_some());
+        assert!(receiver.recv().await.is_none());
+        assert!(weak.upgrade().is_none());
+    }
+
+    /// What's been logged, by every test, from the first call on.
+    fn logged() -> Vec<String> {
+        static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        struct Capture;
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                LOGGED.lock().unwrap().push(record.args().to_string());
+            }
+            fn flush(&self) {}
+        }
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&Capture).unwrap();
+            log::set_max_level(log::LevelFilter::Debug);
+        });
+        LOGGED.lock().unwrap().clone()
+    }
+
+    fn signal(path: &str) -> Message {
+        Message::signal(
+            &path.into(),
+            &PROPERTIES.into(),
+            &"PropertiesChanged".into(),
+        )
+    }
+
+    #[test]
+    fn mis_shaped_bodies_are_logged_and_skipped() {
+        logged();
+        let messages = [
+            signal("/test/empty"),
+            signal("/test/number").append1(7u32),
+            signal("/test/interface").append1(STATION),
+            signal("/test/strings").append2(STATION, "connected"),
+        ];
+        for message in &messages {
+            assert!(PropertiesChanged::read(message).is_none());
+            let path = message.path().unwrap().to_string();
+            assert!(
+                logged().iter().any(|line| line.starts_with("Ignoring")
+                    && line.contains(&format!(" on {}: ", path))),
+                "Nothing logged for {}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn mis_shaped_invalidated_properties_are_ignored() {
+        let mut changed = PropMap::new();
+        changed.insert(
+            "State".into(),
+            Variant(Box::new("connected".to_string())),
+        );
+        let message =
+            signal("/test/invalidated").append3(STATION, changed, 7u32);
+        let properties = PropertiesChanged::read(&message).unwrap();
+        assert_eq!(STATION, properties.interface);
+        assert!(properties.changed.contains_key("State"));
+        assert!(properties.invalidated.is_empty());
+    }
+
+    #[tokio::test]
+    async fn mis_shaped_messages_dont_hold_up_the_stream() {
+        let (sender, mut receiver) = channel(CAPACITY);
+        let path = "/net/connman/iwd/0/3";
+        for message in [
+            signal(path).append1(7u32),
+            state(path, "connected"),
+            signal(path),
+            state(path, "disconnected"),
+        ] {
+            assert!(sender.send(message));
+        }
+        drop(sender);
+        let mut read = Vec::new();
+        while let Some(message) = receiver.recv().await {
+            read.push(PropertiesChanged::read(&message).map(|properties| {
+                properties.changed["State"].0.as_str().unwrap().to_string()
+            }));
+        }
+        assert_eq!(
+            vec![
+                None,
+                Some("connected".to_string()),
+                None,
+                Some("disconnected".to_string())
+            ],
+            read
+        );
+    }
+
+    #[test]
+    fn sending_fails_once_the_receiver_has_gone() {
+        let (sender, receiver) = channel(CAPACITY);
+        drop(receiver);
+        assert!(!sender.send(state("/net/connman/iwd/0/3", "connected")));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
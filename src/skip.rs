@@ -0,0 +1,119 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            skip.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Reasons an update ended without setting the timezone.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use std::fmt;
+use std::time::Duration;
+
+/// Why an update ended without setting the timezone.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SkipReason {
+    /// The detected timezone isn't in allowed_timezones.
+    NotAllowed { timezone: String },
+
+    /// min_dwell hasn't passed since the last change.
+    Dwell { remaining: Duration },
+
+    /// max_changes_per_day has been reached.
+    Capped,
+
+    /// The change was declined at the --confirm prompt.
+    Declined,
+
+    /// The system is already set to the detected timezone.
+    Unchanged,
+
+    /// With apply_reconfirm, a different timezone was detected once
+    /// apply_delay had passed.
+    Unconfirmed { detected: String },
+
+    /// The connection went away while waiting out apply_delay.
+    Disconnected,
+
+    /// A more recent update has already set the timezone.
+    Stale,
+
+    /// With require_active_session, the daemon's session isn't the one in
+    /// the foreground.
+    InactiveSession,
+
+    /// With reject_country_mismatch, the detected timezone isn't used in the
+    /// country the access point advertises.
+    CountryMismatch { timezone: String, country: String },
+
+    /// Detection keeps failing on this network, so it's not being tried
+    /// there for now.
+    GivenUp { ssid: String },
+}
+
+impl SkipReason {
+    /// A short name for the reason, which statistics are keyed on.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NotAllowed { .. } => "not_allowed",
+            Self::Dwell { .. } => "dwell",
+            Self::Capped => "capped",
+            Self::Declined => "declined",
+            Self::Unchanged => "unchanged",
+            Self::Unconfirmed { .. } => "unconfirmed",
+            Self::Disconnected => "disconnected",
+            Self::Stale => "stale",
+            Self::InactiveSession => "inactive_session",
+            Self::CountryMismatch { .. } => "country_mismatch",
+            Self::GivenUp { .. } => "given_up",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "reason={}", self.name())?;
+        match self {
+            Self::NotAllowed { timezone } => {
+                write!(f, " timezone={}", timezone)
+            }
+            Self::Dwell { remaining } => {
+                write!(f, " remaining={}s", remaining.as_secs())
+            }
+            Self::Unconfirmed { detected } => {
+                write!(f, " detected={}", detected)
+            }
+            Self::CountryMismatch { timezone, country } => {
+                write!(f, " timezone={} country={}", timezone, country)
+            }
+            Self::GivenUp { ssid } => write!(f, " ssid={:?}", ssid),
+            _ => Ok(()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,400 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            socket.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     A local socket accepting commands, for scripts that don't
+//                  speak D-Bus.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use anyhow::{anyhow, Context};
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::fs::{self, DirBuilder, Permissions};
+use std::io::ErrorKind;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{self as unix_fs, DirBuilderExt, FileTypeExt};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+
+/// Commands longer than this are refused, and the client disconnected.
+const MAX_LINE: usize = 256;
+
+/// What a client can ask for, one per line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Command {
+    /// "update", or "update --force".
+    Update { force: bool },
+
+    /// "status".
+    Status,
+}
+
+impl Command {
+    fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["update"] => Ok(Self::Update { force: false }),
+            ["update", "--force"] => Ok(Self::Update { force: true }),
+            ["status"] => Ok(Self::Status),
+            _ => Err(format!("Unknown command: {:?}", line.trim())),
+        }
+    }
+}
+
+/// A command, passed to the daemon's loop with somewhere to send the
+/// response.
+pub struct Request {
+    pub command: Command,
+    pub reply: oneshot::Sender<Value>,
+}
+
+/// The response to a command that couldn't be carried out.
+pub fn error(message: &str) -> Value {
+    json!({ "ok": false, "error": message })
+}
+
+/// The group named `group`, or numbered, if it's a number.
+fn group_id(group: &str) -> Result<u32, anyhow::Error> {
+    if let Ok(id) = group.parse() {
+        return Ok(id);
+    }
+    let groups = fs::read_to_string("/etc/group")
+        .context("Couldn't read /etc/group")?;
+    groups
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split(':');
+            (Some(group) == fields.next())
+                .then(|| fields.nth(1)?.parse().ok())
+                .flatten()
+        })
+        .ok_or_else(|| anyhow!("No such group: {}", group))
+}
+
+/// Bind the socket at `path`, replacing one left behind by an earlier run,
+/// readable and writable by its owner and `group`.
+pub fn bind(
+    path: &Path,
+    group: Option<&str>,
+) -> Result<UnixListener, anyhow::Error> {
+    if let Some(directory) = path.parent() {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o755)
+            .create(directory)
+            .with_context(|| {
+                format!("Couldn't create {}", directory.display())
+            })?;
+    }
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            fs::remove_file(path)?
+        }
+        Ok(_) => {
+            return Err(anyhow!(
+                "{} exists, and isn't a socket",
+                path.display()
+            ))
+        }
+        Err(error) if ErrorKind::NotFound == error.kind() => {}
+        Err(error) => return Err(error.into()),
+    }
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Couldn't bind {}", path.display()))?;
+    fs::set_permissions(path, Permissions::from_mode(0o660))?;
+    if let Some(group) = group {
+        unix_fs::chown(path, None, Some(group_id(group)?)).with_context(
+            || format!("Couldn't give {} to {}", path.display(), group),
+        )?;
+    }
+    Ok(listener)
+}
+
+/// Accept clients on `listener` for as long as the daemon runs, passing
+/// their commands to `requests`.
+pub async fn serve(
+    listener: UnixListener,
+    path: PathBuf,
+    requests: mpsc::UnboundedSender<Request>,
+) {
+    info!("Accepting commands on {}", path.display());
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(error) => {
+                warn!("Couldn't accept on {}: {}", path.display(), error);
+                continue;
+            }
+        };
+        let requests = requests.clone();
+        tokio::spawn(async move {
+            if let Err(error) = client(stream, requests).await {
+                debug!("Control socket client: {:#}", error);
+            }
+        });
+    }
+}
+
+/// Answer the commands from one client, until it disconnects or sends a
+/// line that's too long.
+async fn client(
+    mut stream: UnixStream,
+    requests: mpsc::UnboundedSender<Request>,
+) -> Result<(), anyhow::Error> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; MAX_LINE];
+    loop {
+        while let Some(end) = buffer.iter().position(|byte| b'\n' == *byte) {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let response = match std::str::from_utf8(&line) {
+                Ok(line) if line.trim().is_empty() => continue,
+                Ok(line) => respond(line, &requests).await,
+                Err(_) => error("Commands must be UTF-8"),
+            };
+            let mut response = response.to_string();
+            response.push('\n');
+            stream.write_all(response.as_bytes()).await?;
+        }
+        if buffer.len() > MAX_LINE {
+            let mut response = error("Line too long").to_string();
+            response.push('\n');
+            stream.write_all(response.as_bytes()).await?;
+            return Err(anyhow!("Line too long"));
+        }
+
+        let count = stream.read(&mut chunk).await?;
+        if 0 == count {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..count]);
+    }
+}
+
+async fn respond(
+    line: &str,
+    requests: &mpsc::UnboundedSender<Request>,
+) -> Value {
+    let command = match Command::parse(line) {
+        Ok(command) => command,
+        Err(message) => return error(&message),
+    };
+    let (reply, response) = oneshot::channel();
+    if requests.send(Request { command, reply }).is_err() {
+        return error("The daemon is shutting down");
+    }
+    response
+        .await
+        .unwrap_or_else(|_| error("The daemon is shutting down"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    /// A socket in a directory of its own, with a daemon behind it that
+    /// answers status, and reports the updates it's asked for.
+    struct Daemon {
+        path: PathBuf,
+        updates: mpsc::UnboundedReceiver<bool>,
+    }
+
+    impl Daemon {
+        fn start(name: &str) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "socket-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let path = directory.join("control.sock");
+            let listener = bind(&path, None).unwrap();
+            let (sender, mut requests) = mpsc::unbounded_channel();
+            tokio::spawn(serve(listener, path.clone(), sender));
+            let (updated, updates) = mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                while let Some(request) = requests.recv().await {
+                    let Request { command, reply } = request;
+                    let response = match command {
+                        Command::Update { force } => {
+                            let _ = updated.send(force);
+                            json!({ "ok": true, "queued": true })
+                        }
+                        Command::Status => json!({ "ok": true, "status": {} }),
+                    };
+                    let _ = reply.send(response);
+                }
+            });
+            Self { path, updates }
+        }
+
+        async fn connect(&self) -> BufReader<UnixStream> {
+            BufReader::new(UnixStream::connect(&self.path).await.unwrap())
+        }
+    }
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            if let Some(directory) = self.path.parent() {
+                let _ = fs::remove_dir_all(directory);
+            }
+        }
+    }
+
+    async fn ask(stream: &mut BufReader<UnixStream>, line: &[u8]) -> Value {
+        stream.get_mut().write_all(line).await.unwrap();
+        let mut response = String::new();
+        stream.read_line(&mut response).await.unwrap();
+        assert!(response.ends_with('\n'));
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn commands_parse() {
+        assert_eq!(Ok(Command::Status), Command::parse("status\n"));
+        assert_eq!(
+            Ok(Command::Update { force: false }),
+            Command::parse(" update ")
+        );
+        assert_eq!(
+            Ok(Command::Update { force: true }),
+            Command::parse("update --force")
+        );
+        assert!(Command::parse("update --now").is_err());
+        assert!(Command::parse("reboot").is_err());
+    }
+
+    #[tokio::test]
+    async fn answers_each_command_on_a_line() {
+        let daemon = Daemon::start("answers");
+        let mut stream = daemon.connect().await;
+        let status = ask(&mut stream, b"status\n").await;
+        assert_eq!(json!(true), status["ok"]);
+        assert!(status["status"].is_object());
+        let update = ask(&mut stream, b"update\n").await;
+        assert_eq!(json!(true), update["queued"]);
+    }
+
+    #[tokio::test]
+    async fn passes_on_updates_and_whether_theyre_forced() {
+        let mut daemon = Daemon::start("updates");
+        let mut stream = daemon.connect().await;
+        ask(&mut stream, b"update\n").await;
+        ask(&mut stream, b"update --force\n").await;
+        assert_eq!(Some(false), daemon.updates.recv().await);
+        assert_eq!(Some(true), daemon.updates.recv().await);
+    }
+
+    #[tokio::test]
+    async fn takes_several_commands_in_one_write() {
+        let daemon = Daemon::start("pipelined");
+        let mut stream = daemon.connect().await;
+        let status = ask(&mut stream, b"status\n\nupdate\n").await;
+        assert!(status["status"].is_object());
+        let mut response = String::new();
+        stream.read_line(&mut response).await.unwrap();
+        let update: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(json!(true), update["queued"]);
+    }
+
+    #[tokio::test]
+    async fn unknown_commands_are_errors_but_keep_the_connection() {
+        let daemon = Daemon::start("unknown");
+        let mut stream = daemon.connect().await;
+        let response = ask(&mut stream, b"reboot\n").await;
+        assert_eq!(json!(false), response["ok"]);
+        assert!(response["error"].is_string());
+        let response = ask(&mut stream, b"\xff\n").await;
+        assert_eq!(json!(false), response["ok"]);
+        let status = ask(&mut stream, b"status\n").await;
+        assert_eq!(json!(true), status["ok"]);
+    }
+
+    #[tokio::test]
+    async fn disconnects_after_a_line_thats_too_long() {
+        let daemon = Daemon::start("long");
+        let mut stream = daemon.connect().await;
+        let line = vec![b'a'; 2 * MAX_LINE];
+        let response = ask(&mut stream, &line).await;
+        assert_eq!(json!("Line too long"), response["error"]);
+        let mut rest = String::new();
+        assert_eq!(0, stream.read_line(&mut rest).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn survives_clients_that_hang_up_early() {
+        let daemon = Daemon::start("hang-up");
+        // Part of a command, then gone.
+        let mut stream = daemon.connect().await;
+        stream.get_mut().write_all(b"stat").await.unwrap();
+        drop(stream);
+        // A command, but gone before the answer.
+        let mut stream = daemon.connect().await;
+        stream.get_mut().write_all(b"status\n").await.unwrap();
+        drop(stream);
+        let mut stream = daemon.connect().await;
+        let status = ask(&mut stream, b"status\n").await;
+        assert_eq!(json!(true), status["ok"]);
+    }
+
+    #[tokio::test]
+    async fn answers_once_the_daemon_has_gone() {
+        let directory = std::env::temp_dir()
+            .join(format!("socket-{}-gone", std::process::id()));
+        let path = directory.join("control.sock");
+        let listener = bind(&path, None).unwrap();
+        let (sender, requests) = mpsc::unbounded_channel();
+        drop(requests);
+        tokio::spawn(serve(listener, path.clone(), sender));
+        let mut stream =
+            BufReader::new(UnixStream::connect(&path).await.unwrap());
+        let response = ask(&mut stream, b"status\n").await;
+        assert_eq!(json!("The daemon is shutting down"), response["error"]);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bind_replaces_a_stale_socket_but_nothing_else() {
+        let directory = std::env::temp_dir()
+            .join(format!("socket-{}-stale", std::process::id()));
+        let path = directory.join("control.sock");
+        drop(bind(&path, None).unwrap());
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o660, mode & 0o777);
+        assert!(bind(&path, None).is_ok());
+        fs::remove_file(&path).unwrap();
+        fs::write(&path, "").unwrap();
+        assert!(bind(&path, None).is_err());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
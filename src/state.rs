@@ -0,0 +1,310 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            state.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     State persisted across restarts of the daemon.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::boot::{self, BootTime};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const STATE_FILE: &str = "state.json";
+
+/// Replace the file at `path` with `contents`, such that a reader sees
+/// either the old contents or the new, never a partial write. Both are on
+/// disk by the time this returns.
+pub fn write_atomically(
+    path: &Path,
+    contents: &str,
+) -> Result<(), anyhow::Error> {
+    write_atomically_with_mode(path, contents, 0o666)
+}
+
+/// Like [write_atomically], but the file is made with `mode` (less the
+/// umask), for files that mustn't be readable by everyone.
+pub fn write_atomically_with_mode(
+    path: &Path,
+    contents: &str,
+    mode: u32,
+) -> Result<(), anyhow::Error> {
+    let temporary = PathBuf::from(format!("{}.tmp", path.display()));
+    // The mode only applies to a new file, so don't reuse one left behind.
+    match fs::remove_file(&temporary) {
+        Err(error) if ErrorKind::NotFound != error.kind() => {
+            return Err(error).with_context(|| {
+                format!("Couldn't remove {}", temporary.display())
+            })
+        }
+        _ => {}
+    }
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(&temporary)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .with_context(|| format!("Couldn't write {}", temporary.display()))?;
+    // Neither the new file nor the rename is durable until the directory
+    // they're in has been flushed.
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+    let flush = || {
+        File::open(directory)
+            .and_then(|directory| directory.sync_all())
+            .with_context(|| format!("Couldn't flush {}", directory.display()))
+    };
+    flush()?;
+    fs::rename(&temporary, path)
+        .with_context(|| format!("Couldn't write {}", path.display()))?;
+    flush()
+}
+
+/// How a run of the daemon ended.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Shutdown {
+    /// Why it stopped, e.g. "terminated", or the error it gave up on.
+    pub reason: String,
+
+    /// When it stopped, in RFC 3339 format.
+    pub at: String,
+    pub uptime_seconds: u64,
+
+    /// Timezone changes applied during the run.
+    pub updates: u64,
+
+    /// Calls to SetTimezone that failed during the run.
+    pub failures: u64,
+
+    /// The timezone last applied, if any ever was.
+    pub timezone: Option<String>,
+}
+
+impl fmt::Display for Shutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "uptime={}s updates={} failures={} timezone={} reason={:?}",
+            self.uptime_seconds,
+            self.updates,
+            self.failures,
+            self.timezone.as_deref().unwrap_or("none"),
+            self.reason
+        )
+    }
+}
+
+/// A network on which no provider has been able to determine the timezone
+/// lately.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Unreachable {
+    /// Detections in a row that failed completely.
+    pub failures: u32,
+
+    /// When detection on the network was given up on, in seconds since the
+    /// Unix epoch, once failures reached negative_cache_after.
+    pub given_up_at: Option<u64>,
+    pub given_up_at_boot: Option<BootTime>,
+}
+
+/// A call to SetTimezone that counts against max_changes_per_day.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Change {
+    /// When it was made, in seconds since the Unix epoch.
+    pub at: u64,
+    pub at_boot: Option<BootTime>,
+}
+
+impl Change {
+    pub fn now() -> Self {
+        Self {
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            at_boot: BootTime::now(),
+        }
+    }
+
+    /// The time since it was made, relative to boot where possible.
+    pub fn elapsed(&self) -> Duration {
+        boot::since(self.at, self.at_boot.as_ref())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// The salted hash of the public IP address at the time of the last
+    /// lookup. The address itself is never written.
+    pub public_ip_hash: Option<String>,
+
+    /// The timezone decided on by the last lookup.
+    pub timezone: Option<String>,
+
+    /// The timezone in effect before `timezone`.
+    pub previous_timezone: Option<String>,
+
+    /// When the timezone last changed, in seconds since the Unix epoch. Only
+    /// for display, and as a fallback after a reboot.
+    pub changed_at: Option<u64>,
+
+    /// When the timezone last changed, relative to boot. This is what the
+    /// time since is measured from, since the wall clock may have been
+    /// stepped meanwhile.
+    pub changed_at_boot: Option<BootTime>,
+
+    /// The changes made in the last day, oldest first, so a restart doesn't
+    /// reset max_changes_per_day.
+    pub recent_changes: Vec<Change>,
+
+    /// A timezone that timedated failed to set, to be tried again.
+    pub unapplied_timezone: Option<String>,
+
+    /// How the last run ended, if it ended in a way it could record.
+    pub last_shutdown: Option<Shutdown>,
+
+    /// Networks that detection has failed on, by SSID.
+    pub unreachable_networks: BTreeMap<String, Unreachable>,
+}
+
+impl State {
+    /// Read the state from `directory`. A missing state file yields the
+    /// default (empty) state.
+    pub fn load(directory: &Path) -> Result<Self, anyhow::Error> {
+        let path = directory.join(STATE_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if ErrorKind::NotFound == error.kind() => {
+                return Ok(Self::default())
+            }
+            Err(error) => {
+                return Err(error).with_context(|| {
+                    format!("Couldn't read {}", path.display())
+                })
+            }
+        };
+
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Couldn't parse {}", path.display()))
+    }
+
+    /// Write the state to `directory`, replacing the previous state
+    /// atomically.
+    pub fn save(&self, directory: &Path) -> Result<(), anyhow::Error> {
+        fs::create_dir_all(directory).with_context(|| {
+            format!("Couldn't create {}", directory.display())
+        })?;
+        let path = directory.join(STATE_FILE);
+        write_atomically(&path, &serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "state-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn recent_changes_survive_a_restart() {
+        let directory = directory("changes");
+        let state = State {
+            recent_changes: vec![Change::now(), Change::now()],
+            ..State::default()
+        };
+        state.save(&directory).unwrap();
+        let loaded = State::load(&directory).unwrap();
+        assert_eq!(2, loaded.recent_changes.len());
+        assert!(loaded
+            .recent_changes
+            .iter()
+            .all(|change| change.elapsed() < DAY));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn recent_changes_are_timed_from_boot() {
+        // Made during this boot, with the wall clock wrong by a decade.
+        let change = Change {
+            at: 0,
+            ..Change::now()
+        };
+        if change.at_boot.is_some() {
+            assert!(change.elapsed() < Duration::from_secs(5));
+        }
+
+        // Made during another boot, a day ago by the wall clock.
+        let change = Change {
+            at: Change::now().at - DAY.as_secs(),
+            at_boot: Some(BootTime {
+                boot_id: "another boot".into(),
+                uptime: 0,
+            }),
+        };
+        assert!(change.elapsed() >= DAY);
+    }
+
+    #[test]
+    fn a_state_without_recent_changes_loads() {
+        let directory = directory("older");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join(STATE_FILE), r#"{"timezone": "UTC"}"#)
+            .unwrap();
+        let loaded = State::load(&directory).unwrap();
+        assert!(loaded.recent_changes.is_empty());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,87 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            statistics.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Counters describing what the daemon has done since it
+//                  started.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Statistics {
+    /// Timezone changes applied through timedated.
+    pub updates: u64,
+
+    /// Calls to SetTimezone that failed, after any retries.
+    pub failures: u64,
+
+    /// SetTimezone calls retried after a transient D-Bus error.
+    pub set_timezone_retries: u64,
+
+    /// Detections ignored because they weren't in allowed_timezones.
+    pub rejections: u64,
+
+    /// Changes not made because max_changes_per_day had been reached.
+    pub suppressed: u64,
+
+    /// Detections abandoned because they took longer than cycle_timeout.
+    pub cycle_timeouts: u64,
+
+    /// Detections that waited for a station to finish scanning.
+    pub scan_deferrals: u64,
+
+    /// Detections replaced by one of the rewrites.
+    pub rewrites: u64,
+
+    /// Changes rolled back because a critical hook failed.
+    pub rollbacks: u64,
+
+    /// Times a provider was skipped for failing too often.
+    pub demotions: u64,
+
+    /// Connectivity sessions started.
+    pub sessions: u64,
+
+    /// Connection events dropped because newer ones were waiting, and D-Bus
+    /// signals merged into later ones or dropped to make room.
+    pub signals_dropped: u64,
+
+    /// Updates waiting for the one in progress to finish.
+    pub queue_depth: usize,
+
+    /// Updates merged into one already waiting for the same station.
+    pub coalesced: u64,
+
+    /// Updates that ended without setting the timezone, by reason.
+    pub skipped: BTreeMap<&'static str, u64>,
+}
+
+///////////////////////////////////////////////////////////////////////////////
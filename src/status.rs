@@ -0,0 +1,215 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            status.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Status file for health checks that can't speak D-Bus.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::egress::Egress;
+use crate::provider::ProviderHealth;
+use crate::state::write_atomically;
+use log::warn;
+use serde::Serialize;
+use std::fs::{self, DirBuilder};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::PathBuf;
+use std::process;
+use std::time::SystemTime;
+
+pub const DEFAULT_STATUS_FILE: &str = "/run/iwd-auto-timezone/status.json";
+
+/// What the daemon is doing at the moment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activity {
+    /// Waiting for a connection.
+    Waiting,
+
+    /// Consulting the providers.
+    Detecting,
+
+    /// Waiting out apply_delay before setting the timezone.
+    Pending,
+}
+
+pub fn timestamp(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Status {
+    pub state: Activity,
+
+    /// The timezone most recently applied, if any.
+    pub timezone: Option<String>,
+
+    /// When `timezone` was applied, in RFC 3339 format.
+    pub applied_at: Option<String>,
+
+    /// The error that ended the most recent update, if it failed.
+    pub last_error: Option<String>,
+
+    /// Why the most recent update to end without setting the timezone did
+    /// so, e.g. "reason=dwell remaining=142s".
+    pub last_skip: Option<String>,
+
+    /// What the provider reported, when the last detection was rewritten.
+    pub rewritten_from: Option<String>,
+
+    /// The timezone about to be applied, once apply_delay has passed.
+    pub pending: Option<String>,
+
+    /// When `pending` is due to be applied, in RFC 3339 format.
+    pub pending_until: Option<String>,
+
+    /// A timezone that timedated failed to set, which is being retried.
+    pub pending_application: Option<String>,
+
+    /// How many times `pending_application` has been tried.
+    pub application_attempts: u32,
+
+    /// The connectivity session in progress, and when it started in RFC
+    /// 3339 format.
+    pub session: Option<String>,
+    pub session_started: Option<String>,
+
+    /// Whether the public IP address was the same at the last update as at
+    /// the lookup before.
+    pub egress: Egress,
+
+    /// The SSIDs of networks that detection has been given up on for now.
+    pub given_up_networks: Vec<String>,
+
+    /// How each provider has fared, once any have been consulted.
+    pub providers: Vec<ProviderHealth>,
+
+    pub pid: u32,
+}
+
+impl Status {
+    pub fn new(timezone: Option<String>) -> Self {
+        Self {
+            state: Activity::Waiting,
+            timezone,
+            applied_at: None,
+            last_error: None,
+            last_skip: None,
+            rewritten_from: None,
+            pending: None,
+            pending_until: None,
+            pending_application: None,
+            application_attempts: 0,
+            session: None,
+            session_started: None,
+            egress: Egress::Unknown,
+            given_up_networks: Vec::new(),
+            providers: Vec::new(),
+            pid: process::id(),
+        }
+    }
+
+    /// Record that `timezone` was applied just now.
+    pub fn applied(&mut self, timezone: &str) {
+        self.timezone = Some(timezone.to_string());
+        self.applied_at = Some(timestamp(SystemTime::now()));
+        self.last_error = None;
+        self.last_skip = None;
+    }
+
+    /// Record that `timezone` is being retried after `attempts` failed
+    /// attempts, or that nothing is if it's None.
+    pub fn unapplied(&mut self, timezone: Option<&str>, attempts: u32) {
+        self.pending_application = timezone.map(str::to_string);
+        self.application_attempts = attempts;
+    }
+
+    /// Record that `timezone` will be applied at `time`, or that nothing is
+    /// pending if it's None.
+    pub fn pending(&mut self, timezone: Option<&str>, time: SystemTime) {
+        self.pending = timezone.map(str::to_string);
+        self.pending_until = timezone.map(|_| timestamp(time));
+    }
+}
+
+/// Keeps the status file up to date. If the file can't be written (e.g.
+/// /run is read-only), that's logged once and the file is left alone from
+/// then on.
+pub struct StatusFile {
+    path: PathBuf,
+    enabled: bool,
+}
+
+impl StatusFile {
+    pub fn new(path: PathBuf) -> Self {
+        let mut file = Self {
+            path,
+            enabled: true,
+        };
+        if let Some(directory) = file.path.parent() {
+            let result = DirBuilder::new()
+                .recursive(true)
+                .mode(0o755)
+                .create(directory);
+            if let Err(error) = result {
+                file.disable(error.into());
+            }
+        }
+        file
+    }
+
+    fn disable(&mut self, error: anyhow::Error) {
+        warn!(
+            "Not maintaining status file {}: {:#}",
+            self.path.display(),
+            error
+        );
+        self.enabled = false;
+    }
+
+    pub fn write(&mut self, status: &Status) {
+        if !self.enabled {
+            return;
+        }
+        let result = serde_json::to_string_pretty(status)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| write_atomically(&self.path, &contents));
+        if let Err(error) = result {
+            self.disable(error);
+        }
+    }
+
+    /// Remove the status file, on shutdown.
+    pub fn remove(&self) {
+        if self.enabled {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,91 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            test_bus.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     A private message bus, for the tests that talk D-Bus.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use dbus::channel::Channel;
+use dbus::nonblock::SyncConnection;
+use dbus_tokio::connection;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+
+/// A dbus-daemon of our own, stopped when this is dropped.
+pub struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    /// Start a bus. Tests that use one can't do without it, so they fail,
+    /// rather than pass vacuously, where dbus-daemon isn't installed.
+    pub fn start() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("This test needs dbus-daemon on the PATH");
+        let mut address = String::new();
+        let stdout = daemon.stdout.take().unwrap();
+        let read = BufReader::new(stdout).read_line(&mut address);
+        let bus = Self {
+            daemon,
+            address: address.trim().to_string(),
+        };
+        match read {
+            Ok(_) if !bus.address.is_empty() => bus,
+            _ => panic!("dbus-daemon didn't say where it's listening"),
+        }
+    }
+
+    /// A new connection to the bus, driven on the current runtime.
+    pub fn connect(&self) -> Arc<SyncConnection> {
+        let mut channel = Channel::open_private(&self.address).unwrap();
+        channel.register().unwrap();
+        let (resource, connection) =
+            connection::from_channel::<SyncConnection>(channel).unwrap();
+        tokio::spawn(async {
+            let _ = resource.await;
+        });
+        connection
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,190 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            throttle.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Keeps repeated identical failures from flooding the log.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use crate::status::timestamp;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+
+/// How many failures in a row are logged one by one.
+const LOGGED: u64 = 3;
+
+/// After that, how often a summary is logged instead.
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Failures of one thing, in a row and of the same class.
+#[derive(Clone, Debug)]
+pub struct Streak {
+    pub class: &'static str,
+    pub count: u64,
+
+    /// When the streak began, for display.
+    pub since: SystemTime,
+
+    /// The same, on the monotonic clock, for how long it's lasted.
+    began: Instant,
+    next_summary: Instant,
+}
+
+impl Streak {
+    fn new(class: &'static str, now: Instant) -> Self {
+        Self {
+            class,
+            count: 0,
+            since: SystemTime::now(),
+            began: now,
+            next_summary: now,
+        }
+    }
+
+    /// How long the streak has lasted, to the second.
+    pub fn lasted(&self) -> Duration {
+        Duration::from_secs(self.began.elapsed().as_secs())
+    }
+}
+
+impl fmt::Display for Streak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} attempts in {} since {}",
+            self.count,
+            humantime::format_duration(self.lasted()),
+            timestamp(self.since)
+        )
+    }
+}
+
+/// What to log about a failure.
+pub enum Verdict {
+    /// Log it as usual.
+    Log,
+
+    /// Log a summary of the streak it belongs to instead.
+    Summarize(Streak),
+
+    /// Don't log it above debug level.
+    Suppress,
+}
+
+/// Tracks a streak per subject (e.g. a provider), so that only the first few
+/// failures and then a periodic summary reach the log.
+#[derive(Default)]
+pub struct Throttle {
+    streaks: HashMap<String, Streak>,
+}
+
+impl Throttle {
+    /// Record that `subject` failed with an error of `class`, which starts
+    /// a new streak if it's not the class of the last failure.
+    pub fn failed(&mut self, subject: &str, class: &'static str) -> Verdict {
+        let now = Instant::now();
+        let streak = self
+            .streaks
+            .entry(subject.to_string())
+            .and_modify(|streak| {
+                if streak.class != class {
+                    *streak = Streak::new(class, now);
+                }
+            })
+            .or_insert_with(|| Streak::new(class, now));
+        streak.count += 1;
+        if streak.count <= LOGGED {
+            streak.next_summary = now + SUMMARY_INTERVAL;
+            return Verdict::Log;
+        }
+        if now < streak.next_summary {
+            return Verdict::Suppress;
+        }
+        streak.next_summary = now + SUMMARY_INTERVAL;
+        Verdict::Summarize(streak.clone())
+    }
+
+    /// Record that `subject` worked. Returns the streak it ended, if any of
+    /// it went unlogged.
+    pub fn succeeded(&mut self, subject: &str) -> Option<Streak> {
+        self.streaks
+            .remove(subject)
+            .filter(|streak| streak.count > LOGGED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECADE: Duration = Duration::from_secs(10 * 365 * 24 * 60 * 60);
+
+    /// A streak long enough to be reported when it ends.
+    fn streak() -> Streak {
+        let mut throttle = Throttle::default();
+        for _ in 0..=LOGGED {
+            throttle.failed("ipapi", "timeout");
+        }
+        throttle.succeeded("ipapi").unwrap()
+    }
+
+    #[test]
+    fn how_long_a_streak_lasted_ignores_the_wall_clock() {
+        for since in [SystemTime::now() - DECADE, SystemTime::now() + DECADE] {
+            let streak = Streak { since, ..streak() };
+            assert!(streak.lasted() < Duration::from_secs(5));
+            let summary = format!("{}", streak);
+            assert!(
+                summary.starts_with("4 attempts in 0s since "),
+                "{}",
+                summary
+            );
+        }
+    }
+
+    #[test]
+    fn a_streak_is_logged_then_suppressed() {
+        let mut throttle = Throttle::default();
+        for _ in 0..LOGGED {
+            assert!(matches!(
+                throttle.failed("ipapi", "timeout"),
+                Verdict::Log
+            ));
+        }
+        assert!(matches!(
+            throttle.failed("ipapi", "timeout"),
+            Verdict::Suppress
+        ));
+        // Another class starts a new streak, logged from the start again.
+        assert!(matches!(throttle.failed("ipapi", "tls"), Verdict::Log));
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,285 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            zone.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Validation of timezone names.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+use anyhow::anyhow;
+use chrono::{Offset, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const DEFAULT_ZONEINFO: &str = "/usr/share/zoneinfo";
+
+/// A complete tz database has some 600 zones. Far fewer suggests it's been
+/// pruned, e.g. in a container image.
+const MIN_ZONES: usize = 300;
+
+/// Zones that every tz database worth the name has.
+const SENTINELS: [&str; 2] = ["UTC", "Europe/London"];
+
+/// The compact form of the tz database, which records which names are links
+/// (aliases) to which zones.
+const TZDATA: &str = "tzdata.zi";
+
+/// The tables of the countries each zone is used in. zone1970.tab lists
+/// every country for zones that are shared; zone.tab has the zones that were
+/// merged into others since 1970, which may still be what a provider says.
+const COUNTRY_TABLES: [&str; 2] = ["zone1970.tab", "zone.tab"];
+
+static DIRECTORY: OnceLock<PathBuf> = OnceLock::new();
+
+/// Look up zones in `path` instead of the default. Only the first call has
+/// any effect, and it must come before the database is first used.
+pub fn set_directory(path: &Path) {
+    let _ = DIRECTORY.set(path.to_path_buf());
+}
+
+/// Where the tz database is read from.
+pub fn directory() -> &'static Path {
+    DIRECTORY.get_or_init(|| PathBuf::from(DEFAULT_ZONEINFO))
+}
+
+/// Map of alias to zone, read from the tz database the first time it's
+/// needed. Empty if the system doesn't ship tzdata.zi.
+fn links() -> &'static HashMap<String, String> {
+    static LINKS: OnceLock<HashMap<String, String>> = OnceLock::new();
+    LINKS.get_or_init(|| {
+        let contents =
+            fs::read_to_string(directory().join(TZDATA)).unwrap_or_default();
+        contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                match (fields.next(), fields.next(), fields.next()) {
+                    (Some("L"), Some(target), Some(alias)) => {
+                        Some((alias.to_string(), target.to_string()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    })
+}
+
+/// Map of zone to the ISO 3166 codes of the countries that use it, read from
+/// the tz database the first time it's needed.
+fn country_table() -> &'static HashMap<String, Vec<String>> {
+    static COUNTRIES: OnceLock<HashMap<String, Vec<String>>> = OnceLock::new();
+    COUNTRIES.get_or_init(|| {
+        let mut table: HashMap<String, Vec<String>> = HashMap::new();
+        for name in COUNTRY_TABLES {
+            let contents =
+                fs::read_to_string(directory().join(name)).unwrap_or_default();
+            let rows = contents
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    let codes = fields.next()?;
+                    Some((fields.nth(1)?, codes))
+                });
+            for (zone, codes) in rows {
+                let countries = table.entry(zone.to_string()).or_default();
+                for code in codes.split(',') {
+                    if !countries.iter().any(|country| country == code) {
+                        countries.push(code.to_string());
+                    }
+                }
+            }
+        }
+        table
+    })
+}
+
+/// The countries that use the zone `name`, as ISO 3166 codes. Empty for
+/// zones that aren't a country's, like UTC, or if the tables are missing.
+pub fn countries(name: &str) -> &'static [String] {
+    country_table()
+        .get(name)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Check that the tz database is installed. Returns how many aliases it
+/// defines, which is zero if it doesn't ship tzdata.zi.
+pub fn database() -> Result<usize, anyhow::Error> {
+    if !directory().is_dir() {
+        return Err(anyhow!("{} does not exist", directory().display()));
+    }
+    Ok(links().len())
+}
+
+fn count_files(path: &Path) -> usize {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.file_type() {
+            // Symlinked directories aren't followed, since some point back up
+            // the tree (e.g. posix -> .), but symlinked zones are counted.
+            Ok(kind) if kind.is_dir() => count_files(&entry.path()),
+            _ if entry.path().is_file() => 1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Look for signs that the tz database is missing or incomplete, so that
+/// valid detections would be rejected. Returns a description of each.
+pub fn sanity_check() -> Vec<String> {
+    let directory = directory();
+    if !directory.is_dir() {
+        return vec![format!(
+            "{} does not exist, so no timezone will be accepted",
+            directory.display()
+        )];
+    }
+
+    let mut problems = Vec::new();
+    let zones = count_files(directory);
+    if zones < MIN_ZONES {
+        problems.push(format!(
+            "{} has only {} zones; tzdata appears incomplete",
+            directory.display(),
+            zones
+        ));
+    }
+    for zone in SENTINELS {
+        if !directory.join(zone).is_file() {
+            problems.push(format!(
+                "{} is missing from {}; tzdata appears incomplete",
+                zone,
+                directory.display()
+            ));
+        }
+    }
+    problems
+}
+
+/// The canonical name of the zone `name`, e.g. Asia/Kolkata for
+/// Asia/Calcutta. Names that aren't aliases are returned as they are.
+pub fn canonicalize(name: &str) -> String {
+    match links().get(name) {
+        Some(target) => target.clone(),
+        None => name.to_string(),
+    }
+}
+
+/// The offset from UTC currently in effect in the zone `name`, in seconds.
+pub fn utc_offset(name: &str) -> Option<i32> {
+    let zone: Tz = name.parse().ok()?;
+    Some(
+        Utc::now()
+            .with_timezone(&zone)
+            .offset()
+            .fix()
+            .local_minus_utc(),
+    )
+}
+
+fn is_well_formed(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('/')
+        && name.split('/').all(|part| !part.is_empty() && ".." != part)
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/_+-".contains(c))
+}
+
+/// Whether `timezone` matches any of the patterns in denied_timezones.
+pub fn is_denied(denied: &[Pattern], timezone: &str) -> bool {
+    denied.iter().any(|pattern| pattern.matches(timezone))
+}
+
+/// Whether two names are for the same timezone, one perhaps being an alias.
+pub fn is_same(one: &str, other: &str) -> bool {
+    canonicalize(one) == canonicalize(other)
+}
+
+/// A timezone name, or a prefix of one followed by '*', e.g. Etc/*.
+#[derive(Clone, Debug)]
+pub struct Pattern(String);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        match pattern.strip_suffix('*') {
+            Some(_) => Self(pattern.to_string()),
+            None => Self(canonicalize(pattern)),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self.0.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => self.0 == name,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        match self.0.strip_suffix('*') {
+            Some("") => Ok(()),
+            Some(prefix) if is_well_formed(prefix.trim_end_matches('/')) => {
+                Ok(())
+            }
+            Some(_) => Err(anyhow!("{:?} is not a timezone pattern", self.0)),
+            None => validate(&self.0),
+        }
+    }
+}
+
+/// Check that `name` is a timezone the system knows about. Providers are free
+/// to return anything at all, so this is applied to every detection before it
+/// goes anywhere near timedated.
+pub fn validate(name: &str) -> Result<(), anyhow::Error> {
+    if !is_well_formed(name) {
+        return Err(anyhow!("{:?} is not a timezone name", name));
+    }
+
+    if !directory().join(name).is_file() {
+        // If it's a zone that chrono-tz knows, the provider is probably
+        // right, and it's the system that's lacking.
+        if name.parse::<Tz>().is_ok() {
+            return Err(anyhow!(
+                "{} is missing from {}: tzdata appears incomplete",
+                name,
+                directory().display()
+            ));
+        }
+        return Err(anyhow!("Unknown timezone {}", name));
+    }
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,515 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            mod.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     What the integration tests share: a bus with fake services
+//                  on it, and the daemon running on it.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// Each test crate uses only some of this.
+#![allow(dead_code)]
+
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::channel::{Channel, MatchingReceiver, Sender};
+use dbus::message::MatchRule;
+use dbus::nonblock::{Proxy, SyncConnection};
+use dbus::Message;
+use dbus_crossroads::{Crossroads, IfaceBuilder};
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+const TIMEDATED: &str = "org.freedesktop.timedate1";
+const LOGIND: &str = "org.freedesktop.login1";
+const WPA_SUPPLICANT: &str = "fi.w1.wpa_supplicant1";
+const WPA_INTERFACE: &str = "/fi/w1/wpa_supplicant1/Interfaces/0";
+
+/// A dbus-daemon standing in for the system bus, with a fake timedated on
+/// it that records the timezones it's asked to set.
+pub struct Bus {
+    daemon: Child,
+    address: String,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+struct Timedated {
+    timezone: String,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl Bus {
+    /// Start the bus. The tests can't do without it, so they fail, rather
+    /// than pass vacuously, where dbus-daemon isn't installed.
+    pub async fn start(timezone: &str) -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("These tests need dbus-daemon on the PATH");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let bus = Self {
+            daemon,
+            address: address.trim().to_string(),
+            calls: Arc::default(),
+        };
+        bus.serve(timezone).await;
+        bus
+    }
+
+    /// A new connection to the bus, driven on the current runtime.
+    pub fn connect(&self) -> Arc<SyncConnection> {
+        let mut channel = Channel::open_private(&self.address).unwrap();
+        channel.register().unwrap();
+        let (resource, connection) =
+            dbus_tokio::connection::from_channel::<SyncConnection>(channel)
+                .unwrap();
+        tokio::spawn(async {
+            let _ = resource.await;
+        });
+        connection
+    }
+
+    async fn serve(&self, timezone: &str) {
+        let mut crossroads = Crossroads::new();
+        let interface = crossroads.register(
+            TIMEDATED,
+            |b: &mut IfaceBuilder<Timedated>| {
+                b.property("Timezone")
+                    .get(|_, timedated| Ok(timedated.timezone.clone()));
+                b.property("NTPSynchronized").get(|_, _| Ok(true));
+                b.method(
+                    "SetTimezone",
+                    ("timezone", "interactive"),
+                    (),
+                    |_, timedated, (timezone, _): (String, bool)| {
+                        timedated.calls.lock().unwrap().push(timezone.clone());
+                        timedated.timezone = timezone;
+                        Ok(())
+                    },
+                );
+            },
+        );
+        let timedated = Timedated {
+            timezone: timezone.to_string(),
+            calls: self.calls.clone(),
+        };
+        crossroads.insert(
+            "/org/freedesktop/timedate1",
+            &[interface],
+            timedated,
+        );
+        self.export(TIMEDATED, crossroads).await;
+    }
+
+    /// The timezones timedated has been asked to set, in order.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Serve `crossroads` under `name`, on a connection of its own.
+    async fn export(
+        &self,
+        name: &'static str,
+        mut crossroads: Crossroads,
+    ) -> Arc<SyncConnection> {
+        let connection = self.connect();
+        connection.start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |message, connection| {
+                let _ = crossroads.handle_message(message, connection);
+                true
+            }),
+        );
+        connection
+            .request_name(name, false, true, false)
+            .await
+            .unwrap();
+        connection
+    }
+
+    /// Serve a fake logind, which puts every process in one session,
+    /// `active` or not.
+    pub async fn logind(&self, active: bool) {
+        let mut crossroads = Crossroads::new();
+        let manager = crossroads.register(
+            "org.freedesktop.login1.Manager",
+            |b: &mut IfaceBuilder<()>| {
+                b.method(
+                    "GetSessionByPID",
+                    ("pid",),
+                    ("session",),
+                    |_, _, _: (u32,)| {
+                        Ok((dbus::Path::from(
+                            "/org/freedesktop/login1/session/c1",
+                        ),))
+                    },
+                );
+            },
+        );
+        let session = crossroads.register(
+            "org.freedesktop.login1.Session",
+            |b: &mut IfaceBuilder<bool>| {
+                b.property("Active").get(|_, active| Ok(*active));
+            },
+        );
+        crossroads.insert("/org/freedesktop/login1", &[manager], ());
+        crossroads.insert(
+            "/org/freedesktop/login1/session/c1",
+            &[session],
+            active,
+        );
+        self.export(LOGIND, crossroads).await;
+    }
+
+    /// Serve a fake wpa_supplicant with one interface, on a network whose
+    /// access point advertises `country`.
+    pub async fn wpa_supplicant(&self, country: &str) -> WpaSupplicant {
+        let network = "/fi/w1/wpa_supplicant1/Interfaces/0/Networks/0";
+        let bss = "/fi/w1/wpa_supplicant1/Interfaces/0/BSSs/0";
+        let mut crossroads = Crossroads::new();
+        let root =
+            crossroads.register(WPA_SUPPLICANT, |b: &mut IfaceBuilder<()>| {
+                b.property("Interfaces")
+                    .get(|_, _| Ok(vec![dbus::Path::from(WPA_INTERFACE)]));
+            });
+        let interface = crossroads.register(
+            "fi.w1.wpa_supplicant1.Interface",
+            |b: &mut IfaceBuilder<()>| {
+                b.property("State").get(|_, _| Ok("completed".to_string()));
+                b.property("CurrentNetwork")
+                    .get(move |_, _| Ok(dbus::Path::from(network)));
+                b.property("CurrentBSS")
+                    .get(move |_, _| Ok(dbus::Path::from(bss)));
+            },
+        );
+        let properties = crossroads.register(
+            "fi.w1.wpa_supplicant1.Network",
+            |b: &mut IfaceBuilder<()>| {
+                b.property("Properties").get(|_, _| {
+                    let mut properties = PropMap::new();
+                    let ssid = Box::new("\"cafe\"".to_string());
+                    properties.insert("ssid".to_string(), Variant(ssid));
+                    Ok(properties)
+                });
+            },
+        );
+        let elements = crossroads.register(
+            "fi.w1.wpa_supplicant1.BSS",
+            |b: &mut IfaceBuilder<Vec<u8>>| {
+                b.property("IEs").get(|_, elements| Ok(elements.clone()));
+            },
+        );
+        crossroads.insert("/fi/w1/wpa_supplicant1", &[root], ());
+        crossroads.insert(WPA_INTERFACE, &[interface], ());
+        crossroads.insert(network, &[properties], ());
+        // Nothing but the Country element.
+        let mut ies = vec![7, 3];
+        ies.extend(country.bytes());
+        ies.push(b' ');
+        crossroads.insert(bss, &[elements], ies);
+        WpaSupplicant(self.export(WPA_SUPPLICANT, crossroads).await)
+    }
+}
+
+/// A fake wpa_supplicant, served by Bus::wpa_supplicant.
+pub struct WpaSupplicant(Arc<SyncConnection>);
+
+impl WpaSupplicant {
+    /// Announce that the interface has finished connecting.
+    pub fn completed(&self) {
+        let mut changed = PropMap::new();
+        changed.insert(
+            "State".to_string(),
+            Variant(Box::new("completed".to_string()) as Box<dyn RefArg>),
+        );
+        let signal = Message::signal(
+            &WPA_INTERFACE.into(),
+            &"org.freedesktop.DBus.Properties".into(),
+            &"PropertiesChanged".into(),
+        )
+        .append3(
+            "fi.w1.wpa_supplicant1.Interface",
+            changed,
+            Vec::<String>::new(),
+        );
+        self.0.send(signal).unwrap();
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// The daemon on `bus`, configured by the file at `config`.
+pub fn command(bus: &Bus, config: &Path) -> tokio::process::Command {
+    let mut command =
+        tokio::process::Command::new(env!("CARGO_BIN_EXE_iwd-auto-timezone"));
+    command
+        .arg("--config")
+        .arg(config)
+        .env("DBUS_SYSTEM_BUS_ADDRESS", &bus.address)
+        .env("RUST_LOG", "info")
+        .kill_on_drop(true);
+    command
+}
+
+/// A directory for one test, with a configuration that takes commands on a
+/// socket in it, for `backend`, with `settings` added.
+pub fn directory(name: &str, backend: &str, settings: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "daemon-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    let config = format!(
+        "state_directory = \"{}\"\nstatus_file = \"{}\"\n\
+         control_socket = \"{}\"\nbackend = \"{}\"\njitter = 0.0\n{}\n",
+        directory.join("state").display(),
+        directory.join("status.json").display(),
+        socket(&directory).display(),
+        backend,
+        settings
+    );
+    fs::write(directory.join("config.toml"), config).unwrap();
+    directory
+}
+
+pub fn socket(directory: &Path) -> PathBuf {
+    directory.join("control.sock")
+}
+
+/// Another directory for the test `name`, for whatever its providers read.
+pub fn scratch(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "daemon-{}-{}-files",
+        std::process::id(),
+        name
+    ))
+}
+
+/// Settings for a provider that reads the timezone from `directory`, set to
+/// `timezone` for now.
+pub fn provider(directory: &Path, timezone: &str) -> String {
+    fs::create_dir_all(directory).unwrap();
+    fs::write(directory.join("timezone"), timezone).unwrap();
+    format!(
+        "[[providers]]\ntype = \"file\"\npath = \"{}\"\n",
+        directory.join("timezone").display()
+    )
+}
+
+/// Wait for timedated to have been asked to set `count` timezones.
+pub async fn set(bus: &Bus, daemon: &Daemon, count: usize) {
+    let set = tokio::time::timeout(Duration::from_secs(10), async {
+        while bus.calls().len() < count {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(set.is_ok(), "{:?}\n{}", bus.calls(), daemon.log());
+}
+
+/// What GetRecentDetections returns.
+pub async fn recent_detections(bus: &Bus) -> Vec<PropMap> {
+    let proxy = Proxy::new(
+        "io.github.AmateurECE.IwdAutoTimezone1",
+        "/io/github/AmateurECE/IwdAutoTimezone1",
+        Duration::from_secs(1),
+        bus.connect(),
+    );
+    let (detections,): (Vec<PropMap>,) = proxy
+        .method_call(
+            "io.github.AmateurECE.IwdAutoTimezone1",
+            "GetRecentDetections",
+            (),
+        )
+        .await
+        .unwrap();
+    detections
+}
+
+/// The Guards of a detection, as "name=ruling".
+pub fn guards(detection: &PropMap) -> Vec<String> {
+    let Some(guards) = detection.get("Guards") else {
+        return Vec::new();
+    };
+    guards
+        .0
+        .as_iter()
+        .unwrap()
+        .map(|guard| {
+            let mut fields = guard.as_iter().unwrap();
+            let name = fields.next().unwrap().as_str().unwrap().to_string();
+            let ruling = fields.next().unwrap().as_str().unwrap().to_string();
+            format!("{}={}", name, ruling)
+        })
+        .collect()
+}
+
+/// Add each line read from `output` to `log`.
+fn collect(
+    output: impl AsyncRead + Unpin + Send + 'static,
+    log: &Arc<Mutex<String>>,
+) {
+    let collected = log.clone();
+    tokio::spawn(async move {
+        let mut lines = tokio::io::BufReader::new(output).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut log = collected.lock().unwrap();
+            log.push_str(&line);
+            log.push('\n');
+        }
+    });
+}
+
+/// A daemon left running while a test talks to it, with its log collected
+/// as it goes.
+pub struct Daemon {
+    child: tokio::process::Child,
+    log: Arc<Mutex<String>>,
+}
+
+impl Daemon {
+    pub fn spawn(mut command: tokio::process::Command) -> Self {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let log: Arc<Mutex<String>> = Arc::default();
+        collect(child.stdout.take().unwrap(), &log);
+        collect(child.stderr.take().unwrap(), &log);
+        Self { child, log }
+    }
+
+    /// The daemon configured in `directory`, once it's taking commands.
+    pub async fn start(bus: &Bus, directory: &Path) -> Self {
+        let daemon = Self::spawn(command(bus, &directory.join("config.toml")));
+        daemon.accepting().await;
+        daemon
+    }
+
+    /// The daemon configured in `directory`, with `arguments`, on a
+    /// terminal of its own that no one answers, once it's taking commands.
+    pub async fn on_terminal(
+        bus: &Bus,
+        directory: &Path,
+        arguments: &str,
+    ) -> Self {
+        let line = format!(
+            "{} --config {} {}",
+            env!("CARGO_BIN_EXE_iwd-auto-timezone"),
+            directory.join("config.toml").display(),
+            arguments
+        );
+        let mut command = tokio::process::Command::new("script");
+        command
+            .args(["--quiet", "--flush", "--return", "--command"])
+            .arg(line)
+            .arg("/dev/null")
+            .env("DBUS_SYSTEM_BUS_ADDRESS", &bus.address)
+            .env("RUST_LOG", "info")
+            // Held open, as no one answers.
+            .stdin(Stdio::piped())
+            .kill_on_drop(true);
+        let daemon = Self::spawn(command);
+        daemon.accepting().await;
+        daemon
+    }
+
+    async fn accepting(&self) {
+        self.wait_for("Accepting commands on", Duration::from_secs(10))
+            .await;
+    }
+
+    pub fn log(&self) -> String {
+        self.log.lock().unwrap().clone()
+    }
+
+    /// Wait for `text` to be logged, failing the test if it isn't within
+    /// `limit`.
+    pub async fn wait_for(&self, text: &str, limit: Duration) {
+        let logged = tokio::time::timeout(limit, async {
+            while !self.log().contains(text) {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await;
+        assert!(logged.is_ok(), "{:?} wasn't logged:\n{}", text, self.log());
+    }
+
+    /// Stop the daemon with SIGTERM, as systemd would, and wait for it to
+    /// exit.
+    pub async fn terminate(mut self) -> (ExitStatus, String) {
+        let pid = self.child.id().unwrap().to_string();
+        let killed = Command::new("kill").args(["-TERM", &pid]).status();
+        assert!(killed.unwrap().success());
+        let waiting = self.child.wait();
+        let status = tokio::time::timeout(Duration::from_secs(10), waiting)
+            .await
+            .expect("The daemon didn't exit")
+            .unwrap();
+        // Give the rest of the log a moment to be read.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        (status, self.log())
+    }
+}
+
+/// Send `line` to the control socket at `path`, and parse the response.
+pub async fn ask(path: &Path, line: &str) -> Value {
+    let mut stream = UnixStream::connect(path).await.unwrap();
+    stream
+        .write_all(format!("{}\n", line).as_bytes())
+        .await
+        .unwrap();
+    let mut response = String::new();
+    tokio::io::BufReader::new(stream)
+        .read_line(&mut response)
+        .await
+        .unwrap();
+    serde_json::from_str(&response).unwrap()
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,197 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            daemon.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Tests of the daemon as a whole, on a bus of its own.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+#![cfg(all(
+    feature = "backend-netlink",
+    any(feature = "provider-http", feature = "provider-file")
+))]
+
+mod common;
+
+use common::{ask, directory, recent_detections, socket, Bus, Daemon};
+#[cfg(feature = "provider-file")]
+use common::{provider, scratch, set};
+use std::fs;
+#[cfg(feature = "provider-http")]
+use std::net::TcpListener;
+#[cfg(feature = "provider-http")]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(feature = "provider-http")]
+use std::time::Instant;
+
+/// A web server that takes connections, but never answers, counting the
+/// connections it's taken.
+#[cfg(feature = "provider-http")]
+fn unresponsive() -> (u16, Arc<Mutex<usize>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let taken: Arc<Mutex<usize>> = Arc::default();
+    let counted = taken.clone();
+    std::thread::spawn(move || {
+        let mut connections = Vec::new();
+        for stream in listener.incoming() {
+            connections.push(stream);
+            *counted.lock().unwrap() += 1;
+        }
+    });
+    (port, taken)
+}
+
+#[cfg(feature = "provider-http")]
+#[tokio::test]
+async fn a_hung_provider_is_cut_off_and_the_daemon_keeps_answering() {
+    let bus = Bus::start("UTC").await;
+    let (port, taken) = unresponsive();
+    let settings = format!(
+        "cycle_timeout = \"2s\"\n[[providers]]\ntype = \"http\"\n\
+         url = \"http://127.0.0.1:{}/\"\nformat = \"text\"\n",
+        port
+    );
+    let directory = directory("cycle-timeout", "netlink", &settings);
+    let daemon = Daemon::start(&bus, &directory).await;
+    let started = Instant::now();
+    let response = ask(&socket(&directory), "update").await;
+    assert_eq!(Some(true), response["queued"].as_bool(), "{}", response);
+    while 0 == *taken.lock().unwrap() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // While the provider hangs, both the socket and the bus are answered.
+    let asked = Instant::now();
+    let response = ask(&socket(&directory), "status").await;
+    assert_eq!(Some(true), response["ok"].as_bool(), "{}", response);
+    assert_eq!("detecting", response["status"]["state"], "{}", response);
+    assert!(recent_detections(&bus).await.is_empty());
+    assert!(asked.elapsed() < Duration::from_secs(1));
+
+    daemon
+        .wait_for(
+            "Detection timed out after 2s (cycle_timeout)",
+            Duration::from_secs(10),
+        )
+        .await;
+    assert!(started.elapsed() >= Duration::from_secs(2));
+    let detections = recent_detections(&bus).await;
+    assert_eq!(1, detections.len());
+    assert_eq!(Some("timeout"), detections[0]["Error"].0.as_str());
+
+    // The next update starts afresh, consulting the provider again.
+    let response = ask(&socket(&directory), "update").await;
+    assert_eq!(Some(false), response["coalesced"].as_bool(), "{}", response);
+    let consulted = tokio::time::timeout(Duration::from_secs(5), async {
+        while *taken.lock().unwrap() < 2 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(consulted.is_ok(), "{}", daemon.log());
+    let (status, log) = daemon.terminate().await;
+    assert!(status.success(), "{}", log);
+    assert!(bus.calls().is_empty());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[cfg(feature = "provider-file")]
+#[tokio::test]
+async fn the_shutdown_summary_covers_the_run() {
+    let bus = Bus::start("UTC").await;
+    let name = "shutdown";
+    let scratch = scratch(name);
+    let directory =
+        directory(name, "netlink", &provider(&scratch, "Europe/Berlin"));
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    set(&bus, &daemon, 1).await;
+    fs::write(scratch.join("timezone"), "Europe/Paris").unwrap();
+    ask(&socket(&directory), "update --force").await;
+    set(&bus, &daemon, 2).await;
+    assert!(directory.join("status.json").exists());
+
+    let (status, log) = daemon.terminate().await;
+    assert!(status.success(), "{}", log);
+    assert!(
+        log.contains(
+            "updates=2 failures=0 timezone=Europe/Paris \
+             reason=\"terminated\""
+        ),
+        "{}",
+        log
+    );
+    let state = fs::read_to_string(directory.join("state/state.json"));
+    let state: serde_json::Value =
+        serde_json::from_str(&state.unwrap()).unwrap();
+    let summary = &state["last_shutdown"];
+    assert_eq!("terminated", summary["reason"], "{}", summary);
+    assert_eq!(2, summary["updates"], "{}", summary);
+    assert_eq!(0, summary["failures"], "{}", summary);
+    assert_eq!("Europe/Paris", summary["timezone"], "{}", summary);
+    assert!(summary["uptime_seconds"].as_u64().is_some_and(|up| up < 30));
+    assert!(summary["at"].as_str().is_some_and(|at| !at.is_empty()));
+    assert!(!directory.join("status.json").exists());
+    fs::remove_dir_all(&directory).unwrap();
+    fs::remove_dir_all(&scratch).unwrap();
+}
+
+#[cfg(feature = "provider-file")]
+#[tokio::test]
+async fn status_has_the_recent_detections_but_the_status_file_doesnt() {
+    let bus = Bus::start("UTC").await;
+    let name = "recent";
+    let scratch = scratch(name);
+    let directory =
+        directory(name, "netlink", &provider(&scratch, "Europe/Paris"));
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    set(&bus, &daemon, 1).await;
+    let reply = ask(&socket(&directory), "status").await;
+    let written = fs::read_to_string(directory.join("status.json")).unwrap();
+    let (status, log) = daemon.terminate().await;
+    assert!(status.success(), "{}", log);
+
+    assert_eq!(Some(true), reply["ok"].as_bool(), "{}", reply);
+    let detections = reply["recent_detections"].as_array().unwrap();
+    assert_eq!(1, detections.len(), "{}", reply);
+    assert_eq!(Some("Europe/Paris"), detections[0]["timezone"].as_str());
+    assert_eq!(Some(true), detections[0]["applied"].as_bool());
+    assert!(written.contains("Europe/Paris"), "{}", written);
+    assert!(!written.contains("recent_detections"), "{}", written);
+    fs::remove_dir_all(&directory).unwrap();
+    fs::remove_dir_all(&scratch).unwrap();
+}
+
+///////////////////////////////////////////////////////////////////////////////
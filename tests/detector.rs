@@ -0,0 +1,198 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            detector.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Tests for the Detector API, against a mocked HTTP service.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+#![cfg(feature = "provider-http")]
+
+use iwd_auto_timezone::{Config, Detector};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A Geo-IP service on localhost that gives every request the same
+/// response, and counts them.
+struct Service {
+    url: String,
+    requests: Arc<AtomicUsize>,
+}
+
+impl Service {
+    async fn start(status: &str, body: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/json", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = response.clone();
+                tokio::spawn(async move {
+                    // Just enough of HTTP: read up to the end of the headers.
+                    let mut request = Vec::new();
+                    let mut chunk = [0; 1024];
+                    while !request.windows(4).any(|end| b"\r\n\r\n" == end) {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(count) => {
+                                request.extend_from_slice(&chunk[..count])
+                            }
+                        }
+                    }
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        Self { url, requests }
+    }
+
+    async fn json(body: &str) -> Self {
+        Self::start("200 OK", body).await
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// A provider entry for this service.
+    fn provider(&self) -> String {
+        format!(
+            "[[providers]]\n\
+             type = \"http\"\n\
+             url = \"{}\"\n\
+             format = \"json\"\n\
+             field = \"timezone\"\n\
+             latitude_field = \"lat\"\n\
+             longitude_field = \"lon\"\n",
+            self.url
+        )
+    }
+}
+
+fn config(toml: &str) -> Config {
+    toml::from_str(toml).unwrap()
+}
+
+#[tokio::test]
+async fn detects_the_timezone_and_location() {
+    let service = Service::json(
+        r#"{"timezone": "Europe/Berlin", "lat": 52.52, "lon": 13.4}"#,
+    )
+    .await;
+    let mut detector =
+        Detector::from_config(&config(&service.provider())).unwrap();
+    let detection = detector.detect().await.unwrap();
+    assert_eq!("Europe/Berlin", detection.timezone);
+    assert_eq!("127.0.0.1", detection.provider);
+    let coordinates = detection.coordinates.unwrap();
+    assert_eq!(52.52, coordinates.latitude);
+    assert_eq!(13.4, coordinates.longitude);
+    assert_eq!(1, service.requests());
+}
+
+#[tokio::test]
+async fn canonicalizes_what_a_provider_reports() {
+    let service = Service::json(r#"{"timezone": "Asia/Calcutta"}"#).await;
+    let mut detector =
+        Detector::from_config(&config(&service.provider())).unwrap();
+    let detection = detector.detect().await.unwrap();
+    assert_eq!("Asia/Kolkata", detection.timezone);
+    assert!(detection.coordinates.is_none());
+}
+
+#[tokio::test]
+async fn applies_rewrites() {
+    let service = Service::json(r#"{"timezone": "Europe/Berlin"}"#).await;
+    let toml = format!(
+        "{}\n[[rewrites]]\nfrom = \"Europe/*\"\nto = \"Europe/Paris\"\n",
+        service.provider()
+    );
+    let mut detector = Detector::from_config(&config(&toml)).unwrap();
+    assert_eq!("Europe/Paris", detector.detect().await.unwrap().timezone);
+}
+
+#[tokio::test]
+async fn rejects_denied_and_unknown_timezones() {
+    let denied = Service::json(r#"{"timezone": "Europe/Berlin"}"#).await;
+    let toml =
+        format!("denied_timezones = [\"Europe/*\"]\n{}", denied.provider());
+    let mut detector = Detector::from_config(&config(&toml)).unwrap();
+    assert!(detector.detect().await.is_err());
+    assert_eq!(1, denied.requests());
+
+    let unknown = Service::json(r#"{"timezone": "Europe/Nowhere"}"#).await;
+    let mut detector =
+        Detector::from_config(&config(&unknown.provider())).unwrap();
+    assert!(detector.detect().await.is_err());
+}
+
+#[tokio::test]
+async fn falls_back_to_the_next_provider() {
+    let failing = Service::start("500 Internal Server Error", "{}").await;
+    let working = Service::json(r#"{"timezone": "America/Chicago"}"#).await;
+    let toml = format!(
+        "{}{}",
+        failing.provider(),
+        working.provider().replace("127.0.0.1", "localhost")
+    );
+    let mut detector = Detector::from_config(&config(&toml)).unwrap();
+    let detection = detector.detect().await.unwrap();
+    assert_eq!("America/Chicago", detection.timezone);
+    assert_eq!("localhost", detection.provider);
+    assert_eq!(1, failing.requests());
+    assert_eq!(1, working.requests());
+}
+
+#[tokio::test]
+async fn refuses_an_invalid_configuration() {
+    let service = Service::json(r#"{"timezone": "Europe/Berlin"}"#).await;
+    let toml = format!(
+        "allowed_timezones = [\"Not/AZone\"]\n{}",
+        service.provider()
+    );
+    assert!(Detector::from_config(&config(&toml)).is_err());
+    assert_eq!(0, service.requests());
+}
+
+///////////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,228 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            faults.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Tests of how the daemon copes with the faults IAT_FAULTS injects.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+#![cfg(all(feature = "faults", feature = "provider-file"))]
+
+mod common;
+
+use common::Bus;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::time::Duration;
+
+/// A directory for one test, with a configuration whose providers are two
+/// files saying Europe/Berlin.
+fn directory(name: &str, settings: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "faults-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("timezone"), "Europe/Berlin\n").unwrap();
+    let provider = format!(
+        "[[providers]]\ntype = \"file\"\npath = \"{}\"\n",
+        directory.join("timezone").display()
+    );
+    let config = format!(
+        "state_directory = \"{}\"\nstatus_file = \"{}\"\njitter = 0.0\n\
+         {}\n{}{}",
+        directory.join("state").display(),
+        directory.join("status.json").display(),
+        settings,
+        provider,
+        provider
+    );
+    fs::write(directory.join("config.toml"), config).unwrap();
+    directory
+}
+
+/// The daemon on `bus`, with `faults` injected.
+fn command(
+    bus: &Bus,
+    directory: &Path,
+    faults: &str,
+) -> tokio::process::Command {
+    let mut command = common::command(bus, &directory.join("config.toml"));
+    command.env("IAT_FAULTS", faults);
+    command
+}
+
+/// Run the daemon until it exits.
+async fn daemon(
+    bus: &Bus,
+    directory: &Path,
+    faults: &str,
+    once: bool,
+) -> Output {
+    let mut command = command(bus, directory, faults);
+    if once {
+        command.arg("--once");
+    }
+    let run = command.output();
+    tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("The daemon didn't exit")
+        .unwrap()
+}
+
+fn log(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[tokio::test]
+async fn a_provider_that_times_out_is_skipped() {
+    let bus = Bus::start("UTC").await;
+    let directory = directory("provider-timeout", "");
+    let output = daemon(&bus, &directory, "provider-timeout", true).await;
+    assert!(output.status.success(), "{}", log(&output));
+    assert!(log(&output).contains("injected fault"), "{}", log(&output));
+    assert_eq!(vec!["Europe/Berlin"], bus.calls());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[tokio::test]
+async fn set_timezone_is_retried_after_no_reply() {
+    let bus = Bus::start("UTC").await;
+    let directory = directory("no-reply", "");
+    let output =
+        daemon(&bus, &directory, "set-timezone-no-reply*2", true).await;
+    assert!(output.status.success(), "{}", log(&output));
+    let retries = log(&output).matches("SetTimezone failed, retrying").count();
+    assert_eq!(2, retries, "{}", log(&output));
+    assert_eq!(vec!["Europe/Berlin"], bus.calls());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[tokio::test]
+async fn set_timezone_is_given_up_on_after_the_retries() {
+    let bus = Bus::start("UTC").await;
+    let directory = directory("no-reply-forever", "");
+    let output =
+        daemon(&bus, &directory, "set-timezone-no-reply*10", true).await;
+    assert!(!output.status.success(), "{}", log(&output));
+    let retries = log(&output).matches("SetTimezone failed, retrying").count();
+    assert_eq!(3, retries, "{}", log(&output));
+    assert!(bus.calls().is_empty());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[tokio::test]
+async fn access_denied_is_not_retried() {
+    let bus = Bus::start("UTC").await;
+    let directory = directory("denied", "");
+    let output = daemon(&bus, &directory, "set-timezone-denied", true).await;
+    assert!(!output.status.success(), "{}", log(&output));
+    assert!(log(&output).contains("Not authorized"), "{}", log(&output));
+    assert!(!log(&output).contains("retrying"), "{}", log(&output));
+    assert!(bus.calls().is_empty());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[tokio::test]
+async fn an_unchanged_timezone_is_skipped_after_a_fault() {
+    let bus = Bus::start("Europe/Berlin").await;
+    let directory = directory("unchanged", "");
+    let output = daemon(&bus, &directory, "provider-timeout", true).await;
+    assert!(output.status.success(), "{}", log(&output));
+    assert!(
+        log(&output).contains("Skipping update: reason=unchanged"),
+        "{}",
+        log(&output)
+    );
+    assert!(bus.calls().is_empty());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[cfg(feature = "backend-netlink")]
+#[tokio::test]
+async fn the_backend_is_given_up_on_after_repeated_disconnects() {
+    let bus = Bus::start("UTC").await;
+    let settings = "backend = \"netlink\"\nmax_resubscribes = 2";
+    let directory = directory("disconnects", settings);
+    let output = daemon(&bus, &directory, "disconnect*3", false).await;
+    assert!(!output.status.success(), "{}", log(&output));
+    let resubscribes = log(&output).matches(", re-subscribing").count();
+    assert_eq!(2, resubscribes, "{}", log(&output));
+    assert!(
+        log(&output).contains("Giving up after re-subscribing 2 times"),
+        "{}",
+        log(&output)
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+/// Whether there's a default route, which the netlink backend reports as a
+/// connection.
+#[cfg(feature = "backend-netlink")]
+fn has_default_route() -> bool {
+    fs::read_to_string("/proc/net/route").is_ok_and(|routes| {
+        routes
+            .lines()
+            .skip(1)
+            .any(|route| Some("00000000") == route.split_whitespace().nth(1))
+    })
+}
+
+#[cfg(feature = "backend-netlink")]
+#[tokio::test]
+async fn the_backend_is_resubscribed_to_after_a_disconnect() {
+    if !has_default_route() {
+        return;
+    }
+    let bus = Bus::start("UTC").await;
+    let directory = directory("disconnect", "backend = \"netlink\"");
+    let mut daemon = command(&bus, &directory, "disconnect")
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // The connection that's already up is only seen once it's re-subscribed.
+    let updated = tokio::time::timeout(Duration::from_secs(20), async {
+        while bus.calls().is_empty() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await;
+    assert!(updated.is_ok(), "The daemon didn't update the timezone");
+    assert!(daemon.try_wait().unwrap().is_none());
+    assert_eq!(vec!["Europe/Berlin"], bus.calls());
+    daemon.kill().await.unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+///////////////////////////////////////////////////////////////////////////////
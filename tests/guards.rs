@@ -0,0 +1,279 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            guards.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Tests that each guard on a change stops it for its own reason,
+//                  and says so.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+#![cfg(all(feature = "backend-netlink", feature = "provider-file"))]
+
+mod common;
+
+use common::{
+    ask, directory, guards, provider, recent_detections, scratch, set, socket,
+    Bus, Daemon,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A test's directories, with the daemon configured there for `backend`,
+/// detecting Europe/Paris until told otherwise.
+fn configure(name: &str, backend: &str, settings: &str) -> (PathBuf, PathBuf) {
+    let scratch = scratch(name);
+    let settings =
+        format!("{}\n{}", settings, provider(&scratch, "Europe/Paris"));
+    (directory(name, backend, &settings), scratch)
+}
+
+/// Wait for the daemon to skip an update, and return the reason it gives
+/// in its status.
+async fn skipped(daemon: &Daemon, directory: &Path) -> String {
+    daemon
+        .wait_for("Skipping update: ", Duration::from_secs(10))
+        .await;
+    let reply = ask(&socket(directory), "status").await;
+    let reason = reply["status"]["last_skip"].as_str();
+    reason.unwrap_or_else(|| panic!("{}", reply)).to_string()
+}
+
+/// Stop the daemon, and check it logged `reason` as it skipped the update.
+async fn finish(
+    daemon: Daemon,
+    reason: &str,
+    directories: (PathBuf, PathBuf),
+) {
+    let (_, log) = daemon.terminate().await;
+    let logged = format!("Skipping update: {}", reason);
+    assert!(log.contains(&logged), "{}", log);
+    fs::remove_dir_all(&directories.0).unwrap();
+    fs::remove_dir_all(&directories.1).unwrap();
+}
+
+#[tokio::test]
+async fn a_timezone_not_allowed_is_skipped_as_not_allowed() {
+    let bus = Bus::start("UTC").await;
+    let settings = "allowed_timezones = [\"Europe/Berlin\"]";
+    let (directory, scratch) = configure("allowed", "netlink", settings);
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    let reason = skipped(&daemon, &directory).await;
+    assert_eq!("reason=not_allowed timezone=Europe/Paris", reason);
+    assert!(bus.calls().is_empty());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[tokio::test]
+async fn a_change_soon_after_the_last_is_skipped_for_dwell() {
+    let bus = Bus::start("UTC").await;
+    let (directory, scratch) = configure("dwell", "netlink", "");
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    set(&bus, &daemon, 1).await;
+    fs::write(scratch.join("timezone"), "Europe/Berlin").unwrap();
+    ask(&socket(&directory), "update").await;
+    let reason = skipped(&daemon, &directory).await;
+    assert!(reason.starts_with("reason=dwell remaining="), "{}", reason);
+    assert_eq!(vec!["Europe/Paris"], bus.calls());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[tokio::test]
+async fn a_change_past_the_daily_cap_is_skipped_as_capped() {
+    let bus = Bus::start("UTC").await;
+    let settings = "max_changes_per_day = 1\nmin_dwell = \"0s\"";
+    let (directory, scratch) = configure("cap", "netlink", settings);
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    set(&bus, &daemon, 1).await;
+    fs::write(scratch.join("timezone"), "Europe/Berlin").unwrap();
+    ask(&socket(&directory), "update").await;
+    let reason = skipped(&daemon, &directory).await;
+    assert_eq!("reason=capped", reason);
+    assert_eq!(vec!["Europe/Paris"], bus.calls());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[tokio::test]
+async fn a_change_from_an_inactive_session_is_skipped() {
+    let bus = Bus::start("UTC").await;
+    bus.logind(false).await;
+    let settings = "require_active_session = true";
+    let (directory, scratch) = configure("session", "netlink", settings);
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    let reason = skipped(&daemon, &directory).await;
+    assert_eq!("reason=inactive_session", reason);
+    assert!(bus.calls().is_empty());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[tokio::test]
+async fn a_change_no_one_confirms_is_skipped_as_declined() {
+    let bus = Bus::start("UTC").await;
+    let (directory, scratch) = configure("confirm", "netlink", "");
+    let arguments = "--confirm --confirm-timeout 1s";
+    let daemon = Daemon::on_terminal(&bus, &directory, arguments).await;
+
+    ask(&socket(&directory), "update --force").await;
+    let reason = skipped(&daemon, &directory).await;
+    assert_eq!("reason=declined", reason);
+    assert!(daemon
+        .log()
+        .contains("Detected Europe/Paris (currently UTC)"));
+    assert!(bus.calls().is_empty());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[tokio::test]
+async fn the_timezone_already_set_is_skipped_as_unchanged() {
+    let bus = Bus::start("Europe/Paris").await;
+    let (directory, scratch) = configure("unchanged", "netlink", "");
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    let reason = skipped(&daemon, &directory).await;
+    assert_eq!("reason=unchanged", reason);
+    assert!(bus.calls().is_empty());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[cfg(feature = "backend-wpa-supplicant")]
+#[tokio::test]
+async fn a_timezone_from_another_country_is_skipped_as_a_mismatch() {
+    let bus = Bus::start("UTC").await;
+    let wpa_supplicant = bus.wpa_supplicant("DE").await;
+    let settings = "reject_country_mismatch = true";
+    let (directory, scratch) =
+        configure("country", "wpa_supplicant", settings);
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    // Connecting sets off the update, once the daemon is watching for it.
+    let connected = tokio::time::timeout(Duration::from_secs(10), async {
+        while !daemon.log().contains("Skipping update: ") {
+            wpa_supplicant.completed();
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    })
+    .await;
+    assert!(connected.is_ok(), "{}", daemon.log());
+    let reason = skipped(&daemon, &directory).await;
+    assert_eq!(
+        "reason=country_mismatch timezone=Europe/Paris country=DE",
+        reason
+    );
+    assert!(bus.calls().is_empty());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[cfg(feature = "provider-exec")]
+#[tokio::test]
+async fn a_result_landing_after_a_later_one_is_skipped_as_stale() {
+    let bus = Bus::start("UTC").await;
+    let name = "stale";
+    let scratch = scratch(name);
+    fs::create_dir_all(&scratch).unwrap();
+    // A provider that gives each update the next of these.
+    let answers = scratch.join("answers");
+    fs::write(&answers, "Europe/Berlin\nEurope/Paris\n").unwrap();
+    let settings = format!(
+        "apply_delay = \"3s\"\n[[providers]]\ntype = \"exec\"\nshell = true\n\
+         command = [\"head -n 1 {0}; sed -i 1d {0}\"]\n",
+        answers.display()
+    );
+    let directory = directory(name, "netlink", &settings);
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    // The first update's result waits out apply_delay, and meanwhile a
+    // forced one lands and is applied straight away.
+    ask(&socket(&directory), "update").await;
+    daemon
+        .wait_for(
+            "Setting timezone to Europe/Berlin in",
+            Duration::from_secs(10),
+        )
+        .await;
+    ask(&socket(&directory), "update --force").await;
+    set(&bus, &daemon, 1).await;
+    let reason = skipped(&daemon, &directory).await;
+    assert_eq!("reason=stale", reason);
+    let detections = recent_detections(&bus).await;
+    assert_eq!(2, detections.len(), "{}", daemon.log());
+    assert!(guards(&detections[0]).contains(&"stale=block".to_string()));
+    assert_eq!(vec!["Europe/Paris"], bus.calls());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+#[tokio::test]
+async fn guards_are_reported_as_the_update_checks_them() {
+    let bus = Bus::start("UTC").await;
+    let settings = "allowed_timezones = [\"Europe/Paris\"]";
+    let (directory, scratch) = configure("guards", "netlink", settings);
+    let daemon = Daemon::start(&bus, &directory).await;
+
+    ask(&socket(&directory), "update --force").await;
+    set(&bus, &daemon, 1).await;
+    // Berlin isn't allowed, and it's too soon after the change to Paris.
+    fs::write(scratch.join("timezone"), "Europe/Berlin").unwrap();
+    ask(&socket(&directory), "update").await;
+    let reason = skipped(&daemon, &directory).await;
+    let detections = recent_detections(&bus).await;
+    assert_eq!(2, detections.len(), "{}", daemon.log());
+    // Forced, so quiet_hours weren't looked at.
+    assert_eq!(
+        vec![
+            "country=pass",
+            "allowed=pass",
+            "dwell=pass",
+            "stale=pass",
+            "session=pass",
+            "cap=pass",
+            "unchanged=pass"
+        ],
+        guards(&detections[0])
+    );
+    // Both the guards in the way are reported, and nothing past them is
+    // checked. The first decides.
+    assert_eq!(
+        vec!["country=pass", "allowed=block", "dwell=block"],
+        guards(&detections[1])
+    );
+    assert_eq!("reason=not_allowed timezone=Europe/Berlin", reason);
+    assert_eq!(vec!["Europe/Paris"], bus.calls());
+    finish(daemon, &reason, (directory, scratch)).await;
+}
+
+///////////////////////////////////////////////////////////////////////////////
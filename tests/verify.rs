@@ -0,0 +1,113 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            verify.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Tests of the verify subcommand against a fake timedated, and without one.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+#![cfg(feature = "provider-file")]
+
+mod common;
+
+use common::{provider, scratch, Bus};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::Duration;
+
+/// A configuration for the test `name`, whose provider says `timezone`.
+fn config(name: &str, timezone: &str, settings: &str) -> PathBuf {
+    let directory = scratch(name);
+    let _ = fs::remove_dir_all(&directory);
+    let config = format!(
+        "state_directory = \"{}\"\njitter = 0.0\n{}\n{}",
+        directory.join("state").display(),
+        settings,
+        provider(&directory, timezone)
+    );
+    fs::write(directory.join("config.toml"), config).unwrap();
+    directory.join("config.toml")
+}
+
+async fn verify(mut command: tokio::process::Command) -> Output {
+    let run = command.arg("verify").output();
+    tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("verify didn't exit")
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[tokio::test]
+async fn an_alias_of_the_system_zone_matches() {
+    let bus = Bus::start("Asia/Calcutta").await;
+    let config = config("verify-alias", "Asia/Kolkata", "");
+    let output = verify(common::command(&bus, &config)).await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).starts_with("OK "), "{}", stdout(&output));
+    fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn the_zone_is_read_from_localtime_without_timedated() {
+    let link = fs::read_link("/etc/localtime")
+        .expect("This test needs /etc/localtime to be a link");
+    let zone = link
+        .strip_prefix("/usr/share/zoneinfo")
+        .or_else(|_| link.strip_prefix("../usr/share/zoneinfo"))
+        .map(Path::to_string_lossy)
+        .expect("This test needs /etc/localtime to link into zoneinfo")
+        .into_owned();
+    let config = config(
+        "verify-localtime",
+        &zone,
+        "without_timedated = \"localtime\"",
+    );
+    let mut command =
+        tokio::process::Command::new(env!("CARGO_BIN_EXE_iwd-auto-timezone"));
+    command
+        .arg("--config")
+        .arg(&config)
+        .env("DBUS_SYSTEM_BUS_ADDRESS", "unix:path=/nonexistent")
+        .kill_on_drop(true);
+    let output = verify(command).await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert_eq!(
+        format!("OK current={} detected={}\n", zone, zone),
+        stdout(&output)
+    );
+    fs::remove_dir_all(config.parent().unwrap()).unwrap();
+}
+
+///////////////////////////////////////////////////////////////////////////////
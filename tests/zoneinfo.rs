@@ -0,0 +1,75 @@
+///////////////////////////////////////////////////////////////////////////////
+// NAME:            zoneinfo.rs
+//
+// AUTHOR:          Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// DESCRIPTION:     Tests that a Detector reads the tz database it's configured with.
+//                  In a crate of their own, as the directory is set once a process.
+//
+// CREATED:         10/14/2026
+//
+// LAST EDITED:	    10/14/2026
+//
+////
+// Copyright 2026, Ethan D. Twardy
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to
+// deal in the Software without restriction, including without limitation the
+// rights to use, copy, modify, merge, publish, distribute, sublicense, and/or
+// sell copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS
+// IN THE SOFTWARE.
+////
+
+// The file banners are not doc comments.
+#![allow(clippy::four_forward_slashes)]
+#![cfg(feature = "provider-file")]
+
+use iwd_auto_timezone::{Config, Detector};
+use std::fs;
+
+#[tokio::test]
+async fn the_detector_reads_the_configured_tz_database() {
+    let directory =
+        std::env::temp_dir().join(format!("zoneinfo-{}", std::process::id()));
+    let zoneinfo = directory.join("zoneinfo");
+    fs::create_dir_all(zoneinfo.join("Europe")).unwrap();
+    // A database with only Berlin in it.
+    fs::copy(
+        "/usr/share/zoneinfo/Europe/Berlin",
+        zoneinfo.join("Europe/Berlin"),
+    )
+    .unwrap();
+    let path = directory.join("timezone");
+    fs::write(&path, "Europe/Paris").unwrap();
+    let toml = format!(
+        "[[providers]]\ntype = \"file\"\npath = \"{}\"\n",
+        path.display()
+    );
+    let mut config: Config = toml::from_str(&toml).unwrap();
+    config.zoneinfo_directory = zoneinfo;
+
+    let mut detector = Detector::from_config(&config).unwrap();
+    let error = detector.detect().await.unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("Europe/Paris"),
+        "{:#}",
+        error
+    );
+    fs::write(&path, "Europe/Berlin").unwrap();
+    assert_eq!("Europe/Berlin", detector.detect().await.unwrap().timezone);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+///////////////////////////////////////////////////////////////////////////////